@@ -0,0 +1,123 @@
+//! Compares the table and cached engines in MIPS (millions of instructions
+//! per second) on Klaus Dormann's 6502 functional test, taken from
+//! $DORMANN_TEST or roms/6502_functional_test.bin. Without it a small
+//! built-in loop is timed instead. The built-in loop is also timed from a
+//! mapped ROM, the way the system monitors run, where the table engine goes
+//! through the device for every code read.
+//! Run with `cargo bench --bench engines`.
+
+use cpu6502::cpu::{Cpu, Engine};
+use std::{
+    env, fs,
+    hint::black_box,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+const INSTRUCTIONS: u64 = 5_000_000;
+const WARMUP: usize = 2;
+const SAMPLES: usize = 10;
+
+/// LDX #0; loop: LDA $0200,X; ADC #3; STA $0300,X; EOR ($10),Y; INX;
+/// BNE loop; INY; JMP $0400
+const FALLBACK: [u8; 19] = [
+    0xA2, 0x00, 0xBD, 0x00, 0x02, 0x69, 0x03, 0x9D, 0x00, 0x03, 0x51, 0x10, 0xE8, 0xD0, 0xF3, 0xC8,
+    0x4C, 0x00, 0x04,
+];
+
+/// 64K image and where to start it
+fn workload() -> (String, Vec<u8>) {
+    let path = env::var_os("DORMANN_TEST")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("roms/6502_functional_test.bin")
+        });
+    match fs::read(&path) {
+        Ok(image) => (path.display().to_string(), image),
+        Err(_) => {
+            let mut image = vec![0; 0x10000];
+            image[0x0400..0x0400 + FALLBACK.len()].copy_from_slice(&FALLBACK);
+            (
+                format!("built-in loop ({} not found)", path.display()),
+                image,
+            )
+        }
+    }
+}
+
+/// Where the code being timed lives
+#[derive(Clone, Copy)]
+enum Workload<'a> {
+    /// 64K image loaded into RAM
+    Ram(&'a [u8]),
+    /// Code mapped as a ROM device at $0400
+    Rom(&'a [u8]),
+}
+
+/// Runs up to `INSTRUCTIONS`, stopping early if the test traps (jumps or
+/// branches to itself). Returns the instructions run and the time taken.
+fn sample(engine: Engine, workload: Workload) -> (u64, Duration) {
+    let mut cpu = Cpu::new();
+    cpu.engine = engine;
+    match workload {
+        Workload::Ram(image) => cpu.memory.load(0, image),
+        Workload::Rom(code) => {
+            cpu.memory.map_rom(0x0400, code);
+        }
+    }
+    cpu.pc = 0x0400;
+
+    let start = Instant::now();
+    let mut count = 0;
+    while count < INSTRUCTIONS {
+        let pc = cpu.pc;
+        cpu.step();
+        count += 1;
+        if cpu.pc == pc {
+            break;
+        }
+    }
+    let elapsed = start.elapsed();
+    black_box(&cpu);
+    (count, elapsed)
+}
+
+/// Samples the engines in turn so drift in the machine's speed hits them all
+fn bench(workload: Workload) {
+    let engines = [("table", Engine::Table), ("cached", Engine::Cached)];
+    for _ in 0..WARMUP {
+        for (_, engine) in engines {
+            sample(engine, workload);
+        }
+    }
+
+    let mut mips = [Vec::new(), Vec::new()];
+    for _ in 0..SAMPLES {
+        for (i, (_, engine)) in engines.iter().enumerate() {
+            let (count, elapsed) = sample(*engine, workload);
+            mips[i].push(count as f64 / elapsed.as_secs_f64() / 1e6);
+        }
+    }
+
+    let mut medians = [0.0; 2];
+    for (i, (name, _)) in engines.iter().enumerate() {
+        let mips = &mut mips[i];
+        mips.sort_by(|a, b| a.total_cmp(b));
+        medians[i] = mips[SAMPLES / 2];
+        println!(
+            "{name:<6} median {:>7.2} MIPS  [{:.2} .. {:.2}]",
+            medians[i],
+            mips[0],
+            mips[SAMPLES - 1]
+        );
+    }
+    println!("cached / table: {:.2}x", medians[1] / medians[0]);
+}
+
+fn main() {
+    let (name, image) = workload();
+    println!("{name}");
+    bench(Workload::Ram(&image));
+    println!("built-in loop in a mapped ROM");
+    bench(Workload::Rom(&FALLBACK));
+}
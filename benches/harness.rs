@@ -0,0 +1,144 @@
+//! Times what the op_tests harness does per test case: build a `Cpu`, load
+//! its registers and RAM, run one instruction and check the result.
+//! The baseline adds back what every `Cpu` used to set up for itself: its
+//! own decode table and a zeroed 64K buffer copied into base RAM.
+//! Run with `cargo bench --bench harness`.
+
+use cpu6502::{cpu::Cpu, instructions::Instruction, table::INSTRUCTIONS};
+use serde::Deserialize;
+use std::{
+    fs,
+    hint::black_box,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+#[derive(Deserialize)]
+struct State {
+    pc: u16,
+    s: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    p: u8,
+    ram: Vec<(u16, u8)>,
+}
+
+#[derive(Deserialize)]
+struct TestCase {
+    initial: State,
+    #[serde(rename = "final")]
+    final_state: State,
+}
+
+/// Files loaded from op_tests, enough for a stable figure without
+/// spending most of the run parsing JSON
+const FILES: usize = 8;
+const CONSTRUCTIONS: u32 = 100_000;
+
+fn load_cases() -> Vec<TestCase> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("op_tests");
+    let mut paths: Vec<_> = fs::read_dir(&dir)
+        .expect("op_tests must exist")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .take(FILES)
+        .flat_map(|path| {
+            let raw = fs::read_to_string(path).unwrap();
+            let raw = raw.trim();
+            let data = if raw.starts_with('[') {
+                raw.to_string()
+            } else {
+                format!("[{}]", raw.trim_end_matches(','))
+            };
+            serde_json::from_str::<Vec<TestCase>>(&data).unwrap()
+        })
+        .collect()
+}
+
+/// The per instance setup `Cpu::new` did before the decode table was
+/// shared and base RAM was zeroed in place
+fn old_setup() -> ([Instruction; 256], Vec<u8>) {
+    let table = std::array::from_fn(|opcode| black_box(INSTRUCTIONS[opcode]));
+    let zeroed = black_box(vec![0; 0x10000]);
+    let mut storage = Vec::new();
+    storage.extend_from_slice(&zeroed);
+    (table, storage)
+}
+
+fn run_case(case: &TestCase, baseline: bool) -> bool {
+    if baseline {
+        black_box(old_setup());
+    }
+    let mut cpu = Cpu::new();
+    cpu.pc = case.initial.pc;
+    cpu.sp = case.initial.s;
+    cpu.a = case.initial.a;
+    cpu.x = case.initial.x;
+    cpu.y = case.initial.y;
+    cpu.status = case.initial.p;
+    for &(addr, value) in &case.initial.ram {
+        cpu.memory[addr as usize] = value;
+    }
+
+    cpu.step();
+
+    let end = &case.final_state;
+    (cpu.pc, cpu.sp, cpu.a, cpu.x, cpu.y) == (end.pc, end.s, end.a, end.x, end.y)
+        && end
+            .ram
+            .iter()
+            .all(|&(addr, value)| cpu.memory[addr as usize] == value)
+}
+
+fn report(name: &str, elapsed: Duration, count: usize) {
+    println!(
+        "{name:<24} {count:>8} in {:>8.2?}  ({:.0} ns each)",
+        elapsed,
+        elapsed.as_nanos() as f64 / count as f64
+    );
+}
+
+/// Times `run` with and without the old setup, returns the speed-up
+fn compare(name: &str, count: usize, mut run: impl FnMut(bool)) -> f64 {
+    let mut times = [Duration::ZERO; 2];
+    for (baseline, time) in [true, false].into_iter().zip(&mut times) {
+        let start = Instant::now();
+        run(baseline);
+        *time = start.elapsed();
+        let label = if baseline {
+            format!("{name} (baseline)")
+        } else {
+            name.to_string()
+        };
+        report(&label, *time, count);
+    }
+    times[0].as_secs_f64() / times[1].as_secs_f64()
+}
+
+fn main() {
+    let speedup = compare("Cpu::new", CONSTRUCTIONS as usize, |baseline| {
+        for _ in 0..CONSTRUCTIONS {
+            if baseline {
+                black_box(old_setup());
+            }
+            black_box(Cpu::new());
+        }
+    });
+    println!("{:.2}x faster\n", speedup);
+
+    let cases = load_cases();
+    let speedup = compare("op_tests cases", cases.len(), |baseline| {
+        let passed = cases
+            .iter()
+            .filter(|case| run_case(black_box(case), baseline))
+            .count();
+        black_box(passed);
+    });
+    println!("{:.2}x faster", speedup);
+}
@@ -0,0 +1,308 @@
+use crate::opcodes::{AddrMode, Variant, OPCODES};
+use std::{collections::HashMap, error::Error, fmt};
+
+/// Why a line didn't assemble, `line` counts from 1
+#[derive(Debug, PartialEq, Eq)]
+pub enum AsmError {
+    /// No opcode has this mnemonic with this kind of operand
+    UnknownInstruction {
+        line: usize,
+        text: String,
+    },
+    BadOperand {
+        line: usize,
+        text: String,
+    },
+    UnknownLabel {
+        line: usize,
+        label: String,
+    },
+    DuplicateLabel {
+        line: usize,
+        label: String,
+    },
+    /// Branches reach 128 bytes back and 127 forward
+    BranchOutOfRange {
+        line: usize,
+        target: u16,
+    },
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AsmError::UnknownInstruction { line, text } => {
+                write!(f, "line {}: no such instruction `{}`", line, text)
+            }
+            AsmError::BadOperand { line, text } => {
+                write!(f, "line {}: can't read operand `{}`", line, text)
+            }
+            AsmError::UnknownLabel { line, label } => {
+                write!(f, "line {}: label `{}` is never defined", line, label)
+            }
+            AsmError::DuplicateLabel { line, label } => {
+                write!(f, "line {}: label `{}` is already defined", line, label)
+            }
+            AsmError::BranchOutOfRange { line, target } => {
+                write!(f, "line {}: ${:04X} is out of branch range", line, target)
+            }
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Clone, Debug)]
+enum Value {
+    /// `wide` when written with more than two hex digits, which keeps it
+    /// out of zero page
+    Number {
+        value: u16,
+        wide: bool,
+    },
+    Label(String),
+}
+
+/// How the operand was written, before picking an addressing mode
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Syntax {
+    None,
+    Accumulator,
+    Immediate,
+    Direct,
+    DirectX,
+    DirectY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+}
+
+enum Item {
+    Instruction { opcode: u8, operand: Option<Value> },
+    Bytes(Vec<Value>),
+    Words(Vec<Value>),
+}
+
+/// Assembles `source` to be loaded at `origin`.
+///
+/// One statement per line, `;` starts a comment and `name:` defines a
+/// label. Operands are written the usual way (`#$10`, `$10,X`, `($10),Y`,
+/// `A`...) with `$` hex, `%` binary, decimal or label values. Numbers below
+/// $100 pick zero page unless written with more than two hex digits, and
+/// labels only do when they're defined further up. `.byte` and `.word`
+/// take comma separated values.
+pub fn assemble(source: &str, origin: u16) -> Result<Vec<u8>, AsmError> {
+    assemble_for(source, origin, Variant::Nmos6502)
+}
+
+/// Like `assemble`, only taking the opcodes `variant` has
+pub fn assemble_for(source: &str, origin: u16, variant: Variant) -> Result<Vec<u8>, AsmError> {
+    let mut labels = HashMap::new();
+    let mut items = Vec::new();
+    let mut pc = origin;
+
+    // First pass: pick every opcode and place the labels
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut text = text.split(';').next().unwrap_or("").trim();
+
+        if let Some((label, rest)) = text.split_once(':') {
+            let label = label.trim();
+            if labels.insert(label.to_string(), pc).is_some() {
+                return Err(AsmError::DuplicateLabel {
+                    line,
+                    label: label.to_string(),
+                });
+            }
+            text = rest.trim();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let (word, rest) = match text.split_once(char::is_whitespace) {
+            Some((word, rest)) => (word, rest.trim()),
+            None => (text, ""),
+        };
+        let item = match word.to_lowercase().as_str() {
+            ".byte" => Item::Bytes(values(rest, line)?),
+            ".word" => Item::Words(values(rest, line)?),
+            mnemonic => {
+                let (syntax, operand) = operand(rest, line)?;
+                let zero_page = match &operand {
+                    Some(Value::Number { value, wide }) => *value < 0x100 && !wide,
+                    Some(Value::Label(label)) => labels.get(label).is_some_and(|&v| v < 0x100),
+                    None => false,
+                };
+                let opcode = pick(mnemonic, syntax, zero_page, variant).ok_or(
+                    AsmError::UnknownInstruction {
+                        line,
+                        text: text.to_string(),
+                    },
+                )?;
+                Item::Instruction { opcode, operand }
+            }
+        };
+
+        let size = match &item {
+            Item::Instruction { opcode, .. } => OPCODES[*opcode as usize].bytes as u16,
+            Item::Bytes(values) => values.len() as u16,
+            Item::Words(values) => values.len() as u16 * 2,
+        };
+        items.push((line, pc, item));
+        pc = pc.wrapping_add(size);
+    }
+
+    // Second pass: fill in the operands
+    let mut code = Vec::new();
+    for (line, pc, item) in items {
+        let resolve = |value: &Value| match value {
+            Value::Number { value, .. } => Ok(*value),
+            Value::Label(label) => labels.get(label).copied().ok_or(AsmError::UnknownLabel {
+                line,
+                label: label.clone(),
+            }),
+        };
+
+        match item {
+            Item::Instruction { opcode, operand } => {
+                let spec = &OPCODES[opcode as usize];
+                let value = match &operand {
+                    Some(value) => resolve(value)?,
+                    None => 0,
+                };
+                code.push(opcode);
+                match spec.mode {
+                    AddrMode::Relative => {
+                        let offset = value.wrapping_sub(pc.wrapping_add(2)) as i16;
+                        if !(-128..=127).contains(&offset) {
+                            return Err(AsmError::BranchOutOfRange {
+                                line,
+                                target: value,
+                            });
+                        }
+                        code.push(offset as u8);
+                    }
+                    _ => code.extend_from_slice(&value.to_le_bytes()[..spec.bytes as usize - 1]),
+                }
+            }
+            Item::Bytes(values) => {
+                for value in &values {
+                    code.push(resolve(value)? as u8);
+                }
+            }
+            Item::Words(values) => {
+                for value in &values {
+                    code.extend_from_slice(&resolve(value)?.to_le_bytes());
+                }
+            }
+        }
+    }
+    Ok(code)
+}
+
+/// Finds the opcode for `mnemonic` written with `syntax`, preferring the
+/// documented one and skipping those `variant` doesn't have
+fn pick(mnemonic: &str, syntax: Syntax, zero_page: bool, variant: Variant) -> Option<u8> {
+    let modes: &[AddrMode] = match syntax {
+        Syntax::None => &[AddrMode::Implied, AddrMode::Accumulator],
+        Syntax::Accumulator => &[AddrMode::Accumulator],
+        Syntax::Immediate => &[AddrMode::Immediate],
+        Syntax::Direct if zero_page => {
+            &[AddrMode::Relative, AddrMode::ZeroPage, AddrMode::Absolute]
+        }
+        Syntax::Direct => &[AddrMode::Relative, AddrMode::Absolute],
+        Syntax::DirectX if zero_page => &[AddrMode::ZeroPageX, AddrMode::AbsoluteX],
+        Syntax::DirectX => &[AddrMode::AbsoluteX],
+        Syntax::DirectY if zero_page => &[AddrMode::ZeroPageY, AddrMode::AbsoluteY],
+        Syntax::DirectY => &[AddrMode::AbsoluteY],
+        Syntax::Indirect => &[AddrMode::Indirect],
+        Syntax::IndirectX => &[AddrMode::IndirectX],
+        Syntax::IndirectY => &[AddrMode::IndirectY],
+    };
+
+    let found = modes.iter().find_map(|&mode| {
+        let mut matching = OPCODES
+            .iter()
+            .filter(|spec| spec.mnemonic == mnemonic && spec.mode == mode)
+            .filter(|spec| spec.available(variant));
+        let first = matching.clone().next()?;
+        let spec = matching.find(|spec| spec.documented()).unwrap_or(first);
+        Some(spec.opcode)
+    });
+    // BRK's padding byte can be given like an immediate
+    found.or_else(|| (syntax == Syntax::Immediate && mnemonic == "brk").then_some(0x00))
+}
+
+fn operand(text: &str, line: usize) -> Result<(Syntax, Option<Value>), AsmError> {
+    let text = text.replace(char::is_whitespace, "");
+    let upper = text.to_uppercase();
+
+    if text.is_empty() {
+        return Ok((Syntax::None, None));
+    }
+    if upper == "A" {
+        return Ok((Syntax::Accumulator, None));
+    }
+
+    let (syntax, value) = if let Some(value) = text.strip_prefix('#') {
+        (Syntax::Immediate, value)
+    } else if let Some(inner) = text.strip_prefix('(') {
+        if upper.ends_with(",X)") {
+            (Syntax::IndirectX, &inner[..inner.len() - 3])
+        } else if upper.ends_with("),Y") {
+            (Syntax::IndirectY, &inner[..inner.len() - 3])
+        } else if let Some(value) = inner.strip_suffix(')') {
+            (Syntax::Indirect, value)
+        } else {
+            return Err(AsmError::BadOperand { line, text });
+        }
+    } else if upper.ends_with(",X") {
+        (Syntax::DirectX, &text[..text.len() - 2])
+    } else if upper.ends_with(",Y") {
+        (Syntax::DirectY, &text[..text.len() - 2])
+    } else {
+        (Syntax::Direct, text.as_str())
+    };
+
+    let value = parse_value(value).ok_or_else(|| AsmError::BadOperand {
+        line,
+        text: text.clone(),
+    })?;
+    Ok((syntax, Some(value)))
+}
+
+fn values(text: &str, line: usize) -> Result<Vec<Value>, AsmError> {
+    text.split(',')
+        .map(|text| {
+            parse_value(text.trim()).ok_or(AsmError::BadOperand {
+                line,
+                text: text.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    let number = |digits: &str, radix| u16::from_str_radix(digits, radix).ok();
+    if let Some(digits) = text.strip_prefix('$') {
+        Some(Value::Number {
+            value: number(digits, 16)?,
+            wide: digits.len() > 2,
+        })
+    } else if let Some(digits) = text.strip_prefix('%') {
+        Some(Value::Number {
+            value: number(digits, 2)?,
+            wide: false,
+        })
+    } else if text.starts_with(|c: char| c.is_ascii_digit()) {
+        Some(Value::Number {
+            value: number(text, 10)?,
+            wide: false,
+        })
+    } else if !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some(Value::Label(text.to_string()))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,347 @@
+use crate::{rewind::Remap, uninit::PowerOn};
+use std::{cell::RefCell, rc::Rc};
+
+pub type BankId = usize;
+
+/// Bank 0 is always the 64KB of base RAM, identity mapped at power-up
+pub const BASE_RAM: BankId = 0;
+
+/// Page offset marking a page whose writes are ignored
+const NO_WRITE: usize = usize::MAX;
+
+#[derive(Clone, Copy)]
+struct Bank {
+    /// Offset of the bank's first byte in `storage`
+    offset: usize,
+    /// Size in pages
+    pages: usize,
+    writable: bool,
+}
+
+/// Page-granular (256 byte) memory banking.
+/// Every bank (RAM or ROM) lives back to back in one buffer and each CPU
+/// page points at an offset into it, separately for reads and writes, so a
+/// lookup is a table index plus an add no matter how the banks are arranged.
+pub struct Banks {
+    storage: Vec<u8>,
+    banks: Vec<Bank>,
+    /// Per CPU page: offset into `storage` that reads come from
+    read_page: [usize; 256],
+    /// Per CPU page: offset into `storage` that writes go to, or `NO_WRITE`
+    write_page: [usize; 256],
+    /// Per storage page: writes it has taken, so code caches can tell when
+    /// what they decoded changed
+    writes: Vec<u32>,
+    /// Per storage page: a code cache decoded from it, see `watch`
+    watched: Vec<bool>,
+    /// Bumped by every write to a watched page and every read remapping
+    epoch: u32,
+    /// While an instruction is being recorded for `Cpu::step_back`: the
+    /// mapping of every page it remapped, from before
+    remap_log: Option<Vec<Remap>>,
+}
+
+/// Reacts to writes on control registers by remapping banks,
+/// e.g. the C64 processor port or an NES mapper register
+pub trait BankSwitch {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8);
+
+    /// Reads of the control range, `None` falls through to the banked memory
+    fn read(&mut self, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    /// Whether `read` ever answers. Code caches only keep code from the
+    /// control range of switches that don't.
+    fn answers_reads(&self) -> bool {
+        false
+    }
+}
+
+impl<F: FnMut(&mut Banks, u16, u8)> BankSwitch for F {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8) {
+        self(banks, addr, value)
+    }
+}
+
+/// Lets a controller stay reachable from outside the bus
+impl<T: BankSwitch> BankSwitch for Rc<RefCell<T>> {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8) {
+        self.borrow_mut().write(banks, addr, value)
+    }
+
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        self.borrow_mut().read(addr)
+    }
+
+    fn answers_reads(&self) -> bool {
+        self.borrow().answers_reads()
+    }
+}
+
+impl Banks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn read(&self, addr: u16) -> u8 {
+        self.storage[self.read_page[(addr >> 8) as usize] + (addr & 0xFF) as usize]
+    }
+
+    #[inline]
+    pub fn write(&mut self, addr: u16, value: u8) {
+        let base = self.write_page[(addr >> 8) as usize];
+        if base != NO_WRITE {
+            self.storage[base + (addr & 0xFF) as usize] = value;
+            self.count_write(base >> 8);
+        }
+    }
+
+    /// Adds a zero-filled RAM bank of `size` bytes (rounded up to whole pages)
+    pub fn add_ram(&mut self, size: usize) -> BankId {
+        self.add_bank(size, true, |_| {})
+    }
+
+    /// Adds a ROM bank, writes mapped onto it are ignored
+    pub fn add_rom(&mut self, data: &[u8]) -> BankId {
+        self.add_bank(data.len(), false, |bank| {
+            bank[..data.len()].copy_from_slice(data)
+        })
+    }
+
+    /// Adds a zero-filled bank and lets `fill` load it. Zeroing in place
+    /// (rather than copying a zeroed buffer in) keeps the 64K of base RAM
+    /// cheap to set up, which matters when a `Cpu` is built per test case.
+    fn add_bank(&mut self, size: usize, writable: bool, fill: impl FnOnce(&mut [u8])) -> BankId {
+        let pages = size.div_ceil(0x100);
+        let offset = self.storage.len();
+
+        if self.storage.is_empty() {
+            self.storage = vec![0; pages * 0x100];
+        } else {
+            self.storage.resize(offset + pages * 0x100, 0);
+        }
+        fill(&mut self.storage[offset..]);
+        self.writes.resize(self.storage.len() >> 8, 0);
+        self.watched.resize(self.storage.len() >> 8, false);
+        self.banks.push(Bank {
+            offset,
+            pages,
+            writable,
+        });
+
+        self.banks.len() - 1
+    }
+
+    /// Maps `count` pages of `bank` (starting at its page `bank_page`) for
+    /// both reads and writes, starting at CPU page `first_page`
+    pub fn map(&mut self, first_page: u8, count: usize, bank: BankId, bank_page: usize) {
+        self.map_read(first_page, count, bank, bank_page);
+        self.map_write(first_page, count, bank, bank_page);
+    }
+
+    /// Like `map`, for reads only
+    pub fn map_read(&mut self, first_page: u8, count: usize, bank: BankId, bank_page: usize) {
+        for (i, page) in self.pages(first_page, count, bank, bank_page) {
+            self.log_remap(page);
+            self.read_page[page] = self.page_offset(bank, bank_page + i);
+        }
+        self.epoch = self.epoch.wrapping_add(1);
+    }
+
+    /// Like `map`, for writes only. Writes to a ROM bank are ignored.
+    pub fn map_write(&mut self, first_page: u8, count: usize, bank: BankId, bank_page: usize) {
+        for (i, page) in self.pages(first_page, count, bank, bank_page) {
+            self.log_remap(page);
+            self.write_page[page] = if self.banks[bank].writable {
+                self.page_offset(bank, bank_page + i)
+            } else {
+                NO_WRITE
+            };
+        }
+    }
+
+    fn log_remap(&mut self, page: usize) {
+        if let Some(log) = &mut self.remap_log {
+            log.push(Remap {
+                page: page as u8,
+                read: self.read_page[page],
+                write: self.write_page[page],
+            });
+        }
+    }
+
+    pub(crate) fn start_remap_log(&mut self) {
+        self.remap_log = Some(Vec::new());
+    }
+
+    pub(crate) fn take_remap_log(&mut self) -> Vec<Remap> {
+        self.remap_log.take().unwrap_or_default()
+    }
+
+    /// Puts back the mappings `remaps` replaced, newest first
+    pub(crate) fn undo_remaps(&mut self, remaps: &[Remap]) {
+        for remap in remaps.iter().rev() {
+            self.read_page[remap.page as usize] = remap.read;
+            self.write_page[remap.page as usize] = remap.write;
+        }
+        if !remaps.is_empty() {
+            self.epoch = self.epoch.wrapping_add(1);
+        }
+    }
+
+    /// Puts base RAM back under `count` pages starting at `first_page`
+    pub fn unmap(&mut self, first_page: u8, count: usize) {
+        self.map(first_page, count, BASE_RAM, first_page as usize);
+    }
+
+    /// Number of banks, including base RAM
+    pub fn len(&self) -> usize {
+        self.banks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.banks.is_empty()
+    }
+
+    /// Size of a bank in pages
+    pub fn bank_pages(&self, bank: BankId) -> usize {
+        self.banks[bank].pages
+    }
+
+    pub fn bank(&self, bank: BankId) -> &[u8] {
+        let b = &self.banks[bank];
+        &self.storage[b.offset..b.offset + b.pages * 0x100]
+    }
+
+    /// Direct access to a bank's contents (including ROM banks)
+    pub fn bank_mut(&mut self, bank: BankId) -> &mut [u8] {
+        let b = &self.banks[bank];
+        let (offset, pages) = (b.offset, b.pages);
+        self.touch(offset, pages);
+        &mut self.storage[offset..offset + pages * 0x100]
+    }
+
+    /// Direct access to one byte of a bank, cheaper on code caches than
+    /// `bank_mut` as only its page counts as written
+    pub fn byte_mut(&mut self, bank: BankId, offset: usize) -> &mut u8 {
+        let b = &self.banks[bank];
+        assert!(offset < b.pages * 0x100, "past the end of bank {}", bank);
+        let index = b.offset + offset;
+        self.touch(index & !0xFF, 1);
+        &mut self.storage[index]
+    }
+
+    /// Fills every RAM bank with `pattern`, each from its first byte
+    pub fn power_on(&mut self, pattern: PowerOn) {
+        for i in 0..self.banks.len() {
+            let Bank {
+                offset,
+                pages,
+                writable,
+            } = self.banks[i];
+            if writable {
+                self.touch(offset, pages);
+                pattern.fill(&mut self.storage[offset..offset + pages * 0x100]);
+            }
+        }
+    }
+
+    /// Counts a write on `pages` storage pages from `offset`
+    fn touch(&mut self, offset: usize, pages: usize) {
+        for page in offset >> 8..(offset >> 8) + pages {
+            self.count_write(page);
+        }
+    }
+
+    #[inline]
+    fn count_write(&mut self, page: usize) {
+        self.writes[page] = self.writes[page].wrapping_add(1);
+        self.epoch = self.epoch.wrapping_add(self.watched[page] as u32);
+    }
+
+    /// Where in storage a write to `addr` lands, `None` when it's ignored
+    pub(crate) fn write_index(&self, addr: u16) -> Option<usize> {
+        let base = self.write_page[(addr >> 8) as usize];
+        (base != NO_WRITE).then(|| base + (addr & 0xFF) as usize)
+    }
+
+    pub(crate) fn stored(&self, index: usize) -> u8 {
+        self.storage[index]
+    }
+
+    /// Writes straight to a `write_index`, counting it like any other write
+    pub(crate) fn restore(&mut self, index: usize, value: u8) {
+        self.storage[index] = value;
+        self.count_write(index >> 8);
+    }
+
+    /// Whether writes to CPU page `page` land anywhere
+    pub fn writable(&self, page: u8) -> bool {
+        self.write_page[page as usize] != NO_WRITE
+    }
+
+    /// Where reads of CPU page `page` come from, with a count that changes
+    /// whenever that storage page may have been written
+    pub fn page_version(&self, page: u8) -> (usize, u32) {
+        let offset = self.read_page[page as usize];
+        (offset, self.writes[offset >> 8])
+    }
+
+    /// Has writes to the storage page behind CPU page `page` bump `epoch`
+    pub(crate) fn watch(&mut self, page: u8) {
+        self.watched[self.read_page[page as usize] >> 8] = true;
+    }
+
+    /// Changes whenever a watched page may have been written or any page
+    /// was remapped for reads, so a code cache only has to look at its
+    /// `page_version`s again when it moved
+    pub(crate) fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    fn page_offset(&self, bank: BankId, page: usize) -> usize {
+        self.banks[bank].offset + page * 0x100
+    }
+
+    fn pages(
+        &self,
+        first_page: u8,
+        count: usize,
+        bank: BankId,
+        bank_page: usize,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        assert!(bank < self.banks.len(), "bank {} does not exist", bank);
+        assert!(
+            first_page as usize + count <= 0x100,
+            "mapping runs past the end of the address space"
+        );
+        assert!(
+            bank_page + count <= self.banks[bank].pages,
+            "mapping runs past the end of bank {}",
+            bank
+        );
+
+        (0..count).map(move |i| (i, first_page as usize + i))
+    }
+}
+
+impl Default for Banks {
+    fn default() -> Self {
+        let mut banks = Banks {
+            storage: Vec::new(),
+            banks: Vec::new(),
+            read_page: [0; 256],
+            write_page: [0; 256],
+            writes: Vec::new(),
+            watched: Vec::new(),
+            epoch: 0,
+            remap_log: None,
+        };
+
+        banks.add_ram(0x10000);
+        banks.unmap(0x00, 0x100);
+        banks
+    }
+}
@@ -0,0 +1,30 @@
+use cpu6502::{
+    devices::serial::StreamHost,
+    systems::apple1::{Apple1, CLOCK_HZ},
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Cycles run between checks against the wall clock
+const SLICE: u64 = 10_000;
+
+fn main() {
+    let mut apple = Apple1::new(StreamHost::stdio());
+    apple.reset();
+
+    // Run at roughly the real clock speed so Wozmon's keyboard polling
+    // doesn't spin a host core flat out
+    let start = Instant::now();
+    let mut cycles = 0u64;
+    loop {
+        apple.run(SLICE);
+        cycles += SLICE;
+
+        let target = Duration::from_secs_f64(cycles as f64 / CLOCK_HZ as f64);
+        if let Some(ahead) = target.checked_sub(start.elapsed()) {
+            thread::sleep(ahead);
+        }
+    }
+}
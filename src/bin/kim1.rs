@@ -0,0 +1,45 @@
+use cpu6502::{
+    devices::serial::StreamHost,
+    systems::kim1::{Kim1, CLOCK_HZ},
+};
+use std::{
+    env, fs, process, thread,
+    time::{Duration, Instant},
+};
+
+/// Cycles run between checks against the wall clock
+const SLICE: u64 = 10_000;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("usage: {} <6530-002.bin> <6530-003.bin>", args[0]);
+        process::exit(1);
+    }
+
+    let read = |path: &str| {
+        fs::read(path).unwrap_or_else(|err| {
+            eprintln!("{path}: {err}");
+            process::exit(1);
+        })
+    };
+    let rom_002 = read(&args[1]);
+    let rom_003 = read(&args[2]);
+
+    let mut kim = Kim1::new(&rom_002, &rom_003, StreamHost::stdio());
+    kim.reset();
+    // Lets the monitor measure the bit rate, like a RUBOUT typed at the TTY
+    kim.rubout();
+
+    let start = Instant::now();
+    let mut cycles = 0u64;
+    loop {
+        kim.run(SLICE);
+        cycles += SLICE;
+
+        let target = Duration::from_secs_f64(cycles as f64 / CLOCK_HZ as f64);
+        if let Some(ahead) = target.checked_sub(start.elapsed()) {
+            thread::sleep(ahead);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::{
+    cpu::Cpu,
+    opcodes::{AddrMode, OpcodeSpec, Variant, OPCODES},
+    table::INSTRUCTIONS,
+};
+use std::{cell::Cell, rc::Rc};
+
+/// Longest run of instructions decoded at once
+const MAX_BLOCK: usize = 32;
+
+/// Blocks kept before the cache starts over, so code that keeps moving
+/// (or patching itself) can't grow it without end
+const MAX_BLOCKS: usize = 4096;
+
+/// One instruction with its operand already read out of memory
+struct Op {
+    pc: u16,
+    opcode: u8,
+    operand: u16,
+    spec: &'static OpcodeSpec,
+    op: fn(&mut Cpu),
+}
+
+/// Straight-line instructions from one start PC up to the first one that
+/// jumps, all starting on the same page
+struct Block {
+    ops: Vec<Op>,
+    /// `Bus::page_version` of every page the code was read from, when it
+    /// was decoded
+    pages: Vec<(u16, (usize, u32))>,
+    /// `Bus::layout` when it was decoded
+    layout: u32,
+    /// `Bus::code_epoch` when it was last found valid
+    checked: Cell<u32>,
+    /// `Cpu::variant` when it was decoded, it decides which opcodes are NOPs
+    variant: Variant,
+}
+
+impl Block {
+    /// Only goes through the pages when the epoch moved since last time
+    fn valid(&self, cpu: &Cpu) -> bool {
+        if cpu.variant != self.variant {
+            return false;
+        }
+        let epoch = cpu.memory.code_epoch();
+        if self.checked.get() == epoch {
+            return true;
+        }
+        let valid = cpu.memory.layout() == self.layout
+            && self
+                .pages
+                .iter()
+                .all(|&(addr, version)| cpu.memory.page_version(addr) == Some(version));
+        if valid {
+            self.checked.set(epoch);
+        }
+        valid
+    }
+}
+
+/// Decoded blocks for `Engine::Cached`, indexed by start PC. A block is
+/// dropped and decoded again when one of its pages was written or mapped
+/// to something else since. Once `MAX_BLOCKS` have piled up they're all
+/// dropped.
+#[derive(Default)]
+pub struct BlockCache {
+    /// One slot per start PC, allocated on first use
+    blocks: Vec<Option<Rc<Block>>>,
+    len: usize,
+    /// Block being run and the index of its next instruction
+    current: Option<(Rc<Block>, usize)>,
+}
+
+impl Cpu {
+    /// Drops every decoded block
+    pub fn flush_blocks(&mut self) {
+        self.blocks = BlockCache::default();
+    }
+
+    /// Blocks decoded and not yet dropped
+    pub fn cached_blocks(&self) -> usize {
+        self.blocks.len
+    }
+
+    /// Runs the instruction at `pc` from the block cache, decoding a block
+    /// there if needed. Returns the base cycles and the page crossing cycle,
+    /// or `None` when the code can't be cached (it's on
+    /// a device, or an opcode that isn't emulated) and has to be decoded the
+    /// usual way.
+    pub(crate) fn execute_cached(&mut self) -> Option<(u8, u8)> {
+        // Checked before every instruction, the block may have just written
+        // over itself
+        let current = self.blocks.current.take().filter(|(block, index)| {
+            block.ops.get(*index).is_some_and(|op| op.pc == self.pc) && block.valid(self)
+        });
+        let (block, index) = match current {
+            Some(current) => current,
+            None => (self.block_at(self.pc)?, 0),
+        };
+
+        let op = &block.ops[index];
+        // The uninitialized RAM check sees the same code reads as with the
+        // other engines: the opcode and operand, an immediate one being
+        // read by the instruction itself
+        if self.memory.uninit.is_some() {
+            let read = match op.spec.mode {
+                AddrMode::Immediate => 1,
+                mode => 1 + mode.operand_bytes(),
+            };
+            for i in 0..read as u16 {
+                self.memory.read_code(op.pc.wrapping_add(i));
+            }
+        }
+        self.opcode = op.opcode;
+        // Where the addressing mode would have left it, BRK skips its padding
+        self.pc = op.pc.wrapping_add(1 + op.spec.mode.operand_bytes() as u16);
+        let extra = self.resolve(op.spec.mode, op.pc, op.operand);
+        (op.op)(self);
+        let cycles = op.spec.cycles;
+
+        self.blocks.current = Some((block, index + 1));
+        Some((cycles, extra))
+    }
+
+    fn block_at(&mut self, pc: u16) -> Option<Rc<Block>> {
+        if self.blocks.blocks.is_empty() {
+            self.blocks.blocks = vec![None; 0x10000];
+        }
+        if let Some(block) = &self.blocks.blocks[pc as usize] {
+            if block.valid(self) {
+                return Some(block.clone());
+            }
+        }
+
+        let block = Rc::new(self.decode_block(pc)?);
+        for &(addr, _) in &block.pages {
+            self.memory.watch(addr);
+        }
+        let cache = &mut self.blocks;
+        if cache.len >= MAX_BLOCKS {
+            cache.blocks.fill(None);
+            cache.len = 0;
+        }
+        if cache.blocks[pc as usize].replace(block.clone()).is_none() {
+            cache.len += 1;
+        }
+        Some(block)
+    }
+
+    fn decode_block(&self, start: u16) -> Option<Block> {
+        let mut block = Block {
+            ops: Vec::new(),
+            pages: Vec::new(),
+            layout: self.memory.layout(),
+            checked: Cell::new(self.memory.code_epoch()),
+            variant: self.variant,
+        };
+        let mut pc = start;
+        while block.ops.len() < MAX_BLOCK && pc >> 8 == start >> 8 {
+            let opcode = self.memory.peek(pc);
+            let spec = &OPCODES[opcode as usize];
+            if !self.emulates(opcode) {
+                break;
+            }
+
+            // Every byte of it has to come from plain memory
+            let last = pc.wrapping_add(spec.bytes as u16 - 1);
+            let versions: Option<Vec<_>> = (0..spec.bytes as u16)
+                .map(|i| {
+                    let addr = pc.wrapping_add(i);
+                    Some((addr, self.memory.page_version(addr)?))
+                })
+                .collect();
+            let Some(versions) = versions else {
+                break;
+            };
+            for (addr, version) in versions {
+                if !block.pages.iter().any(|&(a, _)| a >> 8 == addr >> 8) {
+                    block.pages.push((addr, version));
+                }
+            }
+
+            let operand = match spec.mode.operand_bytes() {
+                0 => 0,
+                1 => self.memory.peek(pc.wrapping_add(1)) as u16,
+                _ => u16::from_le_bytes([
+                    self.memory.peek(pc.wrapping_add(1)),
+                    self.memory.peek(last),
+                ]),
+            };
+            block.ops.push(Op {
+                pc,
+                opcode,
+                operand,
+                spec,
+                op: INSTRUCTIONS[opcode as usize].op,
+            });
+
+            pc = pc.wrapping_add(spec.bytes as u16);
+            if spec.jumps() {
+                break;
+            }
+        }
+
+        (!block.ops.is_empty()).then_some(block)
+    }
+
+    /// The addressing modes in `cpu.rs` with the operand already known.
+    /// Pointers are still read from memory.
+    fn resolve(&mut self, mode: AddrMode, pc: u16, operand: u16) -> u8 {
+        let crossed = |base: u16, addr: u16| (base & 0xFF00 != addr & 0xFF00) as u8;
+        match mode {
+            AddrMode::Implied | AddrMode::Accumulator => 0,
+            AddrMode::Immediate => {
+                self.addr_abs = pc.wrapping_add(1);
+                0
+            }
+            AddrMode::ZeroPage => {
+                self.addr_abs = operand;
+                0
+            }
+            AddrMode::ZeroPageX => {
+                self.addr_abs = (operand as u8).wrapping_add(self.x) as u16;
+                0
+            }
+            AddrMode::ZeroPageY => {
+                self.addr_abs = (operand as u8).wrapping_add(self.y) as u16;
+                0
+            }
+            AddrMode::Absolute => {
+                self.addr_abs = operand;
+                0
+            }
+            AddrMode::AbsoluteX => {
+                self.addr_abs = operand.wrapping_add(self.x as u16);
+                crossed(operand, self.addr_abs)
+            }
+            AddrMode::AbsoluteY => {
+                self.addr_abs = operand.wrapping_add(self.y as u16);
+                crossed(operand, self.addr_abs)
+            }
+            AddrMode::Indirect => {
+                let lo = self.read(operand) as u16;
+                // Same page wrap bug as `Cpu::ind`
+                let hi = self.read((operand & 0xFF00) | (operand.wrapping_add(1) & 0x00FF)) as u16;
+                self.addr_abs = (hi << 8) | lo;
+                0
+            }
+            AddrMode::IndirectX => {
+                let base = (operand as u8).wrapping_add(self.x);
+                let lo = self.read(base as u16) as u16;
+                let hi = self.read(base.wrapping_add(1) as u16) as u16;
+                self.addr_abs = (hi << 8) | lo;
+                0
+            }
+            AddrMode::IndirectY => {
+                let base = operand as u8;
+                let lo = self.read(base as u16) as u16;
+                let hi = self.read(base.wrapping_add(1) as u16) as u16;
+                let pointer = (hi << 8) | lo;
+                self.addr_abs = pointer.wrapping_add(self.y as u16);
+                crossed(pointer, self.addr_abs)
+            }
+            AddrMode::Relative => {
+                self.addr_rel = operand as u8 as i8 as i16;
+                0
+            }
+        }
+    }
+}
@@ -0,0 +1,600 @@
+use crate::{
+    banking::{BankSwitch, Banks, BASE_RAM},
+    heatmap::Heatmap,
+    rewind::{Remap, Undo},
+    uninit::{PowerOn, UninitChecker},
+};
+use std::{
+    cell::RefCell,
+    ops::{Index, IndexMut},
+    rc::Rc,
+};
+
+/// A peripheral (or any other handler) that can be mapped into the address space.
+/// `addr` is the offset into the region after the mirror mask has been applied.
+pub trait Device {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+
+    /// Advances the device by one CPU cycle
+    fn tick(&mut self) {}
+
+    /// Level of the device's interrupt output (true = asserted)
+    fn interrupt(&self) -> bool {
+        false
+    }
+
+    /// Chip select. Accesses to a deselected device fall through to whatever
+    /// is mapped underneath, it keeps ticking all the same.
+    fn selected(&self) -> bool {
+        true
+    }
+
+    /// What a read of `addr` would return, without its side effects (for
+    /// disassemblers and debuggers). `None` when the device can't tell, the
+    /// banked memory underneath shows instead.
+    fn peek(&self, _addr: u16) -> Option<u8> {
+        None
+    }
+
+    /// Whether the device always answers, with the same bytes every time
+    /// and no side effects, like a ROM. Code caches keep code read from it.
+    /// A shared device can change from outside, so it never is.
+    fn read_only(&self) -> bool {
+        false
+    }
+}
+
+/// Lets a device stay reachable from outside the bus (e.g. to feed it keystrokes)
+impl<T: Device> Device for Rc<RefCell<T>> {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.borrow_mut().read(addr)
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.borrow_mut().write(addr, value)
+    }
+
+    fn tick(&mut self) {
+        self.borrow_mut().tick()
+    }
+
+    fn interrupt(&self) -> bool {
+        self.borrow().interrupt()
+    }
+
+    fn selected(&self) -> bool {
+        self.borrow().selected()
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.borrow().peek(addr)
+    }
+}
+
+/// Read and write callbacks wrapped up as a `Device`
+pub struct Handler<R, W> {
+    pub read: R,
+    pub write: W,
+}
+
+impl<R, W> Device for Handler<R, W>
+where
+    R: FnMut(u16) -> u8,
+    W: FnMut(u16, u8),
+{
+    fn read(&mut self, addr: u16) -> u8 {
+        (self.read)(addr)
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        (self.write)(addr, value)
+    }
+}
+
+/// Read-only memory, writes are ignored
+pub struct Rom {
+    pub data: Vec<u8>,
+}
+
+impl Device for Rom {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.data.get(addr as usize).copied().unwrap_or(0)
+    }
+
+    fn write(&mut self, _addr: u16, _value: u8) {}
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        Some(self.data.get(addr as usize).copied().unwrap_or(0))
+    }
+
+    fn read_only(&self) -> bool {
+        true
+    }
+}
+
+/// Where (and how) a device shows up in the address space
+#[derive(Clone, Copy, Debug)]
+pub struct Mapping {
+    /// First address of the region (inclusive)
+    pub start: u16,
+    /// Last address of the region (inclusive)
+    pub end: u16,
+    /// Applied to the offset into the region, e.g. 0x0007 mirrors 8 registers
+    pub mirror_mask: u16,
+    /// When regions overlap the highest priority wins,
+    /// ties go to the region registered last
+    pub priority: i32,
+}
+
+impl Mapping {
+    pub fn new(start: u16, end: u16) -> Self {
+        Self {
+            start,
+            end,
+            mirror_mask: 0xFFFF,
+            priority: 0,
+        }
+    }
+
+    pub fn contains(&self, addr: u16) -> bool {
+        addr >= self.start && addr <= self.end
+    }
+}
+
+pub type RegionId = usize;
+
+/// The CPU input a device's interrupt output is wired to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Line {
+    Irq,
+    Nmi,
+    /// Left unconnected, like the PIA interrupts on the Apple-1
+    None,
+}
+
+enum Handled {
+    Device(Box<dyn Device>),
+    BankSwitch(Box<dyn BankSwitch>),
+}
+
+struct Region {
+    id: RegionId,
+    mapping: Mapping,
+    handled: Handled,
+    line: Line,
+}
+
+impl Region {
+    fn answers(&self, addr: u16) -> bool {
+        self.mapping.contains(addr)
+            && match &self.handled {
+                Handled::Device(device) => device.selected(),
+                Handled::BankSwitch(_) => true,
+            }
+    }
+}
+
+/// The CPU's view of memory: banked RAM/ROM with devices mapped over it.
+/// Indexing goes straight to base RAM and bypasses banking and devices
+/// (handy for loading programs and inspecting state), `read`/`write` are
+/// what the CPU uses.
+pub struct Bus {
+    /// Page mappings for everything that isn't a device
+    pub banks: Banks,
+    /// Sorted by priority, highest first
+    regions: Vec<Region>,
+    /// Pages with at least one region on them, everything else is plain RAM
+    mapped: [bool; 256],
+    /// Changes whenever a region is mapped or unmapped
+    layout: u32,
+    has_devices: bool,
+    next_id: RegionId,
+    irq: bool,
+    nmi: bool,
+    /// Address lines brought out of the CPU package, the rest read as 0
+    address_mask: u16,
+    /// Every write since the last `take_writes`, while logging is on
+    write_log: Option<Vec<(u16, u8)>>,
+    /// Counts accesses per address while set
+    pub heatmap: Option<Box<Heatmap>>,
+    /// Tracks which RAM has been initialized while set
+    pub uninit: Option<Box<UninitChecker>>,
+    /// Bytes replaced by writes to banked memory during the current
+    /// instruction, while `Cpu::rewind` is set
+    undo_log: Option<Vec<Undo>>,
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn read(&mut self, addr: u16) -> u8 {
+        let addr = addr & self.address_mask;
+        if self.heatmap.is_some() || self.uninit.is_some() {
+            let ram = self.ram(addr);
+            if let Some(heatmap) = self.heatmap.as_deref_mut() {
+                let uninit = self.uninit.as_deref();
+                heatmap.read(addr, ram && uninit.is_some_and(|u| !u.initialized(addr)));
+            }
+            if let Some(uninit) = self.uninit.as_deref_mut().filter(|_| ram) {
+                uninit.read(addr);
+            }
+        }
+        self.read_unlogged(addr)
+    }
+
+    /// Reads an instruction byte, `executed` counts it instead
+    pub fn read_code(&mut self, addr: u16) -> u8 {
+        let addr = addr & self.address_mask;
+        if self.uninit.is_some() {
+            let ram = self.ram(addr);
+            if let Some(uninit) = self.uninit.as_deref_mut().filter(|_| ram) {
+                uninit.read(addr);
+            }
+        }
+        self.read_unlogged(addr)
+    }
+
+    /// Whether `addr` is banked memory that takes writes, with no region
+    /// answering in its place
+    fn ram(&self, addr: u16) -> bool {
+        let page = (addr >> 8) as u8;
+        self.banks.writable(page)
+            && !(self.mapped[page as usize] && self.regions.iter().any(|r| r.answers(addr)))
+    }
+
+    /// Marks the `bytes` of an instruction at `addr` as executed
+    pub fn executed(&mut self, addr: u16, bytes: u8) {
+        if let Some(heatmap) = self.heatmap.as_deref_mut() {
+            for i in 0..bytes as u16 {
+                heatmap.execute(addr.wrapping_add(i) & self.address_mask);
+            }
+        }
+    }
+
+    #[inline]
+    fn read_unlogged(&mut self, addr: u16) -> u8 {
+        if self.mapped[(addr >> 8) as usize] {
+            if let Some(region) = self.regions.iter_mut().find(|r| r.answers(addr)) {
+                let offset = (addr - region.mapping.start) & region.mapping.mirror_mask;
+                match &mut region.handled {
+                    Handled::Device(device) => return device.read(offset),
+                    Handled::BankSwitch(switch) => {
+                        if let Some(value) = switch.read(offset) {
+                            return value;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.banks.read(addr)
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        let addr = addr & self.address_mask;
+        if let Some(log) = &mut self.write_log {
+            log.push((addr, value));
+        }
+        if let Some(heatmap) = self.heatmap.as_deref_mut() {
+            heatmap.write(addr);
+        }
+        if self.uninit.is_some() && self.ram(addr) {
+            if let Some(uninit) = self.uninit.as_deref_mut() {
+                uninit.initialize(addr);
+            }
+        }
+        if self.mapped[(addr >> 8) as usize] {
+            if let Some(region) = self.regions.iter_mut().find(|r| r.answers(addr)) {
+                let offset = (addr - region.mapping.start) & region.mapping.mirror_mask;
+                match &mut region.handled {
+                    Handled::Device(device) => device.write(offset, value),
+                    Handled::BankSwitch(switch) => switch.write(&mut self.banks, offset, value),
+                }
+                return;
+            }
+        }
+
+        if let Some(log) = &mut self.undo_log {
+            if let Some(index) = self.banks.write_index(addr) {
+                let old = self.banks.stored(index);
+                log.push(Undo { addr, index, old });
+            }
+        }
+        self.banks.write(addr, value);
+    }
+
+    pub(crate) fn start_undo(&mut self) {
+        self.undo_log = Some(Vec::new());
+        self.banks.start_remap_log();
+    }
+
+    pub(crate) fn take_undo(&mut self) -> (Vec<Undo>, Vec<Remap>) {
+        let writes = self.undo_log.take().unwrap_or_default();
+        (writes, self.banks.take_remap_log())
+    }
+
+    /// Puts back what `writes` replaced and how `remaps` found the pages,
+    /// newest first
+    pub(crate) fn undo(&mut self, writes: &[Undo], remaps: &[Remap]) {
+        for undo in writes.iter().rev() {
+            self.banks.restore(undo.index, undo.old);
+        }
+        self.banks.undo_remaps(remaps);
+    }
+
+    /// Advances every mapped device by one cycle and samples their
+    /// interrupt outputs. A device should be mapped only once (use a mirror
+    /// mask rather than several regions) or it will be ticked more than once.
+    pub fn tick(&mut self) {
+        self.irq = false;
+        self.nmi = false;
+
+        for region in &mut self.regions {
+            if let Handled::Device(device) = &mut region.handled {
+                device.tick();
+                if device.interrupt() {
+                    match region.line {
+                        Line::Irq => self.irq = true,
+                        Line::Nmi => self.nmi = true,
+                        Line::None => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether any device is mapped, bank switches don't tick
+    pub(crate) fn has_devices(&self) -> bool {
+        self.has_devices
+    }
+
+    /// Whether any device wired to IRQ is asserting it (as of the last tick)
+    pub fn irq(&self) -> bool {
+        self.irq
+    }
+
+    /// Whether any device wired to NMI is asserting it (as of the last tick)
+    pub fn nmi(&self) -> bool {
+        self.nmi
+    }
+
+    /// Wires a region's interrupt output to `line` (IRQ by default)
+    pub fn connect(&mut self, id: RegionId, line: Line) {
+        if let Some(region) = self.regions.iter_mut().find(|r| r.id == id) {
+            region.line = line;
+        }
+    }
+
+    /// Reads `addr` without side effects: from the device answering there
+    /// if it can `peek`, through the bank mappings otherwise
+    pub fn peek(&self, addr: u16) -> u8 {
+        let addr = addr & self.address_mask;
+        if self.mapped[(addr >> 8) as usize] {
+            if let Some(region) = self.regions.iter().find(|r| r.answers(addr)) {
+                if let Handled::Device(device) = &region.handled {
+                    let offset = (addr - region.mapping.start) & region.mapping.mirror_mask;
+                    if let Some(value) = device.peek(offset) {
+                        return value;
+                    }
+                }
+            }
+        }
+        self.banks.read(addr)
+    }
+
+    /// Starts or stops logging writes, for `take_writes`
+    pub fn log_writes(&mut self, on: bool) {
+        self.write_log = on.then(Vec::new);
+    }
+
+    /// Writes (address and value, in order) since the last call
+    pub fn take_writes(&mut self) -> Vec<(u16, u8)> {
+        self.write_log.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Identifies the memory behind `addr`'s page for code caches: where it
+    /// reads from and how many writes it has taken. `None` when reads of
+    /// `addr` itself can come from a device or a bank switch register;
+    /// bank switches whose reads fall through to the banks don't count,
+    /// and a read-only device (counting down from `usize::MAX` by region
+    /// id) never changes.
+    pub fn page_version(&self, addr: u16) -> Option<(usize, u32)> {
+        let addr = addr & self.address_mask;
+        let page = (addr >> 8) as u8;
+        if self.mapped[page as usize] {
+            // The region on top answers, as it does for reads
+            if let Some(region) = self.regions.iter().find(|r| r.mapping.contains(addr)) {
+                match &region.handled {
+                    Handled::Device(device) => {
+                        return device.read_only().then_some((usize::MAX - region.id, 0));
+                    }
+                    Handled::BankSwitch(switch) if switch.answers_reads() => return None,
+                    Handled::BankSwitch(_) => {}
+                }
+            }
+        }
+        Some(self.banks.page_version(page))
+    }
+
+    /// Changes whenever a region is mapped or unmapped, which can change
+    /// what `page_version` says about addresses it didn't change the
+    /// version of
+    pub(crate) fn layout(&self) -> u32 {
+        self.layout
+    }
+
+    /// Has writes to the memory behind `addr` count towards `code_epoch`
+    pub(crate) fn watch(&mut self, addr: u16) {
+        self.banks.watch(((addr & self.address_mask) >> 8) as u8);
+    }
+
+    /// Changes whenever the `page_version` of a watched page (or any page,
+    /// after a remapping) may have changed
+    pub(crate) fn code_epoch(&self) -> u32 {
+        self.layout.wrapping_add(self.banks.epoch())
+    }
+
+    /// `addr` as the bus sees it, with the address lines it doesn't have
+    /// cleared
+    pub(crate) fn masked(&self, addr: u16) -> u16 {
+        addr & self.address_mask
+    }
+
+    /// Keeps only the low `lines` address lines, so everything above
+    /// mirrors the bottom of the address space (13 lines on the 6507)
+    pub fn set_address_lines(&mut self, lines: u32) {
+        assert!((1..=16).contains(&lines), "a 6502 has 16 address lines");
+        self.address_mask = (0xFFFF_u32 >> (16 - lines)) as u16;
+    }
+
+    /// Maps a device into the address space
+    pub fn map(&mut self, mapping: Mapping, device: impl Device + 'static) -> RegionId {
+        self.insert(mapping, Handled::Device(Box::new(device)))
+    }
+
+    /// Maps bank control registers into the address space.
+    /// Writes in the range go to `switch` (which can remap `banks`), reads
+    /// fall through to the banked memory unless `switch` answers them.
+    pub fn map_bank_switch(
+        &mut self,
+        mapping: Mapping,
+        switch: impl BankSwitch + 'static,
+    ) -> RegionId {
+        self.insert(mapping, Handled::BankSwitch(Box::new(switch)))
+    }
+
+    fn insert(&mut self, mapping: Mapping, handled: Handled) -> RegionId {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // Insert after every region with a higher priority and before the
+        // rest, so newer regions win ties
+        let pos = self
+            .regions
+            .iter()
+            .position(|r| r.mapping.priority <= mapping.priority)
+            .unwrap_or(self.regions.len());
+        self.regions.insert(
+            pos,
+            Region {
+                id,
+                mapping,
+                handled,
+                line: Line::Irq,
+            },
+        );
+
+        self.update_mapped();
+        id
+    }
+
+    /// Maps a pair of read/write callbacks into the address space
+    pub fn map_handler<R, W>(&mut self, mapping: Mapping, read: R, write: W) -> RegionId
+    where
+        R: FnMut(u16) -> u8 + 'static,
+        W: FnMut(u16, u8) + 'static,
+    {
+        self.map(mapping, Handler { read, write })
+    }
+
+    /// Maps `data` as ROM starting at `start`
+    pub fn map_rom(&mut self, start: u16, data: &[u8]) -> RegionId {
+        assert!(!data.is_empty(), "ROM image is empty");
+        let end = start as usize + data.len() - 1;
+        assert!(end <= 0xFFFF, "ROM image does not fit in the address space");
+
+        self.map(
+            Mapping::new(start, end as u16),
+            Rom {
+                data: data.to_vec(),
+            },
+        )
+    }
+
+    /// Removes a previously mapped region, returns false if it didn't exist
+    pub fn unmap(&mut self, id: RegionId) -> bool {
+        let before = self.regions.len();
+        self.regions.retain(|r| r.id != id);
+        self.update_mapped();
+        self.regions.len() != before
+    }
+
+    /// Copies `data` straight into base RAM starting at `start`
+    pub fn load(&mut self, start: u16, data: &[u8]) {
+        for (i, &byte) in data.iter().enumerate() {
+            *self
+                .banks
+                .byte_mut(BASE_RAM, start.wrapping_add(i as u16) as usize) = byte;
+        }
+        if let Some(uninit) = self.uninit.as_deref_mut() {
+            for i in 0..data.len() {
+                uninit.initialize(start.wrapping_add(i as u16));
+            }
+        }
+    }
+
+    /// Fills every RAM bank with `pattern` and forgets what was
+    /// initialized, as if the machine had just been switched on
+    pub fn power_on(&mut self, pattern: PowerOn) {
+        self.banks.power_on(pattern);
+        if let Some(uninit) = self.uninit.as_deref_mut() {
+            uninit.clear();
+        }
+    }
+
+    fn update_mapped(&mut self) {
+        self.layout = self.layout.wrapping_add(1);
+        self.has_devices = self
+            .regions
+            .iter()
+            .any(|r| matches!(r.handled, Handled::Device(_)));
+        self.mapped = [false; 256];
+        for region in &self.regions {
+            let first = region.mapping.start >> 8;
+            let last = region.mapping.end >> 8;
+            for page in first..=last {
+                self.mapped[page as usize] = true;
+            }
+        }
+    }
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Bus {
+            banks: Banks::new(),
+            regions: Vec::new(),
+            mapped: [false; 256],
+            layout: 0,
+            has_devices: false,
+            next_id: 0,
+            irq: false,
+            nmi: false,
+            address_mask: 0xFFFF,
+            write_log: None,
+            heatmap: None,
+            uninit: None,
+            undo_log: None,
+        }
+    }
+}
+
+impl Index<usize> for Bus {
+    type Output = u8;
+
+    fn index(&self, addr: usize) -> &u8 {
+        &self.banks.bank(BASE_RAM)[addr]
+    }
+}
+
+impl IndexMut<usize> for Bus {
+    fn index_mut(&mut self, addr: usize) -> &mut u8 {
+        if let Some(uninit) = self.uninit.as_deref_mut() {
+            uninit.initialize(addr as u16);
+        }
+        self.banks.byte_mut(BASE_RAM, addr)
+    }
+}
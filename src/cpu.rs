@@ -1,6 +1,57 @@
-use crate::{instructions::Instruction, table::build_instruction_table};
+use crate::{
+    blocks::BlockCache,
+    bus::Bus,
+    illegal::IllegalPolicy,
+    opcodes::{Access, AddrMode, PagePenalty, Variant, OPCODES},
+    profiler::Profiler,
+    rewind::Rewind,
+    stack::StackChecker,
+    uninit::UninitMode,
+};
+use std::{error::Error, fmt};
 // Information grabbed from: https://www.nesdev.org/wiki/CPU
 
+/// How `Cpu::clock` gets from an opcode to the code that runs it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    /// Two calls through the `INSTRUCTIONS` table's fn pointers
+    #[default]
+    Table,
+    /// Runs of instructions decoded once into a `BlockCache`, operands and
+    /// all
+    Cached,
+}
+
+/// Why the CPU stopped running instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CpuError {
+    /// An illegal opcode whose class is set to `IllegalPolicy::Trap`
+    IllegalOpcode { pc: u16, opcode: u8 },
+    /// A JAM opcode locked the CPU up, only a reset gets it going again
+    Jammed { pc: u16, opcode: u8 },
+    /// The instruction at `pc` read RAM at `addr` that nothing had
+    /// initialized, with `UninitMode::Stop`
+    UninitializedRead { pc: u16, addr: u16 },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::IllegalOpcode { pc, opcode } => {
+                write!(f, "illegal opcode ${:02X} at ${:04X}", opcode, pc)
+            }
+            CpuError::Jammed { pc, opcode } => {
+                write!(f, "CPU jammed by ${:02X} at ${:04X}", opcode, pc)
+            }
+            CpuError::UninitializedRead { pc, addr } => {
+                write!(f, "uninitialized read of ${:04X} at ${:04X}", addr, pc)
+            }
+        }
+    }
+}
+
+impl Error for CpuError {}
+
 /// Represents the 6502 CPU core used in the NES.
 pub struct Cpu {
     /// Program Counter (16-bit)
@@ -20,10 +71,45 @@ pub struct Cpu {
     pub addr_abs: u16,
     pub addr_rel: i16,
     pub fetched: u8,
-    /// 64KB of addressable memory
-    pub memory: [u8; 0x10000],
-
-    pub instruction_table: [Instruction; 256],
+    /// Opcode of the instruction currently executing
+    pub opcode: u8,
+    /// Total cycle count of the instruction currently executing
+    pub instr_cycles: u8,
+    /// 64KB of addressable memory, with any mapped devices on top
+    pub memory: Bus,
+    /// RDY input line level (true = ready, low stalls read cycles)
+    rdy: bool,
+    /// SO input line level (a falling edge sets the overflow flag)
+    so: bool,
+    so_edge: bool,
+    /// IRQ input line driven from outside the bus (true = asserted)
+    irq: bool,
+    /// NMI input line driven from outside the bus (true = asserted)
+    nmi: bool,
+    /// Combined NMI level seen on the last clock, for edge detection
+    nmi_level: bool,
+    /// An NMI edge was seen and will be serviced before the next instruction
+    pub nmi_pending: bool,
+    /// CLI, SEI and PLP change I after the IRQ line has been polled, so the
+    /// next interrupt check still sees the old flag
+    pub(crate) irq_poll_disabled: Option<bool>,
+    /// Set when an opcode stopped the CPU, it sits still until reset
+    pub halted: Option<CpuError>,
+    pub engine: Engine,
+    /// The chip being emulated. It decides whether ADC and SBC have a
+    /// decimal mode (not on the 2A03) and whether undocumented opcodes run
+    /// (on the 65C02 they're NOPs, see `Variant::Cmos65C02`).
+    pub variant: Variant,
+    /// Per `OpcodeClass`, see `set_illegal_policy`
+    pub(crate) illegal_policy: [IllegalPolicy; 4],
+    /// Decoded code for `Engine::Cached`
+    pub(crate) blocks: BlockCache,
+    /// Counts executions and cycles while set
+    pub profiler: Option<Box<Profiler>>,
+    /// Checks stack use while set
+    pub stack_checker: Option<Box<StackChecker>>,
+    /// Keeps undo logs of recent instructions while set
+    pub rewind: Option<Box<Rewind>>,
 }
 
 pub enum Flag {
@@ -43,6 +129,14 @@ impl Cpu {
         Self::default()
     }
 
+    /// The 6507: a 6502 in a smaller package with only 13 address lines,
+    /// so its 8K address space shows up 8 times over
+    pub fn new_6507() -> Self {
+        let mut cpu = Self::default();
+        cpu.memory.set_address_lines(13);
+        cpu
+    }
+
     pub fn get_flag(&self, flag: Flag) -> bool {
         self.status & (1 << flag as u8) != 0
     }
@@ -57,20 +151,211 @@ impl Cpu {
         }
     }
 
+    /// Reads a byte from the address space
+    #[inline]
+    pub fn read(&mut self, addr: u16) -> u8 {
+        self.memory.read(addr)
+    }
+
+    /// Reads an opcode or operand byte, which `Heatmap` counts as executed
+    /// rather than read
+    #[inline]
+    pub fn read_code(&mut self, addr: u16) -> u8 {
+        self.memory.read_code(addr)
+    }
+
+    /// Writes a byte to the address space
+    #[inline]
+    pub fn write(&mut self, addr: u16, value: u8) {
+        self.memory.write(addr, value);
+    }
+
+    /// Drives the RDY input line.
+    /// Pulling it low halts the CPU on its next read cycle. Like the NMOS
+    /// 6502, write cycles are not affected and complete normally.
+    pub fn set_rdy(&mut self, level: bool) {
+        self.rdy = level;
+    }
+
+    pub fn rdy(&self) -> bool {
+        self.rdy
+    }
+
+    /// Drives the SO (set overflow) input line.
+    /// A high-to-low transition sets `Flag::Overflow` on the next clock.
+    pub fn set_so(&mut self, level: bool) {
+        if self.so && !level {
+            self.so_edge = true;
+        }
+        self.so = level;
+    }
+
+    pub fn so(&self) -> bool {
+        self.so
+    }
+
+    /// Drives the IRQ input line (true = asserted). IRQ is level triggered
+    /// and is ORed with the interrupt outputs of the devices on the bus.
+    pub fn set_irq(&mut self, asserted: bool) {
+        self.irq = asserted;
+    }
+
+    /// Whether anything is currently asserting IRQ
+    pub fn irq_asserted(&self) -> bool {
+        self.irq || self.memory.irq()
+    }
+
+    /// Drives the NMI input line (true = asserted). NMI is edge triggered:
+    /// it is serviced once when the combined line becomes asserted.
+    pub fn set_nmi(&mut self, asserted: bool) {
+        self.nmi = asserted;
+    }
+
+    /// Hardware interrupt sequence: push PC and status (with B clear),
+    /// set the interrupt disable flag and jump through `vector`
+    pub fn interrupt(&mut self, vector: u16) {
+        self.push((self.pc >> 8) as u8);
+        self.push((self.pc & 0xFF) as u8);
+
+        let mut flags = self.status;
+        flags &= !(1 << Flag::Break as u8);
+        flags |= 1 << Flag::Unused as u8;
+        self.push(flags);
+
+        self.set_flag(Flag::InterruptDisable, true);
+
+        let lo = self.read(vector) as u16;
+        let hi = self.read(vector.wrapping_add(1)) as u16;
+        self.pc = (hi << 8) | lo;
+    }
+
+    /// Reset sequence: the 6502 runs a BRK with its writes suppressed, so SP
+    /// ends up three lower, I is set and PC is loaded from $FFFC
+    pub fn reset(&mut self) {
+        self.sp = self.sp.wrapping_sub(3);
+        if let Some(checker) = self.stack_checker.as_deref_mut() {
+            checker.reset(self.sp);
+        }
+        self.set_flag(Flag::InterruptDisable, true);
+        self.set_flag(Flag::Unused, true);
+        self.nmi_pending = false;
+        self.irq_poll_disabled = None;
+        self.halted = None;
+
+        let lo = self.read(0xFFFC) as u16;
+        let hi = self.read(0xFFFD) as u16;
+        self.pc = (hi << 8) | lo;
+
+        self.opcode = 0x00;
+        self.cycles = 7;
+        self.instr_cycles = 7;
+    }
+
+    /// Clocks the CPU until the current instruction is done (starting a new
+    /// one if it is between instructions), returns how many cycles that took.
+    /// Stops early on a cycle RDY stalls, as nothing moves until it's raised.
+    pub fn step(&mut self) -> u32 {
+        // With RDY high nothing can stall, so past the first cycle only the
+        // work every cycle does is left. With no devices to tick (and the
+        // inputs only driven from outside) there's none.
+        if self.rdy && self.cycles == 0 {
+            self.clock();
+            let mut cycles = 1;
+            if !self.memory.has_devices() && self.halted.is_none() {
+                cycles += self.cycles as u32;
+                self.cycles = 0;
+            }
+            while self.cycles != 0 {
+                self.cycle();
+                self.cycles = if self.halted.is_some() {
+                    0
+                } else {
+                    self.cycles - 1
+                };
+                cycles += 1;
+            }
+            return cycles;
+        }
+
+        let mut cycles = 0;
+        loop {
+            let stalled = !self.rdy && !self.is_write_cycle();
+            self.clock();
+            cycles += 1;
+            if self.cycles == 0 || stalled {
+                return cycles;
+            }
+        }
+    }
+
+    /// Like `step`, but fails once an opcode has stopped the CPU
+    pub fn try_step(&mut self) -> Result<u32, CpuError> {
+        let cycles = self.step();
+        match self.halted {
+            Some(err) => Err(err),
+            None => Ok(cycles),
+        }
+    }
+
+    /// Runs whole instructions for at least `cycles` cycles, returns how
+    /// many it took or why the CPU stopped
+    pub fn run(&mut self, cycles: u64) -> Result<u64, CpuError> {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.try_step()? as u64;
+        }
+        Ok(elapsed)
+    }
+
+    /// Whether the upcoming cycle is a write cycle.
+    /// Stack pushes are listed here and stores and read-modify-writes come
+    /// from the opcode spec, everything else (including the opcode fetch)
+    /// is a read.
+    pub fn is_write_cycle(&self) -> bool {
+        if self.cycles == 0 {
+            return false;
+        }
+
+        let total = self.instr_cycles;
+        let index = total.saturating_sub(self.cycles);
+        match self.opcode {
+            // BRK pushes PCH, PCL and P on cycles 3-5
+            0x00 => (2..=4).contains(&index),
+            // JSR pushes PCH and PCL on cycles 4-5
+            0x20 => (3..=4).contains(&index),
+            // PHP, PHA
+            0x08 | 0x48 => index == 2,
+            _ if !self.emulates(self.opcode) => false,
+            opcode => match OPCODES[opcode as usize].access {
+                Access::Write => index == total.saturating_sub(1),
+                // Dummy write then final write
+                Access::ReadModifyWrite => index >= total.saturating_sub(2),
+                _ => false,
+            },
+        }
+    }
+
     pub fn push(&mut self, value: u8) {
         let addr = 0x0100 | (self.sp as u16);
-        self.memory[addr as usize] = value;
+        self.write(addr, value);
         self.sp = self.sp.wrapping_sub(1);
     }
 
     pub fn pull(&mut self) -> u8 {
         self.sp = self.sp.wrapping_add(1);
         let addr = 0x0100 | self.sp as u16;
-        self.memory[addr as usize]
+        self.read(addr)
     }
 
+    /// Implied and accumulator: no operand
+    #[inline]
+    pub fn imp(&mut self) -> u8 {
+        0
+    }
+
+    #[inline]
     pub fn rel(&mut self) -> u8 {
-        let raw = self.memory[self.pc as usize];
+        let raw = self.read_code(self.pc);
         let offset = raw as i8;
         self.addr_rel = offset as i16;
 
@@ -78,20 +363,21 @@ impl Cpu {
         0
     }
 
+    #[inline]
     pub fn ind(&mut self) -> u8 {
         // pc points at the low byte of the pointer
-        let ptr_lo = self.memory[self.pc as usize] as u16;
-        let ptr_hi = self.memory[self.pc.wrapping_add(1) as usize] as u16;
+        let ptr_lo = self.read_code(self.pc) as u16;
+        let ptr_hi = self.read_code(self.pc.wrapping_add(1)) as u16;
 
         let ptr = (ptr_hi << 8) | ptr_lo;
 
-        let addr_lo = self.memory[ptr as usize] as u16;
+        let addr_lo = self.read(ptr) as u16;
 
         // 6502 bug: if low byte is $FF, wrap around to beginning of page
         let next_byte = if ptr_lo == 0x00FF {
-            self.memory[(ptr & 0xFF00) as usize] as u16
+            self.read(ptr & 0xFF00) as u16
         } else {
-            self.memory[ptr.wrapping_add(1) as usize] as u16
+            self.read(ptr.wrapping_add(1)) as u16
         };
 
         self.addr_abs = (next_byte << 8) | addr_lo;
@@ -102,10 +388,11 @@ impl Cpu {
     }
 
     /// Indexed Indirect (X)
+    #[inline]
     pub fn indx(&mut self) -> u8 {
-        let base = self.memory[self.pc as usize].wrapping_add(self.x); // operand + X (with wrap)
-        let ptr_lo = self.memory[base as usize] as u16;
-        let ptr_hi = self.memory[base.wrapping_add(1) as usize] as u16;
+        let base = self.read_code(self.pc).wrapping_add(self.x); // operand + X (with wrap)
+        let ptr_lo = self.read(base as u16) as u16;
+        let ptr_hi = self.read(base.wrapping_add(1) as u16) as u16;
 
         self.addr_abs = (ptr_hi << 8) | ptr_lo;
         self.pc = self.pc.wrapping_add(1); // advance PC past operand
@@ -114,10 +401,11 @@ impl Cpu {
     }
 
     /// Indirect Indexed (Y)
+    #[inline]
     pub fn indy(&mut self) -> u8 {
-        let base = self.memory[self.pc as usize];
-        let ptr_lo = self.memory[base as usize] as u16;
-        let ptr_hi = self.memory[base.wrapping_add(1) as usize] as u16;
+        let base = self.read_code(self.pc);
+        let ptr_lo = self.read(base as u16) as u16;
+        let ptr_hi = self.read(base.wrapping_add(1) as u16) as u16;
 
         let base_addr = (ptr_hi << 8) | ptr_lo;
         self.addr_abs = base_addr.wrapping_add(self.y as u16);
@@ -130,21 +418,23 @@ impl Cpu {
         }
     }
 
+    #[inline]
     pub fn abs(&mut self) -> u8 {
-        let lo = self.memory[self.pc as usize] as u16;
+        let lo = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
 
-        let hi = self.memory[self.pc as usize] as u16;
+        let hi = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
 
         self.addr_abs = (hi << 8) | lo;
         0
     }
 
+    #[inline]
     pub fn absx(&mut self) -> u8 {
-        let lo = self.memory[self.pc as usize] as u16;
+        let lo = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
-        let hi = self.memory[self.pc as usize] as u16;
+        let hi = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
 
         let base = (hi << 8) | lo;
@@ -157,10 +447,11 @@ impl Cpu {
         }
     }
 
+    #[inline]
     pub fn absy(&mut self) -> u8 {
-        let lo = self.memory[self.pc as usize] as u16;
+        let lo = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
-        let hi = self.memory[self.pc as usize] as u16;
+        let hi = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
 
         let base = (hi << 8) | lo;
@@ -174,27 +465,31 @@ impl Cpu {
         }
     }
 
+    #[inline]
     pub fn imm(&mut self) -> u8 {
         self.addr_abs = self.pc;
         self.pc = self.pc.wrapping_add(1);
         0
     }
 
+    #[inline]
     pub fn zp0(&mut self) -> u8 {
-        self.addr_abs = self.memory[self.pc as usize] as u16;
+        self.addr_abs = self.read_code(self.pc) as u16;
         self.pc = self.pc.wrapping_add(1);
         0
     }
 
+    #[inline]
     pub fn zpx(&mut self) -> u8 {
-        let base = self.memory[self.pc as usize];
+        let base = self.read_code(self.pc);
         self.addr_abs = base.wrapping_add(self.x) as u16 & 0x00FF;
         self.pc = self.pc.wrapping_add(1);
         0
     }
 
+    #[inline]
     pub fn zpy(&mut self) -> u8 {
-        let base = self.memory[self.pc as usize];
+        let base = self.read_code(self.pc);
         self.addr_abs = base.wrapping_add(self.y) as u16 & 0x00FF; // wrap around zero page
         self.pc = self.pc.wrapping_add(1);
 
@@ -202,24 +497,181 @@ impl Cpu {
     }
 
     /// fetches the value from memory at the absolute address (`addr_abs`) and stores it in `fetched`
+    #[inline]
     pub fn fetch(&mut self) -> u8 {
-        self.fetched = self.memory[self.addr_abs as usize];
+        // An immediate operand is an instruction byte, not a data read
+        self.fetched = if OPCODES[self.opcode as usize].mode == AddrMode::Immediate {
+            self.read_code(self.addr_abs)
+        } else {
+            self.read(self.addr_abs)
+        };
         self.fetched
     }
 
     pub fn clock(&mut self) {
-        if self.cycles == 0 {
-            let opcode = self.memory[self.pc as usize];
+        self.cycle();
 
-            self.pc = self.pc.wrapping_add(1);
-            let addr_cycles = (self.instruction_table[opcode as usize].addr_mode)(self);
+        // NMOS 6502: RDY only stops the CPU on read cycles
+        if !self.rdy && !self.is_write_cycle() {
+            return;
+        }
 
-            (self.instruction_table[opcode as usize].op)(self);
-            self.cycles = self.instruction_table[opcode as usize].cycles + addr_cycles;
+        if self.halted.is_some() {
+            self.cycles = 0;
+            return;
+        }
+
+        if self.cycles == 0 {
+            if self.watched() {
+                self.start_watched();
+            } else {
+                self.start();
+            }
         }
 
         self.cycles = self.cycles.saturating_sub(1);
     }
+
+    /// Starts the next instruction, or the interrupt sequence if one is
+    /// due. Returns whether it was an interrupt.
+    fn start(&mut self) -> bool {
+        let irq_disabled = self.irq_disabled_at_poll();
+
+        // The 6502 forces a BRK into the instruction register for interrupts
+        let interrupted = if self.nmi_pending {
+            self.nmi_pending = false;
+            self.opcode = 0x00;
+            self.interrupt(0xFFFA);
+            self.cycles = 7;
+            true
+        } else if self.irq_asserted() && !irq_disabled {
+            self.opcode = 0x00;
+            self.interrupt(0xFFFE);
+            self.cycles = 7;
+            true
+        } else {
+            let disabled = self.get_flag(Flag::InterruptDisable);
+            let cached = match self.engine {
+                Engine::Cached => self.execute_cached(),
+                _ => None,
+            };
+            let (cycles, addr_cycles) = match cached {
+                Some(cycles) => cycles,
+                None => {
+                    let opcode = self.read_code(self.pc);
+                    self.opcode = opcode;
+                    self.pc = self.pc.wrapping_add(1);
+                    let instruction = self.instruction(opcode);
+                    let addr_cycles = (instruction.addr_mode)(self);
+                    (instruction.op)(self);
+                    (instruction.cycles, addr_cycles)
+                }
+            };
+            let opcode = self.opcode;
+
+            // Taken branches add their extra cycles to `cycles` themselves
+            self.cycles += cycles;
+            if OPCODES[opcode as usize].penalty == PagePenalty::PageCross {
+                self.cycles += addr_cycles;
+            }
+
+            // CLI, SEI, PLP
+            if matches!(opcode, 0x58 | 0x78 | 0x28) {
+                self.irq_poll_disabled = Some(disabled);
+            }
+            false
+        };
+        self.instr_cycles = self.cycles;
+        interrupted
+    }
+
+    /// Whether anything looks at each instruction as it runs
+    #[inline]
+    fn watched(&self) -> bool {
+        self.rewind.is_some()
+            || self.profiler.is_some()
+            || self.stack_checker.is_some()
+            || self.memory.heatmap.is_some()
+            || self.memory.uninit.is_some()
+    }
+
+    /// `start`, with the undo log, observers and uninitialized RAM check
+    /// around it
+    fn start_watched(&mut self) {
+        let (start, sp) = (self.pc, self.sp);
+        let undo = self.begin_undo();
+        let interrupted = self.start();
+
+        // A trapped opcode never ran, so nothing should count it
+        if !matches!(self.halted, Some(CpuError::IllegalOpcode { .. })) {
+            self.observe(start, sp, interrupted);
+        }
+        if let Some(registers) = undo {
+            self.end_undo(registers);
+        }
+        if let Some(uninit) = self.memory.uninit.as_deref_mut() {
+            match uninit.finish(start) {
+                Some(read) if uninit.mode == UninitMode::Stop => {
+                    self.halted = Some(CpuError::UninitializedRead {
+                        pc: read.pc,
+                        addr: read.addr,
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The part of a clock that happens whatever the CPU is doing: devices
+    /// keep running and the NMI and SO inputs are sampled, even while RDY
+    /// stalls the CPU
+    #[inline]
+    fn cycle(&mut self) {
+        self.memory.tick();
+
+        let nmi_level = self.nmi || self.memory.nmi();
+        if nmi_level && !self.nmi_level {
+            self.nmi_pending = true;
+        }
+        self.nmi_level = nmi_level;
+
+        if self.so_edge {
+            self.set_flag(Flag::Overflow, true);
+            self.so_edge = false;
+        }
+    }
+
+    /// Tells the heatmap, profiler and stack checker about the instruction
+    /// (or interrupt sequence) that started at `start` with SP at `sp`
+    fn observe(&mut self, start: u16, sp: u8, interrupted: bool) {
+        if !interrupted {
+            let bytes = OPCODES[self.opcode as usize].bytes;
+            self.memory.executed(start, bytes);
+        }
+        if let Some(profiler) = self.profiler.as_deref_mut() {
+            if interrupted {
+                profiler.interrupt(self.cycles, sp, self.pc);
+            } else {
+                let sp = (sp, self.sp);
+                profiler.instruction(start, self.opcode, self.cycles, sp, self.pc);
+            }
+        }
+        if let Some(checker) = self.stack_checker.as_deref_mut() {
+            let sp = (sp, self.sp);
+            if interrupted {
+                checker.interrupt(start, sp);
+            } else {
+                checker.instruction(start, self.opcode, sp, self.pc);
+            }
+        }
+    }
+
+    /// I flag as seen by the IRQ poll at the end of the last instruction
+    fn irq_disabled_at_poll(&mut self) -> bool {
+        self.irq_poll_disabled
+            .take()
+            .unwrap_or(self.get_flag(Flag::InterruptDisable))
+    }
 }
 
 impl Default for Cpu {
@@ -235,8 +687,25 @@ impl Default for Cpu {
             addr_abs: 0,
             addr_rel: 0,
             fetched: 0,
-            memory: [0; 0x10000],
-            instruction_table: build_instruction_table(),
+            opcode: 0,
+            instr_cycles: 0,
+            memory: Bus::new(),
+            rdy: true,
+            so: true,
+            so_edge: false,
+            irq: false,
+            nmi: false,
+            nmi_level: false,
+            nmi_pending: false,
+            irq_poll_disabled: None,
+            halted: None,
+            engine: Engine::Table,
+            variant: Variant::Nmos6502,
+            illegal_policy: [IllegalPolicy::Emulate; 4],
+            blocks: BlockCache::default(),
+            profiler: None,
+            stack_checker: None,
+            rewind: None,
         }
     }
 }
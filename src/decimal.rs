@@ -0,0 +1,72 @@
+use crate::cpu::{Cpu, Flag};
+
+// Information grabbed from http://www.6502.org/tutorials/decimal_mode.html
+
+impl Cpu {
+    /// Whether ADC and SBC (and the undocumented opcodes built on them)
+    /// work in BCD
+    pub(crate) fn decimal(&self) -> bool {
+        self.get_flag(Flag::Decimal) && self.variant.has_decimal_mode()
+    }
+
+    /// ADC in decimal mode, the NMOS way: Z comes from the binary sum, N
+    /// and V from the sum before the high digit is adjusted
+    pub(crate) fn add_decimal(&mut self, m: u8) {
+        let a = self.a;
+        let carry_in = self.get_flag(Flag::Carry) as u16;
+
+        let mut lo = (a & 0x0F) as u16 + (m & 0x0F) as u16 + carry_in;
+        if lo >= 0x0A {
+            lo = ((lo + 0x06) & 0x0F) + 0x10;
+        }
+        let mut sum = (a & 0xF0) as u16 + (m & 0xF0) as u16 + lo;
+        let signed = (a & 0xF0) as i8 as i16 + (m & 0xF0) as i8 as i16 + lo as i16;
+
+        let binary = a.wrapping_add(m).wrapping_add(carry_in as u8);
+        self.set_flag(Flag::Zero, binary == 0);
+        self.set_flag(Flag::Negative, sum & 0x80 != 0);
+        self.set_flag(Flag::Overflow, !(-128..=127).contains(&signed));
+        if sum >= 0xA0 {
+            sum += 0x60;
+        }
+        self.set_flag(Flag::Carry, sum > 0xFF);
+
+        self.a = sum as u8;
+    }
+
+    /// What SBC leaves in A in decimal mode. The flags are the binary ones.
+    pub(crate) fn subtract_decimal(a: u8, m: u8, carry_in: bool) -> u8 {
+        let (a, m, borrow) = (a as i16, m as i16, !carry_in as i16);
+        let mut lo = (a & 0x0F) - (m & 0x0F) - borrow;
+        if lo < 0 {
+            lo = ((lo - 0x06) & 0x0F) - 0x10;
+        }
+        let mut result = (a & 0xF0) - (m & 0xF0) + lo;
+        if result < 0 {
+            result -= 0x60;
+        }
+        result as u8
+    }
+
+    /// ARR in decimal mode: N, Z and V from the rotate, then each digit
+    /// fixed up when it came out of the AND above 5
+    pub(crate) fn arr_decimal(&mut self) {
+        self.fetch();
+        let and = self.a & self.fetched;
+        let carry_in = self.get_flag(Flag::Carry) as u8;
+        let mut res = and >> 1 | carry_in << 7;
+        self.set_flag(Flag::Negative, carry_in != 0);
+        self.set_flag(Flag::Zero, res == 0);
+        self.set_flag(Flag::Overflow, (res ^ and) & 0x40 != 0);
+
+        if (and & 0x0F) + (and & 0x01) > 0x05 {
+            res = (res & 0xF0) | (res.wrapping_add(0x06) & 0x0F);
+        }
+        let high = (and & 0xF0) as u16 + (and & 0x10) as u16 > 0x50;
+        if high {
+            res = (res & 0x0F) | (res.wrapping_add(0x60) & 0xF0);
+        }
+        self.set_flag(Flag::Carry, high);
+        self.a = res;
+    }
+}
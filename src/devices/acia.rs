@@ -0,0 +1,185 @@
+use crate::{bus::Device, devices::serial::SerialHost};
+
+// Information grabbed from the MOS 6551 datasheet
+
+/// Register select values (RS1-RS0)
+pub const DATA: u16 = 0x0;
+pub const STATUS: u16 = 0x1;
+pub const COMMAND: u16 = 0x2;
+pub const CONTROL: u16 = 0x3;
+
+/// Status register bits
+pub const STATUS_PARITY_ERROR: u8 = 0x01;
+pub const STATUS_FRAMING_ERROR: u8 = 0x02;
+pub const STATUS_OVERRUN: u8 = 0x04;
+pub const STATUS_RDRF: u8 = 0x08;
+pub const STATUS_TDRE: u8 = 0x10;
+pub const STATUS_DCD: u8 = 0x20;
+pub const STATUS_DSR: u8 = 0x40;
+pub const STATUS_IRQ: u8 = 0x80;
+
+/// Baud rates selected by control register bits 0-3.
+/// 0 is the 16x external clock, which runs as fast as the host allows.
+const BAUD_RATES: [u32; 16] = [
+    0, 50, 75, 110, 135, 150, 300, 600, 1200, 1800, 2400, 3600, 4800, 7200, 9600, 19200,
+];
+
+/// MOS 6551 Asynchronous Communications Interface Adapter.
+/// Mapped on the bus with its 4 registers (use a mirror mask of 0x0003).
+/// The serial line goes to a `SerialHost` and the IRQ output to the `Cpu`.
+pub struct Acia {
+    host: Box<dyn SerialHost>,
+    pub status: u8,
+    pub command: u8,
+    pub control: u8,
+    /// Received byte (receiver data register)
+    pub rx_data: u8,
+    /// Byte being sent (transmitter data register)
+    tx_data: u8,
+    /// Cycles until the byte in `tx_data` has gone out, 0 when idle
+    tx_cycles: u32,
+    /// Cycles until the receiver looks at the host again
+    rx_cycles: u32,
+    /// CPU clock, used to time characters at the programmed baud rate
+    pub clock_hz: u32,
+}
+
+impl Acia {
+    pub fn new(host: impl SerialHost + 'static) -> Self {
+        Acia {
+            host: Box::new(host),
+            status: STATUS_TDRE,
+            command: 0,
+            control: 0,
+            rx_data: 0,
+            tx_data: 0,
+            tx_cycles: 0,
+            rx_cycles: 0,
+            clock_hz: 1_000_000,
+        }
+    }
+
+    /// IRQ output (true = asserted)
+    pub fn irq(&self) -> bool {
+        self.status & STATUS_IRQ != 0
+    }
+
+    /// DTR low disables the receiver and all interrupts
+    fn enabled(&self) -> bool {
+        self.command & 0x01 != 0
+    }
+
+    fn rx_irq_enabled(&self) -> bool {
+        self.enabled() && self.command & 0x02 == 0
+    }
+
+    fn tx_irq_enabled(&self) -> bool {
+        self.enabled() && self.command & 0x0C == 0x04
+    }
+
+    fn echo(&self) -> bool {
+        self.command & 0x10 != 0
+    }
+
+    /// CPU cycles to send or receive one character with the current settings
+    fn char_cycles(&self) -> u32 {
+        let baud = BAUD_RATES[(self.control & 0x0F) as usize];
+        if baud == 0 {
+            return 1;
+        }
+
+        let data_bits = 8 - ((self.control >> 5) & 0x03) as u32;
+        let parity = if self.command & 0x20 != 0 { 1 } else { 0 };
+        let stop_bits = if self.control & 0x80 != 0 { 2 } else { 1 };
+        let bits = 1 + data_bits + parity + stop_bits;
+
+        (self.clock_hz / baud * bits).max(1)
+    }
+
+    fn update_irq(&mut self) {
+        let rx = self.rx_irq_enabled() && self.status & STATUS_RDRF != 0;
+        let tx = self.tx_irq_enabled() && self.status & STATUS_TDRE != 0;
+        if rx || tx {
+            self.status |= STATUS_IRQ;
+        }
+    }
+
+    /// Masks a received byte down to the programmed word length
+    fn word_mask(&self) -> u8 {
+        0xFF >> ((self.control >> 5) & 0x03)
+    }
+}
+
+impl Device for Acia {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr & 0x03 {
+            DATA => {
+                self.status &=
+                    !(STATUS_RDRF | STATUS_OVERRUN | STATUS_FRAMING_ERROR | STATUS_PARITY_ERROR);
+                self.rx_data
+            }
+            STATUS => {
+                // Reading the status acknowledges the interrupt
+                let value = self.status;
+                self.status &= !STATUS_IRQ;
+                value
+            }
+            COMMAND => self.command,
+            CONTROL => self.control,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0x03 {
+            DATA => {
+                self.tx_data = value;
+                self.status &= !STATUS_TDRE;
+                self.tx_cycles = self.char_cycles();
+            }
+            STATUS => {
+                // Programmed reset: clears command bits 0-4 and the overrun flag
+                self.command &= 0xE0;
+                self.status &= !STATUS_OVERRUN;
+            }
+            COMMAND => self.command = value,
+            CONTROL => self.control = value,
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.tx_cycles > 0 {
+            self.tx_cycles -= 1;
+            if self.tx_cycles == 0 {
+                self.host.transmit(self.tx_data);
+                self.status |= STATUS_TDRE;
+                self.update_irq();
+            }
+        }
+
+        if !self.enabled() {
+            return;
+        }
+
+        self.rx_cycles = self.rx_cycles.saturating_sub(1);
+
+        // Host bytes wait (as if held off by RTS) until the CPU has read
+        // the previous one, so nothing is lost to overruns
+        if self.rx_cycles == 0 && self.status & STATUS_RDRF == 0 {
+            if let Some(byte) = self.host.receive() {
+                self.rx_data = byte & self.word_mask();
+                self.status |= STATUS_RDRF;
+                if self.echo() {
+                    self.host.transmit(self.rx_data);
+                }
+                self.update_irq();
+            }
+            self.rx_cycles = self.char_cycles();
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.irq()
+    }
+}
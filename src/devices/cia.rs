@@ -0,0 +1,568 @@
+use crate::bus::Device;
+
+// Information grabbed from the MOS 6526 datasheet and https://www.c64-wiki.com/wiki/CIA
+
+/// Register select values (RS3-RS0)
+pub const PRA: u16 = 0x0;
+pub const PRB: u16 = 0x1;
+pub const DDRA: u16 = 0x2;
+pub const DDRB: u16 = 0x3;
+pub const TA_LO: u16 = 0x4;
+pub const TA_HI: u16 = 0x5;
+pub const TB_LO: u16 = 0x6;
+pub const TB_HI: u16 = 0x7;
+pub const TOD_10THS: u16 = 0x8;
+pub const TOD_SEC: u16 = 0x9;
+pub const TOD_MIN: u16 = 0xA;
+pub const TOD_HR: u16 = 0xB;
+pub const SDR: u16 = 0xC;
+pub const ICR: u16 = 0xD;
+pub const CRA: u16 = 0xE;
+pub const CRB: u16 = 0xF;
+
+/// Interrupt control bits
+pub enum CiaInterrupt {
+    TimerA = 0,
+    TimerB = 1,
+    Alarm = 2,
+    SerialPort = 3,
+    Flag = 4,
+}
+
+/// Control register bits shared by CRA and CRB
+const CR_START: u8 = 0x01;
+const CR_PB_ON: u8 = 0x02;
+const CR_TOGGLE: u8 = 0x04;
+const CR_ONE_SHOT: u8 = 0x08;
+const CR_LOAD: u8 = 0x10;
+
+/// One of the two 16-bit interval timers
+#[derive(Default)]
+pub struct Timer {
+    pub counter: u16,
+    pub latch: u16,
+    /// CRA/CRB, without the force load strobe
+    pub control: u8,
+    /// PB6/PB7 level when the timer drives it
+    pb_toggle: bool,
+    /// PB6/PB7 pulse output is high for this cycle
+    pb_pulse: bool,
+}
+
+impl Timer {
+    fn running(&self) -> bool {
+        self.control & CR_START != 0
+    }
+
+    /// Counts one pulse, returns true on underflow.
+    /// The counter runs N, N-1, .., 0 and reloads, so the period is N+1.
+    fn count(&mut self) -> bool {
+        if self.counter == 0 {
+            self.counter = self.latch;
+            self.pb_toggle = !self.pb_toggle;
+            self.pb_pulse = true;
+            if self.control & CR_ONE_SHOT != 0 {
+                self.control &= !CR_START;
+            }
+            return true;
+        }
+
+        self.counter -= 1;
+        false
+    }
+
+    fn write_control(&mut self, value: u8) {
+        if value & CR_LOAD != 0 {
+            self.counter = self.latch;
+        }
+        if value & CR_START != 0 && !self.running() {
+            self.pb_toggle = true;
+        }
+        self.control = value & !CR_LOAD;
+    }
+
+    fn write_hi(&mut self, value: u8) {
+        self.latch = (self.latch & 0x00FF) | (value as u16) << 8;
+        // A stopped timer picks the new latch value up straight away
+        if !self.running() {
+            self.counter = self.latch;
+        }
+    }
+
+    /// Level of PB6/PB7 when CR bit 1 hands the pin to the timer
+    fn pb_output(&self) -> Option<bool> {
+        if self.control & CR_PB_ON == 0 {
+            return None;
+        }
+        Some(if self.control & CR_TOGGLE != 0 {
+            self.pb_toggle
+        } else {
+            self.pb_pulse
+        })
+    }
+}
+
+/// Time of day clock, all fields BCD. Hours are 1-12 with bit 7 as PM.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Tod {
+    pub tenths: u8,
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+}
+
+impl Tod {
+    fn bcd_inc(value: u8) -> u8 {
+        if value & 0x0F == 9 {
+            (value & 0xF0) + 0x10
+        } else {
+            value + 1
+        }
+    }
+
+    fn advance(&mut self) {
+        self.tenths = (self.tenths + 1) % 10;
+        if self.tenths != 0 {
+            return;
+        }
+
+        self.seconds = Self::bcd_inc(self.seconds);
+        if self.seconds != 0x60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = Self::bcd_inc(self.minutes);
+        if self.minutes != 0x60 {
+            return;
+        }
+        self.minutes = 0;
+
+        let pm = self.hours & 0x80;
+        let hours = self.hours & 0x1F;
+        self.hours = match hours {
+            0x11 => 0x12 | (pm ^ 0x80),
+            0x12 => 0x01 | pm,
+            _ => Self::bcd_inc(hours) | pm,
+        };
+    }
+}
+
+/// MOS 6526 Complex Interface Adapter.
+/// Mapped on the bus with its 16 registers (use a mirror mask of 0x000F) and
+/// clocked once per CPU cycle. Its interrupt output is wired to IRQ or NMI
+/// with `Bus::connect` (CIA1 and CIA2 on the C64 respectively).
+pub struct Cia {
+    pub pra: u8,
+    pub prb: u8,
+    pub ddra: u8,
+    pub ddrb: u8,
+    /// Levels driven onto the port pins from outside (pulled up when idle)
+    port_a_pins: u8,
+    port_b_pins: u8,
+    /// Keyboard matrix wired between the ports: bit `row` of `matrix[col]`
+    /// is set while the key connecting PA`col` and PB`row` is held down
+    pub matrix: [u8; 8],
+
+    pub timer_a: Timer,
+    pub timer_b: Timer,
+
+    pub tod: Tod,
+    pub alarm: Tod,
+    /// Registers frozen by reading the hours, until the tenths are read
+    tod_latch: Option<Tod>,
+    /// Clock stopped by writing the hours, until the tenths are written
+    tod_halted: bool,
+    /// CPU cycles per pulse on the TOD pin (50 or 60Hz mains), 0 disables it
+    pub tod_pin_period: u32,
+    tod_pin_cycles: u32,
+    /// TOD pin pulses since the last tenth of a second
+    tod_pulses: u8,
+
+    pub sdr: u8,
+    /// Shift register behind SDR
+    shift: u8,
+    shift_bits: u8,
+    /// A byte written to SDR is waiting to be shifted out
+    sdr_loaded: bool,
+    shifting_out: bool,
+    /// Serial output is halfway through a bit (CNT low)
+    serial_half: bool,
+    cnt: bool,
+    sp: bool,
+
+    /// ICR data (flags) and mask
+    pub icr_flags: u8,
+    pub icr_mask: u8,
+    flag_pin: bool,
+}
+
+impl Cia {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Presses or releases the key at column `col` (PA), row `row` (PB)
+    pub fn set_key(&mut self, col: u8, row: u8, pressed: bool) {
+        if pressed {
+            self.matrix[col as usize] |= 1 << row;
+        } else {
+            self.matrix[col as usize] &= !(1 << row);
+        }
+    }
+
+    pub fn release_all_keys(&mut self) {
+        self.matrix = [0; 8];
+    }
+
+    /// Drives the port A pins from outside (e.g. a joystick), idle is $FF
+    pub fn set_port_a(&mut self, pins: u8) {
+        self.port_a_pins = pins;
+    }
+
+    /// Drives the port B pins from outside, idle is $FF
+    pub fn set_port_b(&mut self, pins: u8) {
+        self.port_b_pins = pins;
+    }
+
+    /// Pin levels of port A, including keys pulling columns low
+    pub fn port_a(&self) -> u8 {
+        let driven_a = self.driven_a();
+        let driven_b = self.driven_b();
+
+        let mut value = driven_a;
+        for col in 0..8 {
+            if self.matrix[col] & !driven_b != 0 {
+                value &= !(1 << col);
+            }
+        }
+        value
+    }
+
+    /// Pin levels of port B, including keys pulling rows low
+    pub fn port_b(&self) -> u8 {
+        let driven_a = self.driven_a();
+        let mut value = self.driven_b();
+
+        for col in 0..8 {
+            if driven_a & (1 << col) == 0 {
+                value &= !self.matrix[col];
+            }
+        }
+
+        if let Some(level) = self.timer_a.pb_output() {
+            value = (value & !0x40) | if level { 0x40 } else { 0 };
+        }
+        if let Some(level) = self.timer_b.pb_output() {
+            value = (value & !0x80) | if level { 0x80 } else { 0 };
+        }
+        value
+    }
+
+    /// Outputs drive their register bit, inputs are pulled up
+    fn driven_a(&self) -> u8 {
+        ((self.pra & self.ddra) | !self.ddra) & self.port_a_pins
+    }
+
+    fn driven_b(&self) -> u8 {
+        ((self.prb & self.ddrb) | !self.ddrb) & self.port_b_pins
+    }
+
+    /// FLAG input, a negative edge raises the FLAG interrupt
+    pub fn set_flag_pin(&mut self, level: bool) {
+        if self.flag_pin && !level {
+            self.set_icr(CiaInterrupt::Flag);
+        }
+        self.flag_pin = level;
+    }
+
+    /// CNT input: counts for timers in CNT mode and clocks serial input
+    pub fn set_cnt(&mut self, level: bool) {
+        let rising = !self.cnt && level;
+        self.cnt = level;
+        if !rising {
+            return;
+        }
+
+        if self.timer_a.running() && self.timer_a.control & 0x20 != 0 && self.timer_a.count() {
+            self.timer_a_underflow();
+        }
+        if self.timer_b.running() && self.timer_b.control & 0x60 == 0x20 && self.timer_b.count() {
+            self.set_icr(CiaInterrupt::TimerB);
+        }
+
+        // Serial input: a bit from SP on every rising CNT edge
+        if self.timer_a.control & 0x40 == 0 {
+            self.shift = (self.shift << 1) | self.sp as u8;
+            self.shift_bits += 1;
+            if self.shift_bits == 8 {
+                self.sdr = self.shift;
+                self.shift_bits = 0;
+                self.set_icr(CiaInterrupt::SerialPort);
+            }
+        }
+    }
+
+    /// SP input, sampled on rising CNT edges when the serial port is an input
+    pub fn set_sp(&mut self, level: bool) {
+        if self.timer_a.control & 0x40 == 0 {
+            self.sp = level;
+        }
+    }
+
+    pub fn cnt(&self) -> bool {
+        self.cnt
+    }
+
+    pub fn sp(&self) -> bool {
+        self.sp
+    }
+
+    /// Interrupt output (true = asserted)
+    pub fn interrupt_asserted(&self) -> bool {
+        self.icr_flags & self.icr_mask & 0x1F != 0
+    }
+
+    /// Pulses the TOD pin once, for hosts that drive it from a real 50/60Hz source
+    pub fn pulse_tod(&mut self) {
+        // CRA bit 7 selects 50Hz (divide by 5) or 60Hz (divide by 6)
+        let divider = if self.timer_a.control & 0x80 != 0 { 5 } else { 6 };
+        self.tod_pulses += 1;
+        if self.tod_pulses < divider {
+            return;
+        }
+        self.tod_pulses = 0;
+
+        if self.tod_halted {
+            return;
+        }
+        self.tod.advance();
+        if self.tod == self.alarm {
+            self.set_icr(CiaInterrupt::Alarm);
+        }
+    }
+
+    fn set_icr(&mut self, flag: CiaInterrupt) {
+        self.icr_flags |= 1 << flag as u8;
+    }
+
+    fn timer_a_underflow(&mut self) {
+        self.set_icr(CiaInterrupt::TimerA);
+
+        // Serial output runs at half the timer A underflow rate
+        if self.timer_a.control & 0x40 != 0 {
+            if !self.shifting_out && self.sdr_loaded {
+                self.shift = self.sdr;
+                self.sdr_loaded = false;
+                self.shifting_out = true;
+                self.shift_bits = 0;
+                self.serial_half = false;
+            } else if self.shifting_out {
+                self.serial_half = !self.serial_half;
+                if self.serial_half {
+                    // CNT low, next bit on SP
+                    self.cnt = false;
+                    self.sp = self.shift & 0x80 != 0;
+                    self.shift <<= 1;
+                } else {
+                    self.cnt = true;
+                    self.shift_bits += 1;
+                    if self.shift_bits == 8 {
+                        self.shifting_out = false;
+                        self.set_icr(CiaInterrupt::SerialPort);
+                    }
+                }
+            }
+        }
+
+        // Timer B can count timer A underflows (optionally gated by CNT)
+        if self.timer_b.running() {
+            let counts = match self.timer_b.control & 0x60 {
+                0x40 => true,
+                0x60 => self.cnt,
+                _ => false,
+            };
+            if counts && self.timer_b.count() {
+                self.set_icr(CiaInterrupt::TimerB);
+            }
+        }
+    }
+
+    fn tod_register(&self, addr: u16) -> u8 {
+        let tod = self.tod_latch.unwrap_or(self.tod);
+        match addr {
+            TOD_10THS => tod.tenths,
+            TOD_SEC => tod.seconds,
+            TOD_MIN => tod.minutes,
+            _ => tod.hours,
+        }
+    }
+
+    fn write_tod(&mut self, addr: u16, value: u8) {
+        // CRB bit 7 redirects writes to the alarm
+        let target = if self.timer_b.control & 0x80 != 0 {
+            &mut self.alarm
+        } else {
+            &mut self.tod
+        };
+        match addr {
+            TOD_10THS => target.tenths = value & 0x0F,
+            TOD_SEC => target.seconds = value & 0x7F,
+            TOD_MIN => target.minutes = value & 0x7F,
+            _ => target.hours = value & 0x9F,
+        }
+
+        if self.timer_b.control & 0x80 == 0 {
+            match addr {
+                TOD_HR => self.tod_halted = true,
+                TOD_10THS => self.tod_halted = false,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Device for Cia {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr & 0x0F {
+            PRA => self.port_a(),
+            PRB => self.port_b(),
+            DDRA => self.ddra,
+            DDRB => self.ddrb,
+            TA_LO => self.timer_a.counter as u8,
+            TA_HI => (self.timer_a.counter >> 8) as u8,
+            TB_LO => self.timer_b.counter as u8,
+            TB_HI => (self.timer_b.counter >> 8) as u8,
+            TOD_HR => {
+                // Reading the hours freezes the registers until the tenths are read
+                if self.tod_latch.is_none() {
+                    self.tod_latch = Some(self.tod);
+                }
+                self.tod_register(TOD_HR)
+            }
+            TOD_10THS => {
+                let value = self.tod_register(TOD_10THS);
+                self.tod_latch = None;
+                value
+            }
+            reg @ (TOD_SEC | TOD_MIN) => self.tod_register(reg),
+            SDR => self.sdr,
+            ICR => {
+                // Reading acknowledges every interrupt
+                let value = self.icr_flags | if self.interrupt_asserted() { 0x80 } else { 0 };
+                self.icr_flags = 0;
+                value
+            }
+            CRA => self.timer_a.control,
+            CRB => self.timer_b.control,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0x0F {
+            PRA => self.pra = value,
+            PRB => self.prb = value,
+            DDRA => self.ddra = value,
+            DDRB => self.ddrb = value,
+            TA_LO => self.timer_a.latch = (self.timer_a.latch & 0xFF00) | value as u16,
+            TA_HI => self.timer_a.write_hi(value),
+            TB_LO => self.timer_b.latch = (self.timer_b.latch & 0xFF00) | value as u16,
+            TB_HI => self.timer_b.write_hi(value),
+            reg @ (TOD_10THS | TOD_SEC | TOD_MIN | TOD_HR) => self.write_tod(reg, value),
+            SDR => {
+                self.sdr = value;
+                self.sdr_loaded = true;
+            }
+            ICR => {
+                if value & 0x80 != 0 {
+                    self.icr_mask |= value & 0x1F;
+                } else {
+                    self.icr_mask &= !(value & 0x1F);
+                }
+            }
+            CRA => {
+                self.timer_a.write_control(value);
+                // Switching the serial port to input releases SP and CNT
+                if value & 0x40 == 0 {
+                    self.shifting_out = false;
+                    self.cnt = true;
+                }
+            }
+            CRB => self.timer_b.write_control(value),
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.timer_a.pb_pulse = false;
+        self.timer_b.pb_pulse = false;
+
+        // Timer A in phase 2 mode (CRA bit 5 clear)
+        if self.timer_a.running() && self.timer_a.control & 0x20 == 0 && self.timer_a.count() {
+            self.timer_a_underflow();
+        }
+        // Timer B in phase 2 mode (CRB bits 5-6 clear)
+        if self.timer_b.running() && self.timer_b.control & 0x60 == 0 && self.timer_b.count() {
+            self.set_icr(CiaInterrupt::TimerB);
+        }
+
+        if self.tod_pin_period != 0 {
+            self.tod_pin_cycles += 1;
+            if self.tod_pin_cycles >= self.tod_pin_period {
+                self.tod_pin_cycles = 0;
+                self.pulse_tod();
+            }
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.interrupt_asserted()
+    }
+}
+
+impl Default for Cia {
+    fn default() -> Self {
+        Cia {
+            pra: 0,
+            prb: 0,
+            ddra: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            matrix: [0; 8],
+            timer_a: Timer {
+                counter: 0xFFFF,
+                latch: 0xFFFF,
+                ..Timer::default()
+            },
+            timer_b: Timer {
+                counter: 0xFFFF,
+                latch: 0xFFFF,
+                ..Timer::default()
+            },
+            tod: Tod {
+                hours: 0x01,
+                ..Tod::default()
+            },
+            alarm: Tod::default(),
+            tod_latch: None,
+            tod_halted: false,
+            // PAL C64: 985248Hz CPU, 50Hz mains
+            tod_pin_period: 985_248 / 50,
+            tod_pin_cycles: 0,
+            tod_pulses: 0,
+            sdr: 0,
+            shift: 0,
+            shift_bits: 0,
+            sdr_loaded: false,
+            shifting_out: false,
+            serial_half: false,
+            cnt: true,
+            sp: true,
+            icr_flags: 0,
+            icr_mask: 0,
+            flag_pin: true,
+        }
+    }
+}
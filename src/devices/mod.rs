@@ -0,0 +1,11 @@
+// Peripheral chips that plug into the `Bus`
+pub mod acia;
+pub mod cia;
+pub mod pia;
+pub mod riot;
+pub mod rriot;
+pub mod serial;
+pub mod sid;
+pub mod timer;
+pub mod via;
+pub mod vic;
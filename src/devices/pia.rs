@@ -0,0 +1,245 @@
+use crate::bus::Device;
+
+// Information grabbed from the Motorola MC6821 datasheet
+
+/// Register select values (RS1-RS0)
+pub const PORT_A: u16 = 0x0;
+pub const CONTROL_A: u16 = 0x1;
+pub const PORT_B: u16 = 0x2;
+pub const CONTROL_B: u16 = 0x3;
+
+/// Control register bits
+pub const CR_C1_IRQ_ENABLE: u8 = 0x01;
+pub const CR_C1_POSITIVE: u8 = 0x02;
+pub const CR_OUTPUT_REGISTER: u8 = 0x04;
+pub const CR_IRQ2: u8 = 0x40;
+pub const CR_IRQ1: u8 = 0x80;
+
+/// One half of the PIA: a port with its two control lines
+#[derive(Default)]
+pub struct PiaPort {
+    pub output: u8,
+    pub ddr: u8,
+    pub control: u8,
+    /// Levels driven onto the port pins from outside
+    pins: u8,
+    c1: bool,
+    c2: bool,
+    /// A one cycle C2 strobe is in progress
+    c2_pulse: bool,
+}
+
+impl PiaPort {
+    fn new() -> Self {
+        PiaPort {
+            pins: 0xFF,
+            c1: true,
+            c2: true,
+            ..Default::default()
+        }
+    }
+
+    fn c2_output(&self) -> bool {
+        self.control & 0x20 != 0
+    }
+
+    fn irq(&self) -> bool {
+        (self.control & CR_IRQ1 != 0 && self.control & CR_C1_IRQ_ENABLE != 0)
+            || (self.control & CR_IRQ2 != 0 && self.control & 0x08 != 0 && !self.c2_output())
+    }
+
+    fn write_control(&mut self, value: u8) {
+        // The interrupt flags are read only
+        self.control = (self.control & 0xC0) | (value & 0x3F);
+        // Manual output mode drives C2 from bit 3
+        if self.control & 0x30 == 0x30 {
+            self.c2 = self.control & 0x08 != 0;
+        }
+    }
+
+    /// Start of a strobe after a data access (read for A, write for B)
+    fn strobe(&mut self) {
+        match self.control & 0x38 {
+            // Handshake: low until the next C1 active transition
+            0x20 => self.c2 = false,
+            // Pulse: low for one cycle
+            0x28 => {
+                self.c2 = false;
+                self.c2_pulse = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_c1(&mut self, level: bool) {
+        let old = self.c1;
+        self.c1 = level;
+        let active = if self.control & CR_C1_POSITIVE != 0 {
+            !old && level
+        } else {
+            old && !level
+        };
+        if active {
+            self.control |= CR_IRQ1;
+            if self.control & 0x38 == 0x20 {
+                self.c2 = true;
+            }
+        }
+    }
+
+    fn set_c2(&mut self, level: bool) {
+        if self.c2_output() {
+            return;
+        }
+        let old = self.c2;
+        self.c2 = level;
+        let active = if self.control & 0x10 != 0 {
+            !old && level
+        } else {
+            old && !level
+        };
+        if active {
+            self.control |= CR_IRQ2;
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.c2_pulse {
+            self.c2_pulse = false;
+        } else if self.control & 0x38 == 0x28 {
+            self.c2 = true;
+        }
+    }
+}
+
+/// Motorola 6821 Peripheral Interface Adapter.
+/// Mapped on the bus with its 4 registers (use a mirror mask of 0x0003),
+/// with IRQA and IRQB wire-ORed onto the device's interrupt output.
+pub struct Pia {
+    pub a: PiaPort,
+    pub b: PiaPort,
+}
+
+impl Pia {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin levels of port A as seen from outside
+    pub fn port_a(&self) -> u8 {
+        (self.a.output & self.a.ddr) | (self.a.pins & !self.a.ddr)
+    }
+
+    /// Pin levels of port B as seen from outside
+    pub fn port_b(&self) -> u8 {
+        (self.b.output & self.b.ddr) | (self.b.pins & !self.b.ddr)
+    }
+
+    pub fn set_port_a(&mut self, pins: u8) {
+        self.a.pins = pins;
+    }
+
+    pub fn set_port_b(&mut self, pins: u8) {
+        self.b.pins = pins;
+    }
+
+    pub fn set_ca1(&mut self, level: bool) {
+        self.a.set_c1(level);
+    }
+
+    pub fn set_ca2(&mut self, level: bool) {
+        self.a.set_c2(level);
+    }
+
+    pub fn set_cb1(&mut self, level: bool) {
+        self.b.set_c1(level);
+    }
+
+    pub fn set_cb2(&mut self, level: bool) {
+        self.b.set_c2(level);
+    }
+
+    pub fn ca2(&self) -> bool {
+        self.a.c2
+    }
+
+    pub fn cb2(&self) -> bool {
+        self.b.c2
+    }
+
+    pub fn irqa(&self) -> bool {
+        self.a.irq()
+    }
+
+    pub fn irqb(&self) -> bool {
+        self.b.irq()
+    }
+}
+
+impl Device for Pia {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr & 0x03 {
+            PORT_A => {
+                if self.a.control & CR_OUTPUT_REGISTER == 0 {
+                    return self.a.ddr;
+                }
+                // Reading the data clears both flags and strobes CA2
+                self.a.control &= !(CR_IRQ1 | CR_IRQ2);
+                self.a.strobe();
+                self.port_a()
+            }
+            CONTROL_A => self.a.control,
+            PORT_B => {
+                if self.b.control & CR_OUTPUT_REGISTER == 0 {
+                    return self.b.ddr;
+                }
+                self.b.control &= !(CR_IRQ1 | CR_IRQ2);
+                self.port_b()
+            }
+            CONTROL_B => self.b.control,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0x03 {
+            PORT_A => {
+                if self.a.control & CR_OUTPUT_REGISTER == 0 {
+                    self.a.ddr = value;
+                } else {
+                    self.a.output = value;
+                }
+            }
+            CONTROL_A => self.a.write_control(value),
+            PORT_B => {
+                if self.b.control & CR_OUTPUT_REGISTER == 0 {
+                    self.b.ddr = value;
+                } else {
+                    // CB2 strobes on writes rather than reads
+                    self.b.output = value;
+                    self.b.strobe();
+                }
+            }
+            CONTROL_B => self.b.write_control(value),
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.a.tick();
+        self.b.tick();
+    }
+
+    fn interrupt(&self) -> bool {
+        self.irqa() || self.irqb()
+    }
+}
+
+impl Default for Pia {
+    fn default() -> Self {
+        Pia {
+            a: PiaPort::new(),
+            b: PiaPort::new(),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{bus::Device, devices::timer::IntervalTimer};
+use std::{cell::RefCell, rc::Rc};
+
+// Information grabbed from the MOS 6532 datasheet and the Stella programmer's guide
+
+/// Interrupt flag register bits
+pub const FLAG_TIMER: u8 = 0x80;
+pub const FLAG_PA7: u8 = 0x40;
+
+/// MOS 6532 RAM-I/O-Timer.
+/// The `Riot` itself is the I/O and timer side (mapped with a mirror mask of
+/// 0x001F, address bits A0-A4 select the register). Its 128 bytes of RAM are
+/// usually decoded elsewhere by the RS pin, map them with `RiotRam`.
+/// Clocked once per CPU cycle, the IRQ output goes to the `Cpu`.
+pub struct Riot {
+    pub ram: [u8; 128],
+
+    pub dra: u8,
+    pub ddra: u8,
+    pub drb: u8,
+    pub ddrb: u8,
+    /// Levels driven onto the port pins from outside
+    port_a_pins: u8,
+    port_b_pins: u8,
+
+    /// Interval timer
+    pub timer: IntervalTimer,
+    timer_irq_enabled: bool,
+
+    /// PA7 edge detection: positive edge when set
+    pa7_positive: bool,
+    pa7_irq_enabled: bool,
+
+    /// Interrupt flags (timer in bit 7, PA7 in bit 6)
+    pub flags: u8,
+}
+
+impl Riot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin levels of port A as seen from outside
+    pub fn port_a(&self) -> u8 {
+        (self.dra & self.ddra) | (self.port_a_pins & !self.ddra)
+    }
+
+    /// Pin levels of port B as seen from outside
+    pub fn port_b(&self) -> u8 {
+        (self.drb & self.ddrb) | (self.port_b_pins & !self.ddrb)
+    }
+
+    /// Drives the port A pins, watching PA7 for the programmed edge
+    pub fn set_port_a(&mut self, pins: u8) {
+        let old = self.port_a() & 0x80 != 0;
+        self.port_a_pins = pins;
+        self.check_pa7(old);
+    }
+
+    pub fn set_port_b(&mut self, pins: u8) {
+        self.port_b_pins = pins;
+    }
+
+    /// IRQ output (true = asserted)
+    pub fn irq(&self) -> bool {
+        (self.timer_irq_enabled && self.flags & FLAG_TIMER != 0)
+            || (self.pa7_irq_enabled && self.flags & FLAG_PA7 != 0)
+    }
+
+    fn check_pa7(&mut self, old: bool) {
+        let new = self.port_a() & 0x80 != 0;
+        let edge = if self.pa7_positive {
+            !old && new
+        } else {
+            old && !new
+        };
+        if edge {
+            self.flags |= FLAG_PA7;
+        }
+    }
+}
+
+impl Device for Riot {
+    fn read(&mut self, addr: u16) -> u8 {
+        if addr & 0x04 == 0 {
+            return match addr & 0x03 {
+                0 => self.port_a(),
+                1 => self.ddra,
+                2 => self.port_b(),
+                _ => self.ddrb,
+            };
+        }
+
+        if addr & 0x01 == 0 {
+            // Reading the timer clears its flag, A3 sets the interrupt enable
+            self.timer_irq_enabled = addr & 0x08 != 0;
+            self.flags &= !FLAG_TIMER;
+            self.timer.read()
+        } else {
+            // Reading the flags clears the PA7 flag
+            let value = self.flags;
+            self.flags &= !FLAG_PA7;
+            value
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        if addr & 0x04 == 0 {
+            let old = self.port_a() & 0x80 != 0;
+            match addr & 0x03 {
+                0 => self.dra = value,
+                1 => self.ddra = value,
+                2 => self.drb = value,
+                _ => self.ddrb = value,
+            }
+            self.check_pa7(old);
+            return;
+        }
+
+        if addr & 0x10 != 0 {
+            // Write timer: A0-A1 pick the divider, A3 the interrupt enable
+            self.timer.load(addr, value);
+            self.timer_irq_enabled = addr & 0x08 != 0;
+            self.flags &= !FLAG_TIMER;
+        } else {
+            // Edge detect control
+            self.pa7_positive = addr & 0x01 != 0;
+            self.pa7_irq_enabled = addr & 0x02 != 0;
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.timer.tick() {
+            self.flags |= FLAG_TIMER;
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.irq()
+    }
+}
+
+impl Default for Riot {
+    fn default() -> Self {
+        Riot {
+            ram: [0; 128],
+            dra: 0,
+            ddra: 0,
+            drb: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            timer: IntervalTimer::new(),
+            timer_irq_enabled: false,
+            pa7_positive: false,
+            pa7_irq_enabled: false,
+            flags: 0,
+        }
+    }
+}
+
+/// The RAM side of a shared `Riot`. It doesn't tick, so the RIOT can be
+/// mapped twice (I/O and RAM) without running its timer at double speed.
+pub struct RiotRam(pub Rc<RefCell<Riot>>);
+
+impl Device for RiotRam {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.0.borrow().ram[(addr & 0x7F) as usize]
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.0.borrow_mut().ram[(addr & 0x7F) as usize] = value;
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        Some(self.0.borrow().ram[(addr & 0x7F) as usize])
+    }
+}
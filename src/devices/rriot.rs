@@ -0,0 +1,124 @@
+use crate::{bus::Device, devices::timer::IntervalTimer};
+
+// Information grabbed from the MOS 6530 datasheet and the KIM-1 user manual
+
+/// Interrupt flag register bit
+pub const FLAG_TIMER: u8 = 0x80;
+
+/// MOS 6530 ROM-RAM-I/O-Timer.
+/// The `Rriot` is the I/O and timer side (mapped with a mirror mask of
+/// 0x000F, address bits A0-A3 select the register). The mask programmed
+/// ROM and the 64 bytes of RAM behave like any other memory, map them on
+/// the bus with `map_rom` or leave them to base RAM.
+/// Clocked once per CPU cycle, the IRQ output (shared with PB7) goes to the
+/// `Cpu` when the board wires it up.
+pub struct Rriot {
+    pub dra: u8,
+    pub ddra: u8,
+    pub drb: u8,
+    pub ddrb: u8,
+    /// Levels driven onto the port pins from outside
+    port_a_pins: u8,
+    port_b_pins: u8,
+
+    /// Interval timer
+    pub timer: IntervalTimer,
+    irq_enabled: bool,
+
+    /// Interrupt flag (timer in bit 7)
+    pub flags: u8,
+}
+
+impl Rriot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin levels of port A as seen from outside
+    pub fn port_a(&self) -> u8 {
+        (self.dra & self.ddra) | (self.port_a_pins & !self.ddra)
+    }
+
+    /// Pin levels of port B as seen from outside
+    pub fn port_b(&self) -> u8 {
+        (self.drb & self.ddrb) | (self.port_b_pins & !self.ddrb)
+    }
+
+    pub fn set_port_a(&mut self, pins: u8) {
+        self.port_a_pins = pins;
+    }
+
+    pub fn set_port_b(&mut self, pins: u8) {
+        self.port_b_pins = pins;
+    }
+
+    /// IRQ output (true = asserted)
+    pub fn irq(&self) -> bool {
+        self.irq_enabled && self.flags & FLAG_TIMER != 0
+    }
+}
+
+impl Device for Rriot {
+    fn read(&mut self, addr: u16) -> u8 {
+        if addr & 0x04 == 0 {
+            return match addr & 0x03 {
+                0 => self.port_a(),
+                1 => self.ddra,
+                2 => self.port_b(),
+                _ => self.ddrb,
+            };
+        }
+
+        if addr & 0x01 == 0 {
+            // Reading the timer clears its flag, A3 sets the interrupt enable
+            self.irq_enabled = addr & 0x08 != 0;
+            self.flags &= !FLAG_TIMER;
+            self.timer.read()
+        } else {
+            self.flags
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        if addr & 0x04 == 0 {
+            match addr & 0x03 {
+                0 => self.dra = value,
+                1 => self.ddra = value,
+                2 => self.drb = value,
+                _ => self.ddrb = value,
+            }
+            return;
+        }
+
+        // Write timer: A0-A1 pick the divider, A3 the interrupt enable
+        self.timer.load(addr, value);
+        self.irq_enabled = addr & 0x08 != 0;
+        self.flags &= !FLAG_TIMER;
+    }
+
+    fn tick(&mut self) {
+        if self.timer.tick() {
+            self.flags |= FLAG_TIMER;
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.irq()
+    }
+}
+
+impl Default for Rriot {
+    fn default() -> Self {
+        Rriot {
+            dra: 0,
+            ddra: 0,
+            drb: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            timer: IntervalTimer::new(),
+            irq_enabled: false,
+            flags: 0,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+/// The host end of an emulated serial line
+pub trait SerialHost {
+    /// A byte the emulated machine sent
+    fn transmit(&mut self, byte: u8);
+    /// The next byte for the emulated machine, if one is waiting
+    fn receive(&mut self) -> Option<u8>;
+}
+
+/// Lets a host stay reachable from outside the device (e.g. to check output in tests)
+impl<T: SerialHost> SerialHost for Rc<RefCell<T>> {
+    fn transmit(&mut self, byte: u8) {
+        self.borrow_mut().transmit(byte)
+    }
+
+    fn receive(&mut self) -> Option<u8> {
+        self.borrow_mut().receive()
+    }
+}
+
+/// In-memory serial line
+#[derive(Default)]
+pub struct BufferHost {
+    /// Bytes waiting to be received by the emulated machine
+    pub input: VecDeque<u8>,
+    /// Bytes the emulated machine sent
+    pub output: Vec<u8>,
+}
+
+impl BufferHost {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `data` for the emulated machine
+    pub fn send(&mut self, data: &[u8]) {
+        self.input.extend(data);
+    }
+}
+
+impl SerialHost for BufferHost {
+    fn transmit(&mut self, byte: u8) {
+        self.output.push(byte);
+    }
+
+    fn receive(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+}
+
+/// Serial line connected to a host byte stream (stdio, a pipe or a pty).
+/// The reading end runs on its own thread so the emulation never blocks.
+pub struct StreamHost {
+    rx: Receiver<u8>,
+    tx: Box<dyn Write>,
+    /// The reader hit end of file or an error
+    pub closed: bool,
+}
+
+impl StreamHost {
+    pub fn new(reader: impl Read + Send + 'static, writer: impl Write + 'static) -> Self {
+        let (sender, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            for byte in BufReader::new(reader).bytes() {
+                match byte {
+                    Ok(byte) if sender.send(byte).is_ok() => {}
+                    _ => break,
+                }
+            }
+        });
+
+        Self {
+            rx,
+            tx: Box::new(writer),
+            closed: false,
+        }
+    }
+
+    /// Connects to the process' stdin and stdout
+    pub fn stdio() -> Self {
+        Self::new(io::stdin(), io::stdout())
+    }
+
+    /// Connects to a pair of (named) pipes or files
+    pub fn pipe(input: impl AsRef<Path>, output: impl AsRef<Path>) -> io::Result<Self> {
+        let reader = File::open(input)?;
+        let writer = OpenOptions::new().write(true).open(output)?;
+        Ok(Self::new(reader, writer))
+    }
+
+    /// Opens a new pseudo-terminal and connects to its master side.
+    /// Returns the path of the slave side for a terminal program to open.
+    #[cfg(unix)]
+    pub fn pty() -> io::Result<(Self, PathBuf)> {
+        use std::{ffi::CStr, os::fd::FromRawFd};
+
+        // SAFETY: plain libc calls on a descriptor we own, ptsname's result
+        // is copied out before anything else can call it
+        unsafe {
+            let fd = libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY);
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let master = File::from_raw_fd(fd);
+
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let name = libc::ptsname(fd);
+            if name.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let path = PathBuf::from(CStr::from_ptr(name).to_string_lossy().into_owned());
+
+            let reader = master.try_clone()?;
+            Ok((Self::new(reader, master), path))
+        }
+    }
+}
+
+impl SerialHost for StreamHost {
+    fn transmit(&mut self, byte: u8) {
+        // A host that went away just drops the output
+        let _ = self.tx.write_all(&[byte]).and_then(|_| self.tx.flush());
+    }
+
+    fn receive(&mut self) -> Option<u8> {
+        match self.rx.try_recv() {
+            Ok(byte) => Some(byte),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.closed = true;
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::bus::Device;
+
+// Information grabbed from the MOS 6581 datasheet
+
+/// Read-only registers
+pub const POT_X: u16 = 0x19;
+pub const POT_Y: u16 = 0x1A;
+pub const OSC_3: u16 = 0x1B;
+pub const ENV_3: u16 = 0x1C;
+
+/// MOS 6581 SID register file, without any sound.
+/// Writes are kept for inspection, reads of the write-only registers give
+/// 0 and the paddles read as not connected. Mapped with a mirror mask of
+/// 0x001F.
+#[derive(Default)]
+pub struct Sid {
+    pub registers: [u8; 0x20],
+}
+
+impl Sid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Device for Sid {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            POT_X | POT_Y => 0xFF,
+            // No voice 3 to sample
+            OSC_3 | ENV_3 => 0,
+            // Write-only
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.registers[addr as usize] = value;
+    }
+}
@@ -0,0 +1,70 @@
+// Information grabbed from the MOS 6530 and 6532 datasheets
+
+/// Interval timer shared by the 6530 and 6532. Loading it picks a divider
+/// of 1, 8, 64 or 1024 cycles per decrement; once past zero it counts every
+/// cycle until the program reads it.
+/// The chips keep the interrupt flag and enable, the timer only reports
+/// the cycle it underflows.
+pub struct IntervalTimer {
+    pub value: u8,
+    /// Cycles per decrement: 1, 8, 64 or 1024
+    pub divider: u16,
+    /// Cycles until the next decrement
+    prescale: u16,
+    /// Past zero the timer counts every cycle until it is read
+    underflowed: bool,
+}
+
+impl IntervalTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the timer, A0-A1 of the register address pick the divider
+    pub fn load(&mut self, addr: u16, value: u8) {
+        self.divider = [1, 8, 64, 1024][(addr & 0x03) as usize];
+        self.value = value;
+        self.underflowed = false;
+        // The first decrement happens on the next cycle
+        self.prescale = 1;
+    }
+
+    /// Reads the count, going back to the divider if it had underflowed
+    pub fn read(&mut self) -> u8 {
+        if self.underflowed {
+            self.underflowed = false;
+            self.prescale = self.divider;
+        }
+        self.value
+    }
+
+    /// Clocks the timer once, returns true on the cycle it passes zero
+    pub fn tick(&mut self) -> bool {
+        if self.underflowed {
+            self.value = self.value.wrapping_sub(1);
+            return false;
+        }
+
+        self.prescale -= 1;
+        if self.prescale == 0 {
+            self.prescale = self.divider;
+            self.value = self.value.wrapping_sub(1);
+            if self.value == 0xFF {
+                self.underflowed = true;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Default for IntervalTimer {
+    fn default() -> Self {
+        IntervalTimer {
+            value: 0,
+            divider: 1024,
+            prescale: 1024,
+            underflowed: false,
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use crate::bus::Device;
+
+// Information grabbed from the MOS 6522 datasheet (Rockwell R6522 revision)
+
+/// Register select values (RS3-RS0)
+pub const ORB: u16 = 0x0;
+pub const ORA: u16 = 0x1;
+pub const DDRB: u16 = 0x2;
+pub const DDRA: u16 = 0x3;
+pub const T1C_L: u16 = 0x4;
+pub const T1C_H: u16 = 0x5;
+pub const T1L_L: u16 = 0x6;
+pub const T1L_H: u16 = 0x7;
+pub const T2C_L: u16 = 0x8;
+pub const T2C_H: u16 = 0x9;
+pub const SR: u16 = 0xA;
+pub const ACR: u16 = 0xB;
+pub const PCR: u16 = 0xC;
+pub const IFR: u16 = 0xD;
+pub const IER: u16 = 0xE;
+pub const ORA_NO_HANDSHAKE: u16 = 0xF;
+
+/// Interrupt flag / enable bits
+pub enum ViaInterrupt {
+    Ca2 = 0,
+    Ca1 = 1,
+    ShiftRegister = 2,
+    Cb2 = 3,
+    Cb1 = 4,
+    Timer2 = 5,
+    Timer1 = 6,
+}
+
+/// MOS 6522 Versatile Interface Adapter.
+/// Mapped on the bus with its 16 registers (use a mirror mask of 0x000F),
+/// it is clocked once per CPU cycle and its IRQ output goes to the `Cpu`.
+pub struct Via {
+    /// Output registers and data direction registers (1 = output)
+    pub ora: u8,
+    pub orb: u8,
+    pub ddra: u8,
+    pub ddrb: u8,
+    /// Levels driven onto the port pins from outside
+    port_a_pins: u8,
+    port_b_pins: u8,
+    /// Input latches (ACR bits 0/1)
+    ira_latch: u8,
+    irb_latch: u8,
+
+    pub t1_counter: u16,
+    pub t1_latch: u16,
+    /// Timer 1 will raise its interrupt when it runs out
+    t1_armed: bool,
+    /// Free-running timer 1 reloads from the latch on the next cycle
+    t1_reload: bool,
+    /// PB7 level when timer 1 drives it (ACR bit 7)
+    pb7: bool,
+
+    pub t2_counter: u16,
+    /// Only the low byte of timer 2 is latched
+    pub t2_latch_lo: u8,
+    t2_armed: bool,
+
+    pub sr: u8,
+    /// Bits shifted since the shift register was last accessed
+    sr_bits: u8,
+    sr_active: bool,
+    /// Cycles until the next shift in timer 2 driven modes
+    sr_timer: u16,
+
+    pub acr: u8,
+    pub pcr: u8,
+    pub ifr: u8,
+    pub ier: u8,
+
+    /// Handshake line levels
+    ca1: bool,
+    ca2: bool,
+    cb1: bool,
+    cb2: bool,
+    /// A one cycle CA2/CB2 pulse output is in progress
+    ca2_pulse: bool,
+    cb2_pulse: bool,
+}
+
+impl Via {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pin levels of port A as seen from outside (outputs driven by the VIA)
+    pub fn port_a(&self) -> u8 {
+        (self.ora & self.ddra) | (self.port_a_pins & !self.ddra)
+    }
+
+    /// Pin levels of port B as seen from outside (outputs driven by the VIA)
+    pub fn port_b(&self) -> u8 {
+        let mut value = (self.orb & self.ddrb) | (self.port_b_pins & !self.ddrb);
+        if self.acr & 0x80 != 0 {
+            value = (value & 0x7F) | if self.pb7 { 0x80 } else { 0x00 };
+        }
+        value
+    }
+
+    /// Drives the port A input pins
+    pub fn set_port_a(&mut self, pins: u8) {
+        self.port_a_pins = pins;
+    }
+
+    /// Drives the port B input pins. A falling edge on PB6 is counted by
+    /// timer 2 in pulse counting mode.
+    pub fn set_port_b(&mut self, pins: u8) {
+        let falling_pb6 = self.port_b_pins & 0x40 != 0 && pins & 0x40 == 0;
+        self.port_b_pins = pins;
+
+        if falling_pb6 && self.acr & 0x20 != 0 {
+            self.count_t2();
+        }
+    }
+
+    pub fn set_ca1(&mut self, level: bool) {
+        let old = self.ca1;
+        self.ca1 = level;
+        // PCR bit 0 selects the active edge (0 = negative, 1 = positive)
+        if Self::active_edge(old, level, self.pcr & 0x01 != 0) {
+            self.set_ifr(ViaInterrupt::Ca1);
+            if self.acr & 0x01 != 0 {
+                self.ira_latch = self.port_a();
+            }
+            // Handshake output mode: CA2 goes back high on the CA1 edge
+            if self.pcr & 0x0E == 0x08 {
+                self.ca2 = true;
+            }
+        }
+    }
+
+    pub fn set_ca2(&mut self, level: bool) {
+        let old = self.ca2;
+        // Only an input when PCR bit 3 is clear
+        if self.pcr & 0x08 != 0 {
+            return;
+        }
+        self.ca2 = level;
+        if Self::active_edge(old, level, self.pcr & 0x04 != 0) {
+            self.set_ifr(ViaInterrupt::Ca2);
+        }
+    }
+
+    pub fn set_cb1(&mut self, level: bool) {
+        let old = self.cb1;
+        self.cb1 = level;
+        if Self::active_edge(old, level, self.pcr & 0x10 != 0) {
+            self.set_ifr(ViaInterrupt::Cb1);
+            if self.acr & 0x02 != 0 {
+                self.irb_latch = self.port_b();
+            }
+            if self.pcr & 0xE0 == 0x80 {
+                self.cb2 = true;
+            }
+        }
+
+        // Shift register clocked by CB1 shifts on the rising edge
+        if !old && level && self.acr & 0x0C == 0x0C {
+            self.shift();
+        }
+    }
+
+    pub fn set_cb2(&mut self, level: bool) {
+        let old = self.cb2;
+        // CB2 is an output in PCR output modes and when the shift register
+        // is shifting out
+        if self.pcr & 0x80 != 0 || self.acr & 0x10 != 0 {
+            return;
+        }
+        self.cb2 = level;
+        if Self::active_edge(old, level, self.pcr & 0x40 != 0) {
+            self.set_ifr(ViaInterrupt::Cb2);
+        }
+    }
+
+    pub fn ca2(&self) -> bool {
+        self.ca2
+    }
+
+    pub fn cb1(&self) -> bool {
+        self.cb1
+    }
+
+    pub fn cb2(&self) -> bool {
+        self.cb2
+    }
+
+    /// IRQ output (true = asserted)
+    pub fn irq(&self) -> bool {
+        self.ifr & self.ier & 0x7F != 0
+    }
+
+    fn active_edge(old: bool, new: bool, positive: bool) -> bool {
+        if positive {
+            !old && new
+        } else {
+            old && !new
+        }
+    }
+
+    fn set_ifr(&mut self, flag: ViaInterrupt) {
+        self.ifr |= 1 << flag as u8;
+    }
+
+    fn clear_ifr(&mut self, flag: ViaInterrupt) {
+        self.ifr &= !(1 << flag as u8);
+    }
+
+    /// Clears CA1 and (unless CA2 is an independent input) CA2
+    fn port_a_accessed(&mut self, handshake: bool) {
+        self.clear_ifr(ViaInterrupt::Ca1);
+        if self.pcr & 0x0A != 0x02 {
+            self.clear_ifr(ViaInterrupt::Ca2);
+        }
+
+        if handshake {
+            match self.pcr & 0x0E {
+                // Handshake: low until the next CA1 active edge
+                0x08 => self.ca2 = false,
+                // Pulse: low for one cycle
+                0x0A => {
+                    self.ca2 = false;
+                    self.ca2_pulse = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn port_b_accessed(&mut self, write: bool) {
+        self.clear_ifr(ViaInterrupt::Cb1);
+        if self.pcr & 0xA0 != 0x20 {
+            self.clear_ifr(ViaInterrupt::Cb2);
+        }
+
+        // CB2 handshaking only happens on writes to ORB
+        if write {
+            match self.pcr & 0xE0 {
+                0x80 => self.cb2 = false,
+                0xA0 => {
+                    self.cb2 = false;
+                    self.cb2_pulse = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn write_pcr(&mut self, value: u8) {
+        self.pcr = value;
+        // Manual output modes drive CA2/CB2 straight away
+        match value & 0x0E {
+            0x0C => self.ca2 = false,
+            0x0E => self.ca2 = true,
+            _ => {}
+        }
+        match value & 0xE0 {
+            0xC0 => self.cb2 = false,
+            0xE0 => self.cb2 = true,
+            _ => {}
+        }
+    }
+
+    fn count_t2(&mut self) {
+        self.t2_counter = self.t2_counter.wrapping_sub(1);
+        if self.t2_counter == 0xFFFF && self.t2_armed {
+            self.set_ifr(ViaInterrupt::Timer2);
+            self.t2_armed = false;
+        }
+    }
+
+    fn sr_start(&mut self) {
+        self.clear_ifr(ViaInterrupt::ShiftRegister);
+        self.sr_bits = 0;
+        self.sr_active = self.acr & 0x1C != 0;
+        self.sr_timer = self.t2_latch_lo as u16 + 2;
+    }
+
+    /// Shifts one bit in from or out to CB2
+    fn shift(&mut self) {
+        if !self.sr_active {
+            return;
+        }
+
+        let mode = (self.acr >> 2) & 0x07;
+        if mode & 0x04 != 0 {
+            // Shift out, the register rotates so it can be sent again
+            let bit = self.sr & 0x80 != 0;
+            self.cb2 = bit;
+            self.sr = (self.sr << 1) | bit as u8;
+        } else {
+            self.sr = (self.sr << 1) | self.cb2 as u8;
+        }
+
+        // Free running output never stops and never interrupts
+        if mode == 0x04 {
+            return;
+        }
+
+        self.sr_bits += 1;
+        if self.sr_bits == 8 {
+            self.sr_active = false;
+            self.set_ifr(ViaInterrupt::ShiftRegister);
+        }
+    }
+}
+
+impl Device for Via {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr & 0x0F {
+            ORB => {
+                self.port_b_accessed(false);
+                let pins = if self.acr & 0x02 != 0 {
+                    self.irb_latch
+                } else {
+                    self.port_b()
+                };
+                // Output bits always read back the output register
+                let mut value = (self.orb & self.ddrb) | (pins & !self.ddrb);
+                if self.acr & 0x80 != 0 {
+                    value = (value & 0x7F) | if self.pb7 { 0x80 } else { 0x00 };
+                }
+                value
+            }
+            ORA | ORA_NO_HANDSHAKE => {
+                self.port_a_accessed(addr & 0x0F == ORA);
+                if self.acr & 0x01 != 0 {
+                    self.ira_latch
+                } else {
+                    self.port_a()
+                }
+            }
+            DDRB => self.ddrb,
+            DDRA => self.ddra,
+            T1C_L => {
+                self.clear_ifr(ViaInterrupt::Timer1);
+                self.t1_counter as u8
+            }
+            T1C_H => (self.t1_counter >> 8) as u8,
+            T1L_L => self.t1_latch as u8,
+            T1L_H => (self.t1_latch >> 8) as u8,
+            T2C_L => {
+                self.clear_ifr(ViaInterrupt::Timer2);
+                self.t2_counter as u8
+            }
+            T2C_H => (self.t2_counter >> 8) as u8,
+            SR => {
+                self.sr_start();
+                self.sr
+            }
+            ACR => self.acr,
+            PCR => self.pcr,
+            IFR => {
+                let any = if self.irq() { 0x80 } else { 0x00 };
+                (self.ifr & 0x7F) | any
+            }
+            IER => self.ier | 0x80,
+            _ => unreachable!(),
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr & 0x0F {
+            ORB => {
+                self.orb = value;
+                self.port_b_accessed(true);
+            }
+            ORA | ORA_NO_HANDSHAKE => {
+                self.ora = value;
+                self.port_a_accessed(addr & 0x0F == ORA);
+            }
+            DDRB => self.ddrb = value,
+            DDRA => self.ddra = value,
+            T1C_L | T1L_L => self.t1_latch = (self.t1_latch & 0xFF00) | value as u16,
+            T1C_H => {
+                // Loads the counter from the latch and starts the timer
+                self.t1_latch = (self.t1_latch & 0x00FF) | (value as u16) << 8;
+                self.t1_counter = self.t1_latch;
+                self.t1_armed = true;
+                self.t1_reload = false;
+                self.clear_ifr(ViaInterrupt::Timer1);
+                if self.acr & 0x80 != 0 {
+                    self.pb7 = false;
+                }
+            }
+            T1L_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (value as u16) << 8;
+                self.clear_ifr(ViaInterrupt::Timer1);
+            }
+            T2C_L => self.t2_latch_lo = value,
+            T2C_H => {
+                self.t2_counter = (value as u16) << 8 | self.t2_latch_lo as u16;
+                self.t2_armed = true;
+                self.clear_ifr(ViaInterrupt::Timer2);
+            }
+            SR => {
+                self.sr = value;
+                self.sr_start();
+            }
+            ACR => {
+                self.acr = value;
+                if self.acr & 0x80 != 0 {
+                    self.pb7 = true;
+                }
+            }
+            PCR => self.write_pcr(value),
+            IFR => self.ifr &= !(value & 0x7F),
+            IER => {
+                if value & 0x80 != 0 {
+                    self.ier |= value & 0x7F;
+                } else {
+                    self.ier &= !(value & 0x7F);
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.ca2_pulse {
+            self.ca2_pulse = false;
+        } else if self.pcr & 0x0E == 0x0A {
+            self.ca2 = true;
+        }
+        if self.cb2_pulse {
+            self.cb2_pulse = false;
+        } else if self.pcr & 0xE0 == 0xA0 {
+            self.cb2 = true;
+        }
+
+        // Timer 1: N, N-1, .., 0, FFFF (interrupt), then reload when free running
+        if self.t1_reload {
+            self.t1_counter = self.t1_latch;
+            self.t1_reload = false;
+        } else {
+            self.t1_counter = self.t1_counter.wrapping_sub(1);
+            if self.t1_counter == 0xFFFF && self.t1_armed {
+                self.set_ifr(ViaInterrupt::Timer1);
+                if self.acr & 0x40 != 0 {
+                    self.t1_reload = true;
+                    self.pb7 = !self.pb7;
+                } else {
+                    self.t1_armed = false;
+                    self.pb7 = true;
+                }
+            }
+        }
+
+        // Timer 2 counts cycles unless it is counting PB6 pulses
+        if self.acr & 0x20 == 0 {
+            self.count_t2();
+        }
+
+        // Shift register rate: timer 2 modes shift every T2L-L + 2 cycles,
+        // phase 2 modes every cycle, CB1 modes on the CB1 edge
+        if self.sr_active {
+            match (self.acr >> 2) & 0x07 {
+                0x01 | 0x04 | 0x05 => {
+                    self.sr_timer = self.sr_timer.saturating_sub(1);
+                    if self.sr_timer == 0 {
+                        self.sr_timer = self.t2_latch_lo as u16 + 2;
+                        self.shift();
+                    }
+                }
+                0x02 | 0x06 => self.shift(),
+                _ => {}
+            }
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.irq()
+    }
+}
+
+impl Default for Via {
+    fn default() -> Self {
+        Via {
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            port_a_pins: 0xFF,
+            port_b_pins: 0xFF,
+            ira_latch: 0,
+            irb_latch: 0,
+            t1_counter: 0xFFFF,
+            t1_latch: 0xFFFF,
+            t1_armed: false,
+            t1_reload: false,
+            pb7: true,
+            t2_counter: 0xFFFF,
+            t2_latch_lo: 0xFF,
+            t2_armed: false,
+            sr: 0,
+            sr_bits: 0,
+            sr_active: false,
+            sr_timer: 0,
+            acr: 0,
+            pcr: 0,
+            ifr: 0,
+            ier: 0,
+            ca1: true,
+            ca2: true,
+            cb1: true,
+            cb2: true,
+            ca2_pulse: false,
+            cb2_pulse: false,
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::bus::Device;
+
+// Information grabbed from Christian Bauer's "The MOS 6567/6569 video
+// controller (VIC-II) and its application in the Commodore 64"
+
+/// Registers the stub gives a meaning to
+pub const CONTROL_1: u16 = 0x11;
+pub const RASTER: u16 = 0x12;
+pub const IRQ_FLAGS: u16 = 0x19;
+pub const IRQ_ENABLE: u16 = 0x1A;
+
+/// Interrupt flag bits
+pub const IRQ_RASTER: u8 = 0x01;
+pub const IRQ_ANY: u8 = 0x80;
+
+/// MOS 6569 VIC-II register file, without any video.
+/// Just enough for CPU-side code: the raster counter runs (PAL timing by
+/// default) and raises the raster interrupt, every other register reads
+/// back what was written. Mapped with a mirror mask of 0x003F.
+pub struct Vic {
+    pub registers: [u8; 0x40],
+    /// Current raster line and the cycle within it
+    pub raster: u16,
+    pub cycle: u16,
+    /// Line compared against `raster` for the raster interrupt
+    pub raster_compare: u16,
+    pub cycles_per_line: u16,
+    pub lines: u16,
+}
+
+impl Vic {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Switches to the 6567R8 timing of NTSC machines
+    pub fn ntsc() -> Self {
+        Vic {
+            cycles_per_line: 65,
+            lines: 263,
+            ..Self::default()
+        }
+    }
+
+    /// IRQ output (true = asserted)
+    pub fn irq(&self) -> bool {
+        self.registers[IRQ_FLAGS as usize] & self.registers[IRQ_ENABLE as usize] & 0x0F != 0
+    }
+}
+
+impl Device for Vic {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            CONTROL_1 => {
+                (self.registers[CONTROL_1 as usize] & 0x7F) | ((self.raster >> 1) as u8 & 0x80)
+            }
+            RASTER => self.raster as u8,
+            IRQ_FLAGS => {
+                let flags = self.registers[IRQ_FLAGS as usize];
+                let any = if self.irq() { IRQ_ANY } else { 0 };
+                flags | any | 0x70
+            }
+            IRQ_ENABLE => self.registers[IRQ_ENABLE as usize] | 0xF0,
+            // Unused registers
+            0x2F..=0x3F => 0xFF,
+            _ => self.registers[addr as usize],
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            CONTROL_1 => {
+                self.registers[CONTROL_1 as usize] = value;
+                self.raster_compare = (self.raster_compare & 0xFF) | ((value as u16 & 0x80) << 1);
+            }
+            RASTER => self.raster_compare = (self.raster_compare & 0x100) | value as u16,
+            // Writing ones acknowledges the interrupts
+            IRQ_FLAGS => self.registers[IRQ_FLAGS as usize] &= !value & 0x0F,
+            _ => self.registers[addr as usize] = value,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.cycle += 1;
+        if self.cycle < self.cycles_per_line {
+            return;
+        }
+
+        self.cycle = 0;
+        self.raster += 1;
+        if self.raster == self.lines {
+            self.raster = 0;
+        }
+        if self.raster == self.raster_compare {
+            self.registers[IRQ_FLAGS as usize] |= IRQ_RASTER;
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.irq()
+    }
+}
+
+impl Default for Vic {
+    fn default() -> Self {
+        Vic {
+            registers: [0; 0x40],
+            raster: 0,
+            cycle: 0,
+            raster_compare: 0,
+            cycles_per_line: 63,
+            lines: 312,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    bus::Bus,
+    opcodes::{AddrMode, OpcodeSpec, Variant, OPCODES},
+};
+use std::fmt;
+
+/// One decoded instruction. Displays as the mnemonic and operand, with
+/// branch targets already worked out (`BNE $0212`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub addr: u16,
+    pub opcode: u8,
+    /// Little endian operand, 0 when there's none
+    pub operand: u16,
+    /// Opcodes it doesn't have show up as NOP
+    pub variant: Variant,
+}
+
+impl Line {
+    pub fn spec(&self) -> &'static OpcodeSpec {
+        &OPCODES[self.opcode as usize]
+    }
+
+    /// Address of the following instruction
+    pub fn next(&self) -> u16 {
+        self.addr.wrapping_add(self.spec().bytes as u16)
+    }
+
+    /// Lowercase, NOP for an opcode the variant doesn't have
+    pub fn mnemonic(&self) -> &'static str {
+        let spec = self.spec();
+        if spec.available(self.variant) {
+            spec.mnemonic
+        } else {
+            "nop"
+        }
+    }
+
+    /// The opcode and operand as they sit in memory
+    pub fn bytes(&self) -> Vec<u8> {
+        let [lo, hi] = self.operand.to_le_bytes();
+        [self.opcode, lo, hi][..self.spec().bytes as usize].to_vec()
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let spec = self.spec();
+        let mnemonic = self.mnemonic().to_uppercase();
+        let value = self.operand;
+        match spec.mode {
+            AddrMode::Implied => write!(f, "{}", mnemonic),
+            AddrMode::Accumulator => write!(f, "{} A", mnemonic),
+            AddrMode::Immediate => write!(f, "{} #${:02X}", mnemonic, value),
+            AddrMode::ZeroPage => write!(f, "{} ${:02X}", mnemonic, value),
+            AddrMode::ZeroPageX => write!(f, "{} ${:02X},X", mnemonic, value),
+            AddrMode::ZeroPageY => write!(f, "{} ${:02X},Y", mnemonic, value),
+            AddrMode::Absolute => write!(f, "{} ${:04X}", mnemonic, value),
+            AddrMode::AbsoluteX => write!(f, "{} ${:04X},X", mnemonic, value),
+            AddrMode::AbsoluteY => write!(f, "{} ${:04X},Y", mnemonic, value),
+            AddrMode::Indirect => write!(f, "{} (${:04X})", mnemonic, value),
+            AddrMode::IndirectX => write!(f, "{} (${:02X},X)", mnemonic, value),
+            AddrMode::IndirectY => write!(f, "{} (${:02X}),Y", mnemonic, value),
+            AddrMode::Relative => {
+                let target = self.next().wrapping_add(value as u8 as i8 as u16);
+                write!(f, "{} ${:04X}", mnemonic, target)
+            }
+        }
+    }
+}
+
+/// Decodes the instruction at `addr` for `variant`, reading bytes through
+/// `read`
+pub fn disassemble_at(mut read: impl FnMut(u16) -> u8, addr: u16, variant: Variant) -> Line {
+    let opcode = read(addr);
+    let operand = match OPCODES[opcode as usize].bytes - 1 {
+        0 => 0,
+        1 => read(addr.wrapping_add(1)) as u16,
+        _ => u16::from_le_bytes([read(addr.wrapping_add(1)), read(addr.wrapping_add(2))]),
+    };
+    Line {
+        addr,
+        opcode,
+        operand,
+        variant,
+    }
+}
+
+/// Decodes all of `code` as if it were loaded at `origin`. An instruction
+/// cut off at the end reads zeros for its missing bytes.
+pub fn disassemble(code: &[u8], origin: u16) -> Vec<Line> {
+    disassemble_for(code, origin, Variant::Nmos6502)
+}
+
+/// Like `disassemble`, showing the opcodes `variant` doesn't have as NOP
+pub fn disassemble_for(code: &[u8], origin: u16, variant: Variant) -> Vec<Line> {
+    let read = |addr: u16| {
+        let offset = addr.wrapping_sub(origin) as usize;
+        code.get(offset).copied().unwrap_or(0)
+    };
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < code.len() {
+        let line = disassemble_at(read, origin.wrapping_add(offset as u16), variant);
+        offset += line.spec().bytes as usize;
+        lines.push(line);
+    }
+    lines
+}
+
+impl Bus {
+    /// Decodes `count` instructions from `addr` on for `variant`, reading
+    /// them with `peek` so no device sees the reads
+    pub fn disassemble(&self, addr: u16, count: usize, variant: Variant) -> Vec<Line> {
+        let mut addr = addr;
+        (0..count)
+            .map(|_| {
+                let line = disassemble_at(|a| self.peek(a), addr, variant);
+                addr = line.next();
+                line
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,118 @@
+use std::io::{self, Write};
+
+/// Which counter of a `Heatmap` to look at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Heat {
+    Reads,
+    Writes,
+    /// Opcode and operand bytes of instructions that ran
+    Executes,
+}
+
+/// Per-address access counts, collected by `Bus` while `Bus::heatmap` is
+/// set. Everything the CPU reads or writes counts, devices included;
+/// instruction bytes count as executed rather than read. Reads of
+/// uninitialized RAM are counted too while `Bus::uninit` is also set,
+/// by its rules.
+pub struct Heatmap {
+    reads: Vec<u32>,
+    writes: Vec<u32>,
+    executes: Vec<u32>,
+    /// Reads the `UninitChecker` flagged
+    unwritten: Vec<u32>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Heatmap {
+            reads: vec![0; 0x10000],
+            writes: vec![0; 0x10000],
+            executes: vec![0; 0x10000],
+            unwritten: vec![0; 0x10000],
+        }
+    }
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `uninitialized` when `Bus::uninit` flags the read
+    pub(crate) fn read(&mut self, addr: u16, uninitialized: bool) {
+        let addr = addr as usize;
+        self.reads[addr] = self.reads[addr].saturating_add(1);
+        if uninitialized {
+            self.unwritten[addr] = self.unwritten[addr].saturating_add(1);
+        }
+    }
+
+    pub(crate) fn write(&mut self, addr: u16) {
+        let addr = addr as usize;
+        self.writes[addr] = self.writes[addr].saturating_add(1);
+    }
+
+    pub(crate) fn execute(&mut self, addr: u16) {
+        let addr = addr as usize;
+        self.executes[addr] = self.executes[addr].saturating_add(1);
+    }
+
+    /// One count per address, saturating at `u32::MAX`
+    pub fn counts(&self, heat: Heat) -> &[u32] {
+        match heat {
+            Heat::Reads => &self.reads,
+            Heat::Writes => &self.writes,
+            Heat::Executes => &self.executes,
+        }
+    }
+
+    /// RAM addresses read before anything was written or loaded there,
+    /// with how many such reads there were. Only counted while
+    /// `Bus::uninit` is set, this is where its `reads` land.
+    pub fn unwritten_reads(&self) -> Vec<(u16, u32)> {
+        (0..0x10000)
+            .filter(|&addr| self.unwritten[addr] > 0)
+            .map(|addr| (addr as u16, self.unwritten[addr]))
+            .collect()
+    }
+
+    /// A 256x256 binary PGM, one pixel per address and one row per page.
+    /// Brightness grows with the log of the count, so rarely touched
+    /// addresses still show up next to hot loops.
+    pub fn write_pgm(&self, heat: Heat, out: &mut impl Write) -> io::Result<()> {
+        let counts = self.counts(heat);
+        let max = counts.iter().copied().max().unwrap_or(0).max(2) as f64;
+        let pixels: Vec<u8> = counts
+            .iter()
+            .map(|&count| match count {
+                0 => 0,
+                count => 1 + (254.0 * (count as f64).ln() / max.ln()) as u8,
+            })
+            .collect();
+
+        write!(out, "P5\n256 256\n255\n")?;
+        out.write_all(&pixels)
+    }
+
+    /// `address,reads,writes,executes,unwritten_reads` for every address
+    /// that was touched
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "address,reads,writes,executes,unwritten_reads")?;
+        for addr in 0..0x10000 {
+            let counts = [
+                self.reads[addr],
+                self.writes[addr],
+                self.executes[addr],
+                self.unwritten[addr],
+            ];
+            if counts.iter().any(|&count| count > 0) {
+                writeln!(
+                    out,
+                    "{:04X},{},{},{},{}",
+                    addr, counts[0], counts[1], counts[2], counts[3]
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
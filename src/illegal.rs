@@ -0,0 +1,146 @@
+use crate::{
+    cpu::Cpu,
+    instructions::Instruction,
+    opcodes::{spec, OPCODES},
+    table::{build_instruction_table, INSTRUCTIONS},
+};
+
+// Information grabbed from https://www.masswerk.at/6502/6502_instruction_set.html#illegals
+
+/// The 105 opcodes the 6502 documentation leaves out, grouped by how much
+/// software can rely on them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpcodeClass {
+    /// Extra NOPs, some of them reading an operand
+    Nop,
+    /// Two documented operations at once (SLO, RLA, SRE, RRA, SAX, LAX,
+    /// DCP, ISC) and the immediate ones that behave the same on every chip
+    /// (ANC, ALR, ARR, SBX, the SBC at $EB)
+    Stable,
+    /// Results that depend on the chip or the bus (ANE, LXA, SHA, SHX, SHY,
+    /// TAS, LAS)
+    Unstable,
+    /// JAM (KIL): locks the CPU up until reset
+    Jam,
+}
+
+impl OpcodeClass {
+    /// Which class `opcode` is in, `None` for documented opcodes
+    pub const fn of(opcode: u8) -> Option<Self> {
+        spec(opcode).class
+    }
+}
+
+/// What the CPU does when it runs into an opcode of a given class
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IllegalPolicy {
+    /// Do what an NMOS 6502 does (the default)
+    Emulate,
+    /// Skip it like a NOP, stepping over the same bytes and taking the same
+    /// base cycles. The operand's address isn't resolved, so nothing past
+    /// the opcode is read. JAMs become a one byte, two cycle NOP.
+    Nop,
+    /// Stop before executing it, `Cpu::run` returns
+    /// `CpuError::IllegalOpcode`
+    Trap,
+}
+
+/// Per opcode class, looked up on every instruction
+static CLASSES: [Option<OpcodeClass>; 256] = {
+    let mut classes = [None; 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        classes[opcode] = OpcodeClass::of(opcode as u8);
+        opcode += 1;
+    }
+    classes
+};
+
+/// The decode table with every illegal opcode swapped for a NOP or a trap
+static NOPS: [Instruction; 256] = with_policy(IllegalPolicy::Nop);
+static TRAPS: [Instruction; 256] = with_policy(IllegalPolicy::Trap);
+
+const fn with_policy(policy: IllegalPolicy) -> [Instruction; 256] {
+    let mut table = build_instruction_table();
+    let mut opcode = 0;
+    while opcode < 256 {
+        let instruction = table[opcode];
+        table[opcode] = match (OpcodeClass::of(opcode as u8), policy) {
+            (None, _) | (_, IllegalPolicy::Emulate) => instruction,
+            (Some(OpcodeClass::Jam), IllegalPolicy::Nop) => Instruction {
+                name: "nop",
+                op: Cpu::nop,
+                addr_mode: |_cpu| 0,
+                cycles: 2,
+            },
+            (_, IllegalPolicy::Nop) => Instruction {
+                name: "nop",
+                op: Cpu::nop,
+                addr_mode: skip_operand(spec(opcode as u8).mode.operand_bytes()),
+                cycles: instruction.cycles,
+            },
+            (_, IllegalPolicy::Trap) => Instruction {
+                name: instruction.name,
+                op: Cpu::trap,
+                addr_mode: |_cpu| 0,
+                cycles: 1,
+            },
+        };
+        opcode += 1;
+    }
+    table
+}
+
+/// Steps over `bytes` of operand without reading them
+const fn skip_operand(bytes: u8) -> fn(&mut Cpu) -> u8 {
+    match bytes {
+        0 => |_cpu| 0,
+        1 => |cpu| {
+            cpu.pc = cpu.pc.wrapping_add(1);
+            0
+        },
+        _ => |cpu| {
+            cpu.pc = cpu.pc.wrapping_add(2);
+            0
+        },
+    }
+}
+
+impl Cpu {
+    /// Sets how every opcode in `class` is handled
+    pub fn set_illegal_policy(&mut self, class: OpcodeClass, policy: IllegalPolicy) {
+        self.illegal_policy[class as usize] = policy;
+        // Cached blocks were decoded under the old policy
+        self.flush_blocks();
+    }
+
+    pub fn illegal_policy(&self, class: OpcodeClass) -> IllegalPolicy {
+        self.illegal_policy[class as usize]
+    }
+
+    /// Whether `opcode` runs as an NMOS 6502 would run it
+    #[inline]
+    pub fn emulates(&self, opcode: u8) -> bool {
+        self.policy(opcode) == IllegalPolicy::Emulate
+    }
+
+    /// How `opcode` gets decoded under the current policies and variant
+    #[inline]
+    pub fn instruction(&self, opcode: u8) -> &'static Instruction {
+        match self.policy(opcode) {
+            IllegalPolicy::Emulate => &INSTRUCTIONS[opcode as usize],
+            IllegalPolicy::Nop => &NOPS[opcode as usize],
+            IllegalPolicy::Trap => &TRAPS[opcode as usize],
+        }
+    }
+
+    /// Opcodes the variant doesn't have run as NOPs whatever the policy
+    #[inline]
+    fn policy(&self, opcode: u8) -> IllegalPolicy {
+        match CLASSES[opcode as usize] {
+            None => IllegalPolicy::Emulate,
+            Some(_) if !OPCODES[opcode as usize].available(self.variant) => IllegalPolicy::Nop,
+            Some(class) => self.illegal_policy[class as usize],
+        }
+    }
+}
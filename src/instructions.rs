@@ -1,5 +1,6 @@
-use crate::cpu::{Cpu, Flag};
+use crate::cpu::{Cpu, CpuError, Flag};
 
+#[derive(Clone, Copy)]
 pub struct Instruction {
     pub name: &'static str,
     pub op: fn(&mut Cpu),              // Might be an issue later
@@ -7,21 +8,27 @@ pub struct Instruction {
     pub cycles: u8,
 }
 
+impl Instruction {
+    /// Filler for table slots that haven't been decoded yet
+    pub const UNUSED: Instruction = Instruction {
+        name: "???",
+        op: |_cpu| {},
+        addr_mode: |_cpu| 0,
+        cycles: 0,
+    };
+}
+
 impl Default for Instruction {
     fn default() -> Self {
-        Self {
-            name: "???",
-            op: |_cpu| {},
-            addr_mode: |_cpu| 0,
-            cycles: 0,
-        }
+        Self::UNUSED
     }
 }
 
 impl Cpu {
     /// BRK - Break (software IRQ)
     pub fn brk(&mut self) {
-        let return_addr = self.pc;
+        // Returns past the padding byte after the opcode
+        let return_addr = self.pc.wrapping_add(1);
 
         self.push((return_addr >> 8) as u8); // Push high byte
         self.push((return_addr & 0xFF) as u8); // push low byte
@@ -33,8 +40,8 @@ impl Cpu {
 
         self.set_flag(Flag::InterruptDisable, true);
 
-        let lo = self.memory[0xFFFE] as u16;
-        let hi = self.memory[0xFFFF] as u16;
+        let lo = self.read(0xFFFE) as u16;
+        let hi = self.read(0xFFFF) as u16;
         self.pc = (hi << 8) | lo;
     }
 
@@ -117,7 +124,7 @@ impl Cpu {
 
     /// STY - Store Y
     pub fn sty(&mut self) {
-        self.memory[self.addr_abs as usize] = self.y;
+        self.write(self.addr_abs, self.y);
     }
 
     /// PHP - Push Processor Status
@@ -376,24 +383,12 @@ impl Cpu {
     /// ADC - Add with Carry
     pub fn adc(&mut self) {
         self.fetch();
-        let carry_in = if self.get_flag(Flag::Carry) { 1 } else { 0 };
-
-        let a = self.a;
-        let m = self.fetched;
-
-        let result = a as u16 + m as u16 + carry_in as u16;
-
-        self.set_flag(Flag::Carry, result > 0xFF);
-        self.set_flag(Flag::Zero, (result & 0xFF) == 0);
-        self.set_flag(Flag::Overflow, (!(a ^ m) & (a ^ result as u8) & 0x80) != 0);
-        self.set_flag(Flag::Negative, (result & 0x80) != 0);
-
-        self.a = result as u8;
+        self.add(self.fetched);
     }
 
     /// STA - Store A
     pub fn sta(&mut self) {
-        self.memory[self.addr_abs as usize] = self.a;
+        self.write(self.addr_abs, self.a);
     }
 
     /// LDA - Load A
@@ -418,21 +413,42 @@ impl Cpu {
     /// SBC - Subtract with Carry
     pub fn sbc(&mut self) {
         self.fetch();
+        self.subtract(self.fetched);
+    }
 
-        let value = (self.fetched as u16) ^ 0x00FF; // bitwise NOT of fetched
+    /// A + `m` + C, as ADC does it
+    fn add(&mut self, m: u8) {
+        if self.decimal() {
+            self.add_decimal(m);
+        } else {
+            self.add_with_carry(m);
+        }
+    }
+
+    /// A - `m` - !C, as SBC does it
+    fn subtract(&mut self, m: u8) {
+        let a = self.a;
+        let carry_in = self.get_flag(Flag::Carry);
+        // In binary mode subtracting is adding the one's complement, NMOS
+        // parts keep those flags in decimal mode too
+        self.add_with_carry(!m);
+        if self.decimal() {
+            self.a = Cpu::subtract_decimal(a, m, carry_in);
+        }
+    }
+
+    fn add_with_carry(&mut self, m: u8) {
         let carry_in = if self.get_flag(Flag::Carry) { 1 } else { 0 };
 
-        let sum = self.a as u16 + value + carry_in;
+        let a = self.a;
+        let result = a as u16 + m as u16 + carry_in as u16;
 
-        self.set_flag(Flag::Carry, sum > 0xFF);
-        self.set_flag(Flag::Zero, (sum & 0xFF) == 0);
-        self.set_flag(
-            Flag::Overflow,
-            ((sum ^ self.a as u16) & (sum ^ value) & 0x80) != 0,
-        );
-        self.set_flag(Flag::Negative, (sum & 0x80) != 0);
+        self.set_flag(Flag::Carry, result > 0xFF);
+        self.set_flag(Flag::Zero, (result & 0xFF) == 0);
+        self.set_flag(Flag::Overflow, (!(a ^ m) & (a ^ result as u8) & 0x80) != 0);
+        self.set_flag(Flag::Negative, (result & 0x80) != 0);
 
-        self.a = (sum & 0xFF) as u8; // can leabe out 0xFF but its less clear
+        self.a = result as u8;
     }
 
     /// ASL - Arithmetic Shift Left (Accumulator)
@@ -449,10 +465,10 @@ impl Cpu {
 
     /// ASL - Arithmetic Shift Left (Memory)
     pub fn asl_mem(&mut self) {
-        let value = self.memory[self.addr_abs as usize];
+        let value = self.read(self.addr_abs);
 
         // [Read-Modify-Write] Write original value back
-        self.memory[self.addr_abs as usize] = value;
+        self.write(self.addr_abs, value);
 
         // Step 2: Perform shift
         let res = value << 1;
@@ -463,15 +479,15 @@ impl Cpu {
         self.set_flag(Flag::Negative, res & 0x80 != 0);
 
         // Step 4: Write result
-        self.memory[self.addr_abs as usize] = res;
+        self.write(self.addr_abs, res);
     }
 
     /// ROL - Rotate Left (Memory)
     pub fn rol_mem(&mut self) {
-        let value = self.memory[self.addr_abs as usize];
+        let value = self.read(self.addr_abs);
 
         // [Read-Modify-Write] Write original value back
-        self.memory[self.addr_abs as usize] = value;
+        self.write(self.addr_abs, value);
 
         let carry_flag = if self.get_flag(Flag::Carry) { 1 } else { 0 };
 
@@ -484,7 +500,7 @@ impl Cpu {
         self.set_flag(Flag::Negative, res & 0x80 != 0);
 
         // Step 4: Write result
-        self.memory[self.addr_abs as usize] = res;
+        self.write(self.addr_abs, res);
     }
 
     /// ROL - Rotate Left (Accumulator)
@@ -502,10 +518,10 @@ impl Cpu {
 
     /// LSR - Logical Shift Right
     pub fn lsr_mem(&mut self) {
-        let value = self.memory[self.addr_abs as usize];
+        let value = self.read(self.addr_abs);
 
         // [Read-Modify-Write] Write original value back
-        self.memory[self.addr_abs as usize] = value;
+        self.write(self.addr_abs, value);
 
         // Step 2: Perform shift
         let res = value >> 1;
@@ -516,7 +532,7 @@ impl Cpu {
         self.set_flag(Flag::Negative, res & 0x80 != 0);
 
         // Step 4: Write result
-        self.memory[self.addr_abs as usize] = res;
+        self.write(self.addr_abs, res);
     }
 
     /// LSR - Logical Shift Right (Accumulator)
@@ -533,10 +549,10 @@ impl Cpu {
 
     /// ROR - Rotate Right (Memory)
     pub fn ror_mem(&mut self) {
-        let value = self.memory[self.addr_abs as usize];
+        let value = self.read(self.addr_abs);
 
         // [Read-Modify-Write] Write original value back
-        self.memory[self.addr_abs as usize] = value;
+        self.write(self.addr_abs, value);
 
         let carry_flag = if self.get_flag(Flag::Carry) { 1 } else { 0 };
 
@@ -549,7 +565,7 @@ impl Cpu {
         self.set_flag(Flag::Negative, res & 0x80 != 0);
 
         // Step 4: Write result
-        self.memory[self.addr_abs as usize] = res;
+        self.write(self.addr_abs, res);
     }
 
     /// ROR - Rotate Right (Accumulator)
@@ -567,7 +583,7 @@ impl Cpu {
 
     /// STX - Store X
     pub fn stx(&mut self) {
-        self.memory[self.addr_abs as usize] = self.x;
+        self.write(self.addr_abs, self.x);
     }
 
     /// TXA - Transfer X to A
@@ -610,10 +626,10 @@ impl Cpu {
 
     /// DEC - Decrement Memory
     pub fn dec(&mut self) {
-        let value = self.memory[self.addr_abs as usize];
+        let value = self.read(self.addr_abs);
 
         // [Read-Modify-Write] Write original value back
-        self.memory[self.addr_abs as usize] = value;
+        self.write(self.addr_abs, value);
 
         // Step 2: Perform math
         let res = value.wrapping_sub(1);
@@ -623,7 +639,7 @@ impl Cpu {
         self.set_flag(Flag::Negative, res & 0x80 != 0);
 
         // Step 4: Write result
-        self.memory[self.addr_abs as usize] = res;
+        self.write(self.addr_abs, res);
     }
 
     /// DEX - Decrement X
@@ -636,10 +652,10 @@ impl Cpu {
 
     /// INC - Increment Memory
     pub fn inc(&mut self) {
-        let value = self.memory[self.addr_abs as usize];
+        let value = self.read(self.addr_abs);
 
         // [Read-Modify-Write] Write original value back
-        self.memory[self.addr_abs as usize] = value;
+        self.write(self.addr_abs, value);
 
         // Step 2: Perform math
         let res = value.wrapping_add(1);
@@ -649,9 +665,214 @@ impl Cpu {
         self.set_flag(Flag::Negative, res & 0x80 != 0);
 
         // Step 4: Write result
-        self.memory[self.addr_abs as usize] = res;
+        self.write(self.addr_abs, res);
     }
 
     /// NOP - No Operation
     pub fn nop(&mut self) {}
+
+    // Undocumented NMOS opcodes, see https://www.masswerk.at/6502/6502_instruction_set.html#illegals
+
+    /// SLO - ASL then ORA
+    pub fn slo(&mut self) {
+        let res = self.read_modify_write(|cpu, value| {
+            cpu.set_flag(Flag::Carry, value & 0x80 != 0);
+            value << 1
+        });
+        self.a |= res;
+
+        self.set_flag(Flag::Zero, self.a == 0);
+        self.set_flag(Flag::Negative, self.a & 0x80 != 0);
+    }
+
+    /// RLA - ROL then AND
+    pub fn rla(&mut self) {
+        let res = self.read_modify_write(|cpu, value| {
+            let carry_in = cpu.get_flag(Flag::Carry) as u8;
+            cpu.set_flag(Flag::Carry, value & 0x80 != 0);
+            value << 1 | carry_in
+        });
+        self.a &= res;
+
+        self.set_flag(Flag::Zero, self.a == 0);
+        self.set_flag(Flag::Negative, self.a & 0x80 != 0);
+    }
+
+    /// SRE - LSR then EOR
+    pub fn sre(&mut self) {
+        let res = self.read_modify_write(|cpu, value| {
+            cpu.set_flag(Flag::Carry, value & 0x01 != 0);
+            value >> 1
+        });
+        self.a ^= res;
+
+        self.set_flag(Flag::Zero, self.a == 0);
+        self.set_flag(Flag::Negative, self.a & 0x80 != 0);
+    }
+
+    /// RRA - ROR then ADC
+    pub fn rra(&mut self) {
+        let res = self.read_modify_write(|cpu, value| {
+            let carry_in = cpu.get_flag(Flag::Carry) as u8;
+            cpu.set_flag(Flag::Carry, value & 0x01 != 0);
+            value >> 1 | carry_in << 7
+        });
+        self.add(res);
+    }
+
+    /// SAX - Store A AND X
+    pub fn sax(&mut self) {
+        self.write(self.addr_abs, self.a & self.x);
+    }
+
+    /// LAX - LDA and LDX at once
+    pub fn lax(&mut self) {
+        self.lda();
+        self.x = self.a;
+    }
+
+    /// DCP - DEC then CMP
+    pub fn dcp(&mut self) {
+        let res = self.read_modify_write(|_, value| value.wrapping_sub(1));
+
+        self.set_flag(Flag::Carry, self.a >= res);
+        self.set_flag(Flag::Zero, self.a == res);
+        self.set_flag(Flag::Negative, self.a.wrapping_sub(res) & 0x80 != 0);
+    }
+
+    /// ISC - INC then SBC
+    pub fn isc(&mut self) {
+        let res = self.read_modify_write(|_, value| value.wrapping_add(1));
+        self.subtract(res);
+    }
+
+    /// ANC - AND, with N copied into C
+    pub fn anc(&mut self) {
+        self.and();
+        self.set_flag(Flag::Carry, self.a & 0x80 != 0);
+    }
+
+    /// ALR - AND then LSR A
+    pub fn alr(&mut self) {
+        self.and();
+        self.lsr_acc();
+    }
+
+    /// ARR - AND then ROR A, with C and V taken from bits 6 and 5.
+    /// In decimal mode each digit gets ADC's fix up instead.
+    pub fn arr(&mut self) {
+        if self.decimal() {
+            return self.arr_decimal();
+        }
+        self.and();
+        self.ror_acc();
+        self.set_flag(Flag::Carry, self.a & 0x40 != 0);
+        self.set_flag(Flag::Overflow, ((self.a >> 6) ^ (self.a >> 5)) & 0x01 != 0);
+    }
+
+    /// ANE (XAA) - unstable, uses the usual magic constant of $EE
+    pub fn ane(&mut self) {
+        self.fetch();
+        self.a = (self.a | 0xEE) & self.x & self.fetched;
+
+        self.set_flag(Flag::Zero, self.a == 0);
+        self.set_flag(Flag::Negative, self.a & 0x80 != 0);
+    }
+
+    /// LXA - unstable, uses the usual magic constant of $EE
+    pub fn lxa(&mut self) {
+        self.fetch();
+        self.a = (self.a | 0xEE) & self.fetched;
+        self.x = self.a;
+
+        self.set_flag(Flag::Zero, self.a == 0);
+        self.set_flag(Flag::Negative, self.a & 0x80 != 0);
+    }
+
+    /// SBX (AXS) - X = (A AND X) - operand, flags like CMP
+    pub fn sbx(&mut self) {
+        self.fetch();
+        let ax = self.a & self.x;
+        self.x = ax.wrapping_sub(self.fetched);
+
+        self.set_flag(Flag::Carry, ax >= self.fetched);
+        self.set_flag(Flag::Zero, self.x == 0);
+        self.set_flag(Flag::Negative, self.x & 0x80 != 0);
+    }
+
+    /// SHA (AHX) - Store A AND X AND (high byte + 1)
+    pub fn sha(&mut self) {
+        self.store_high_and(self.a & self.x, self.y);
+    }
+
+    /// SHX - Store X AND (high byte + 1)
+    pub fn shx(&mut self) {
+        self.store_high_and(self.x, self.y);
+    }
+
+    /// SHY - Store Y AND (high byte + 1)
+    pub fn shy(&mut self) {
+        self.store_high_and(self.y, self.x);
+    }
+
+    /// TAS - SP = A AND X, then SHA's store with SP
+    pub fn tas(&mut self) {
+        self.sp = self.a & self.x;
+        self.store_high_and(self.sp, self.y);
+    }
+
+    /// LAS - A, X and SP = operand AND SP
+    pub fn las(&mut self) {
+        self.fetch();
+        self.sp &= self.fetched;
+        self.a = self.sp;
+        self.x = self.sp;
+
+        self.set_flag(Flag::Zero, self.a == 0);
+        self.set_flag(Flag::Negative, self.a & 0x80 != 0);
+    }
+
+    /// JAM (KIL) - Locks the CPU up until the next reset
+    pub fn jam(&mut self) {
+        self.pc = self.pc.wrapping_sub(1);
+        self.halted = Some(CpuError::Jammed {
+            pc: self.pc,
+            opcode: self.opcode,
+        });
+    }
+
+    /// Stops on an opcode whose class is set to `IllegalPolicy::Trap`
+    pub fn trap(&mut self) {
+        self.pc = self.pc.wrapping_sub(1);
+        self.halted = Some(CpuError::IllegalOpcode {
+            pc: self.pc,
+            opcode: self.opcode,
+        });
+    }
+
+    /// Read-modify-write cycle shared by the combined opcodes, returns the
+    /// value written
+    fn read_modify_write(&mut self, modify: fn(&mut Cpu, u8) -> u8) -> u8 {
+        let value = self.read(self.addr_abs);
+
+        // [Read-Modify-Write] Write original value back
+        self.write(self.addr_abs, value);
+        let res = modify(self, value);
+        self.write(self.addr_abs, res);
+        res
+    }
+
+    /// The SH* stores AND `value` with the base address's high byte + 1.
+    /// When indexing crosses a page that value also replaces the high byte
+    /// of the address.
+    fn store_high_and(&mut self, value: u8, index: u8) {
+        let base = self.addr_abs.wrapping_sub(index as u16);
+        let value = value & ((base >> 8) as u8).wrapping_add(1);
+        let addr = if base & 0xFF00 != self.addr_abs & 0xFF00 {
+            (value as u16) << 8 | (self.addr_abs & 0x00FF)
+        } else {
+            self.addr_abs
+        };
+        self.write(addr, value);
+    }
 }
@@ -1,3 +1,19 @@
+pub mod assembler;
+pub mod banking;
+pub mod blocks;
+pub mod bus;
 pub mod cpu;
+pub mod decimal;
+pub mod devices;
+pub mod disassembler;
+pub mod heatmap;
+pub mod illegal;
 pub mod instructions;
+pub mod lockstep;
+pub mod opcodes;
+pub mod profiler;
+pub mod rewind;
+pub mod stack;
+pub mod systems;
 pub mod table;
+pub mod uninit;
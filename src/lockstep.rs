@@ -0,0 +1,292 @@
+use crate::{cpu::Cpu, disassembler::Line};
+use std::{collections::VecDeque, error::Error, fmt, str::FromStr};
+
+/// Instructions of disassembly kept for a `Divergence`
+const CONTEXT: usize = 8;
+
+/// What one instruction (or interrupt) did: where it started, the registers
+/// after it, its cycles and its writes. Displays as one line of a trace,
+/// `0400 PC:0402 A:01 X:00 Y:00 SP:FD P:24 CYC:2 W:0300=01`, and parses
+/// back from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Step {
+    pub addr: u16,
+    pub pc: u16,
+    pub a: u8,
+    pub x: u8,
+    pub y: u8,
+    pub sp: u8,
+    pub status: u8,
+    pub cycles: u32,
+    pub writes: Vec<(u16, u8)>,
+}
+
+impl Step {
+    /// Runs one instruction on `cpu`, which has to be logging writes
+    /// (`Bus::log_writes`) for them to show up
+    pub fn run(cpu: &mut Cpu) -> Step {
+        cpu.memory.take_writes();
+        let addr = cpu.pc;
+        let cycles = cpu.step();
+        Step {
+            addr,
+            pc: cpu.pc,
+            a: cpu.a,
+            x: cpu.x,
+            y: cpu.y,
+            sp: cpu.sp,
+            status: cpu.status,
+            cycles,
+            writes: cpu.memory.take_writes(),
+        }
+    }
+
+    /// Which fields of `actual` don't match this one
+    pub fn differences(&self, actual: &Step) -> Vec<Field> {
+        [
+            (Field::Addr, self.addr != actual.addr),
+            (Field::Pc, self.pc != actual.pc),
+            (Field::A, self.a != actual.a),
+            (Field::X, self.x != actual.x),
+            (Field::Y, self.y != actual.y),
+            (Field::Sp, self.sp != actual.sp),
+            (Field::Status, self.status != actual.status),
+            (Field::Cycles, self.cycles != actual.cycles),
+            (Field::Writes, self.writes != actual.writes),
+        ]
+        .into_iter()
+        .filter_map(|(field, differs)| differs.then_some(field))
+        .collect()
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04X} PC:{:04X} A:{:02X} X:{:02X} Y:{:02X} SP:{:02X} P:{:02X} CYC:{}",
+            self.addr, self.pc, self.a, self.x, self.y, self.sp, self.status, self.cycles
+        )?;
+        for (i, (addr, value)) in self.writes.iter().enumerate() {
+            let separator = if i == 0 { " W:" } else { "," };
+            write!(f, "{}{:04X}={:02X}", separator, addr, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// A trace line that doesn't read as a `Step`
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseStepError(pub String);
+
+impl fmt::Display for ParseStepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "not a trace line: `{}`", self.0)
+    }
+}
+
+impl Error for ParseStepError {}
+
+impl FromStr for Step {
+    type Err = ParseStepError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let error = || ParseStepError(line.to_string());
+        let hex = |text: &str| u16::from_str_radix(text, 16).map_err(|_| error());
+
+        let mut words = line.split_whitespace();
+        let addr = hex(words.next().ok_or_else(error)?)?;
+        let mut field = |name: &str| -> Result<u16, ParseStepError> {
+            let word = words.next().ok_or_else(error)?;
+            hex(word.strip_prefix(name).ok_or_else(error)?)
+        };
+        let pc = field("PC:")?;
+        let a = field("A:")? as u8;
+        let x = field("X:")? as u8;
+        let y = field("Y:")? as u8;
+        let sp = field("SP:")? as u8;
+        let status = field("P:")? as u8;
+
+        let cycles = words
+            .next()
+            .and_then(|word| word.strip_prefix("CYC:"))
+            .and_then(|cycles| cycles.parse().ok())
+            .ok_or_else(error)?;
+        let writes = match words.next() {
+            Some(word) => word
+                .strip_prefix("W:")
+                .ok_or_else(error)?
+                .split(',')
+                .map(|write| {
+                    let (addr, value) = write.split_once('=').ok_or_else(error)?;
+                    Ok((hex(addr)?, hex(value)? as u8))
+                })
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Step {
+            addr,
+            pc,
+            a,
+            x,
+            y,
+            sp,
+            status,
+            cycles,
+            writes,
+        })
+    }
+}
+
+/// Part of a `Step` two runs disagree on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    /// The instruction started somewhere else
+    Addr,
+    Pc,
+    A,
+    X,
+    Y,
+    Sp,
+    Status,
+    Cycles,
+    Writes,
+}
+
+/// The first instruction two runs disagree on
+#[derive(Debug)]
+pub struct Divergence {
+    /// Instructions that agreed before this one
+    pub instruction: u64,
+    pub fields: Vec<Field>,
+    pub expected: Step,
+    pub actual: Step,
+    /// The last instructions run, this one last, disassembled before they ran
+    pub context: Vec<Line>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "diverged on instruction {} at ${:04X}: {:?}",
+            self.instruction, self.actual.addr, self.fields
+        )?;
+        for (i, line) in self.context.iter().enumerate() {
+            let marker = if i + 1 == self.context.len() {
+                '>'
+            } else {
+                ' '
+            };
+            let bytes: Vec<String> = line.bytes().iter().map(|b| format!("{:02X}", b)).collect();
+            writeln!(
+                f,
+                "{} {:04X}  {:<8}  {}",
+                marker,
+                line.addr,
+                bytes.join(" "),
+                line
+            )?;
+        }
+        writeln!(f, "expected {}", self.expected)?;
+        write!(f, "actual   {}", self.actual)
+    }
+}
+
+impl Error for Divergence {}
+
+/// Counts instructions and keeps the disassembly context for a
+/// `Divergence`
+#[derive(Default)]
+struct Checker {
+    instructions: u64,
+    history: VecDeque<Line>,
+}
+
+impl Checker {
+    /// Runs an instruction on `cpu` and compares it against what
+    /// `expected` says it should do
+    fn check(
+        &mut self,
+        cpu: &mut Cpu,
+        expected: impl FnOnce() -> Step,
+    ) -> Result<(), Box<Divergence>> {
+        if self.history.len() == CONTEXT {
+            self.history.pop_front();
+        }
+        self.history.push_back(cpu.memory.disassemble(cpu.pc, 1, cpu.variant)[0]);
+
+        let expected = expected();
+        let actual = Step::run(cpu);
+        let fields = expected.differences(&actual);
+        if !fields.is_empty() {
+            return Err(Box::new(Divergence {
+                instruction: self.instructions,
+                fields,
+                expected,
+                actual,
+                context: self.history.iter().copied().collect(),
+            }));
+        }
+        self.instructions += 1;
+        Ok(())
+    }
+}
+
+/// Runs a reference `Cpu` and one under test side by side, an instruction
+/// at a time, until they disagree on registers, flags, cycles or writes
+pub struct LockStep {
+    pub reference: Cpu,
+    pub candidate: Cpu,
+    checker: Checker,
+}
+
+impl LockStep {
+    /// Both CPUs should start out in the same state
+    pub fn new(mut reference: Cpu, mut candidate: Cpu) -> Self {
+        reference.memory.log_writes(true);
+        candidate.memory.log_writes(true);
+        LockStep {
+            reference,
+            candidate,
+            checker: Checker::default(),
+        }
+    }
+
+    /// Instructions both ran the same
+    pub fn instructions(&self) -> u64 {
+        self.checker.instructions
+    }
+
+    pub fn step(&mut self) -> Result<(), Box<Divergence>> {
+        let reference = &mut self.reference;
+        self.checker
+            .check(&mut self.candidate, || Step::run(reference))
+    }
+
+    /// Runs up to `count` instructions, returns the total that agreed
+    pub fn run(&mut self, count: u64) -> Result<u64, Box<Divergence>> {
+        for _ in 0..count {
+            self.step()?;
+        }
+        Ok(self.instructions())
+    }
+}
+
+/// Runs `count` instructions on `cpu`, for `replay` to compare against
+/// later
+pub fn record(cpu: &mut Cpu, count: usize) -> Vec<Step> {
+    cpu.memory.log_writes(true);
+    (0..count).map(|_| Step::run(cpu)).collect()
+}
+
+/// Runs `cpu` through a recorded trace, stopping where it goes its own way
+pub fn replay(cpu: &mut Cpu, trace: &[Step]) -> Result<(), Box<Divergence>> {
+    cpu.memory.log_writes(true);
+    let mut checker = Checker::default();
+    for step in trace {
+        checker.check(cpu, || step.clone())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,483 @@
+use crate::{cpu::Cpu, illegal::OpcodeClass, instructions::Instruction};
+
+// Information grabbed from https://www.masswerk.at/6502/6502_instruction_set.html
+
+/// How an instruction finds its operand
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddrMode {
+    Implied,
+    Accumulator,
+    Immediate,
+    ZeroPage,
+    ZeroPageX,
+    ZeroPageY,
+    Absolute,
+    AbsoluteX,
+    AbsoluteY,
+    Indirect,
+    IndirectX,
+    IndirectY,
+    Relative,
+}
+
+impl AddrMode {
+    /// Bytes following the opcode
+    pub const fn operand_bytes(self) -> u8 {
+        match self {
+            AddrMode::Implied | AddrMode::Accumulator => 0,
+            AddrMode::Absolute | AddrMode::AbsoluteX | AddrMode::AbsoluteY | AddrMode::Indirect => {
+                2
+            }
+            _ => 1,
+        }
+    }
+
+    /// The `Cpu` method that resolves the operand's address
+    pub(crate) const fn function(self) -> fn(&mut Cpu) -> u8 {
+        match self {
+            AddrMode::Implied | AddrMode::Accumulator => Cpu::imp,
+            AddrMode::Immediate => Cpu::imm,
+            AddrMode::ZeroPage => Cpu::zp0,
+            AddrMode::ZeroPageX => Cpu::zpx,
+            AddrMode::ZeroPageY => Cpu::zpy,
+            AddrMode::Absolute => Cpu::abs,
+            AddrMode::AbsoluteX => Cpu::absx,
+            AddrMode::AbsoluteY => Cpu::absy,
+            AddrMode::Indirect => Cpu::ind,
+            AddrMode::IndirectX => Cpu::indx,
+            AddrMode::IndirectY => Cpu::indy,
+            AddrMode::Relative => Cpu::rel,
+        }
+    }
+}
+
+/// What an instruction does with the memory its addressing mode points at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    /// Writes on its last cycle
+    Write,
+    /// Writes the old value back, then the new one, on its last two cycles
+    ReadModifyWrite,
+    /// Registers, the stack and control flow
+    Other,
+}
+
+/// Cycles an instruction can take on top of its base count
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PagePenalty {
+    None,
+    /// One more when indexing crosses a page. Indexed writes and RMWs
+    /// always spend that cycle, so it's in their base count instead.
+    PageCross,
+    /// One more when taken, another when the target is on another page
+    Branch,
+}
+
+/// Chips running the 6502 instruction set. The 6507 and 6510 decode like
+/// the 6502.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Nmos6502,
+    /// The NES CPU, an NMOS 6502 with decimal mode cut out
+    Ricoh2A03,
+    /// CMOS parts turned every undocumented opcode into a NOP or a new
+    /// instruction. Only the NOPs are emulated: undocumented opcodes run as
+    /// under `IllegalPolicy::Nop`, disassemble as NOP and don't assemble.
+    Cmos65C02,
+}
+
+/// Everything known about one opcode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OpcodeSpec {
+    pub opcode: u8,
+    /// Lowercase, as `Instruction::name`
+    pub mnemonic: &'static str,
+    pub mode: AddrMode,
+    /// Opcode and operand, plus the padding byte after BRK
+    pub bytes: u8,
+    /// Cycles before any penalty
+    pub cycles: u8,
+    pub penalty: PagePenalty,
+    pub access: Access,
+    /// `None` for documented opcodes
+    pub class: Option<OpcodeClass>,
+}
+
+impl Variant {
+    /// Whether ADC and SBC honour the D flag. The 65C02 gets the NMOS
+    /// results, its valid N and Z flags and extra cycle aren't emulated.
+    pub const fn has_decimal_mode(self) -> bool {
+        !matches!(self, Variant::Ricoh2A03)
+    }
+}
+
+impl OpcodeSpec {
+    const fn new(
+        opcode: u8,
+        mnemonic: &'static str,
+        mode: AddrMode,
+        bytes: u8,
+        cycles: u8,
+        access: Access,
+        class: Option<OpcodeClass>,
+    ) -> Self {
+        let penalty = match (mode, access) {
+            (AddrMode::Relative, _) => PagePenalty::Branch,
+            (AddrMode::AbsoluteX | AddrMode::AbsoluteY | AddrMode::IndirectY, Access::Read) => {
+                PagePenalty::PageCross
+            }
+            _ => PagePenalty::None,
+        };
+        OpcodeSpec {
+            opcode,
+            mnemonic,
+            mode,
+            bytes,
+            cycles,
+            penalty,
+            access,
+            class,
+        }
+    }
+
+    pub const fn documented(&self) -> bool {
+        self.class.is_none()
+    }
+
+    /// Whether `variant` runs this opcode as listed, the ones it doesn't
+    /// are NOPs there
+    pub const fn available(&self, variant: Variant) -> bool {
+        self.documented() || !matches!(variant, Variant::Cmos65C02)
+    }
+
+    /// Whether the next instruction can be somewhere other than right after
+    /// this one (branches, jumps, returns, BRK and JAM)
+    pub fn jumps(&self) -> bool {
+        self.mode == AddrMode::Relative
+            || matches!(self.mnemonic, "jmp" | "jsr" | "rts" | "rti" | "brk" | "jam")
+    }
+}
+
+/// The spec of every opcode, indexed by opcode
+pub static OPCODES: [OpcodeSpec; 256] = {
+    let mut specs = [spec(0); 256];
+    let mut opcode = 0;
+    while opcode < 256 {
+        specs[opcode] = spec(opcode as u8);
+        opcode += 1;
+    }
+    specs
+};
+
+macro_rules! opcode_bytes {
+    ($mode:ident) => {
+        1 + AddrMode::$mode.operand_bytes()
+    };
+    ($mode:ident, $bytes:literal) => {
+        $bytes
+    };
+}
+
+macro_rules! opcode_class {
+    () => {
+        None
+    };
+    ($class:ident) => {
+        Some(OpcodeClass::$class)
+    };
+}
+
+/// Every opcode once: mnemonic, operation, addressing mode (with the
+/// length in brackets where the mode doesn't give it), base cycles, memory
+/// access and, for undocumented ones, their `OpcodeClass`. The spec and
+/// the table of fn pointers (`INSTRUCTIONS`) are both generated from this
+/// list.
+macro_rules! opcodes {
+    ($($opcode:literal => $name:ident, $op:ident, $mode:ident $([$bytes:literal])?, $cycles:literal,
+        $access:ident $(, $class:ident)?;)*) => {
+        pub(crate) const fn spec(opcode: u8) -> OpcodeSpec {
+            match opcode {
+                $($opcode => OpcodeSpec::new(
+                    $opcode,
+                    stringify!($name),
+                    AddrMode::$mode,
+                    opcode_bytes!($mode $(, $bytes)?),
+                    $cycles,
+                    Access::$access,
+                    opcode_class!($($class)?),
+                ),)*
+            }
+        }
+
+        pub(crate) const fn decode(opcode: u8) -> Instruction {
+            match opcode {
+                $($opcode => Instruction {
+                    name: stringify!($name),
+                    op: Cpu::$op,
+                    addr_mode: AddrMode::$mode.function(),
+                    cycles: $cycles,
+                },)*
+            }
+        }
+    };
+}
+
+opcodes! {
+    0x00 => brk, brk, Implied[2], 7, Other;
+    0x01 => ora, ora, IndirectX, 6, Read;
+    0x02 => jam, jam, Implied, 2, Other, Jam;
+    0x03 => slo, slo, IndirectX, 8, ReadModifyWrite, Stable;
+    0x04 => nop, nop, ZeroPage, 3, Read, Nop;
+    0x05 => ora, ora, ZeroPage, 3, Read;
+    0x06 => asl, asl_mem, ZeroPage, 5, ReadModifyWrite;
+    0x07 => slo, slo, ZeroPage, 5, ReadModifyWrite, Stable;
+    0x08 => php, php, Implied, 3, Other;
+    0x09 => ora, ora, Immediate, 2, Read;
+    0x0A => asl, asl_acc, Accumulator, 2, Other;
+    0x0B => anc, anc, Immediate, 2, Read, Stable;
+    0x0C => nop, nop, Absolute, 4, Read, Nop;
+    0x0D => ora, ora, Absolute, 4, Read;
+    0x0E => asl, asl_mem, Absolute, 6, ReadModifyWrite;
+    0x0F => slo, slo, Absolute, 6, ReadModifyWrite, Stable;
+    0x10 => bpl, bpl, Relative, 2, Other;
+    0x11 => ora, ora, IndirectY, 5, Read;
+    0x12 => jam, jam, Implied, 2, Other, Jam;
+    0x13 => slo, slo, IndirectY, 8, ReadModifyWrite, Stable;
+    0x14 => nop, nop, ZeroPageX, 4, Read, Nop;
+    0x15 => ora, ora, ZeroPageX, 4, Read;
+    0x16 => asl, asl_mem, ZeroPageX, 6, ReadModifyWrite;
+    0x17 => slo, slo, ZeroPageX, 6, ReadModifyWrite, Stable;
+    0x18 => clc, clc, Implied, 2, Other;
+    0x19 => ora, ora, AbsoluteY, 4, Read;
+    0x1A => nop, nop, Implied, 2, Other, Nop;
+    0x1B => slo, slo, AbsoluteY, 7, ReadModifyWrite, Stable;
+    0x1C => nop, nop, AbsoluteX, 4, Read, Nop;
+    0x1D => ora, ora, AbsoluteX, 4, Read;
+    0x1E => asl, asl_mem, AbsoluteX, 7, ReadModifyWrite;
+    0x1F => slo, slo, AbsoluteX, 7, ReadModifyWrite, Stable;
+    0x20 => jsr, jsr, Absolute, 6, Other;
+    0x21 => and, and, IndirectX, 6, Read;
+    0x22 => jam, jam, Implied, 2, Other, Jam;
+    0x23 => rla, rla, IndirectX, 8, ReadModifyWrite, Stable;
+    0x24 => bit, bit, ZeroPage, 3, Read;
+    0x25 => and, and, ZeroPage, 3, Read;
+    0x26 => rol, rol_mem, ZeroPage, 5, ReadModifyWrite;
+    0x27 => rla, rla, ZeroPage, 5, ReadModifyWrite, Stable;
+    0x28 => plp, plp, Implied, 4, Other;
+    0x29 => and, and, Immediate, 2, Read;
+    0x2A => rol, rol_acc, Accumulator, 2, Other;
+    0x2B => anc, anc, Immediate, 2, Read, Stable;
+    0x2C => bit, bit, Absolute, 4, Read;
+    0x2D => and, and, Absolute, 4, Read;
+    0x2E => rol, rol_mem, Absolute, 6, ReadModifyWrite;
+    0x2F => rla, rla, Absolute, 6, ReadModifyWrite, Stable;
+    0x30 => bmi, bmi, Relative, 2, Other;
+    0x31 => and, and, IndirectY, 5, Read;
+    0x32 => jam, jam, Implied, 2, Other, Jam;
+    0x33 => rla, rla, IndirectY, 8, ReadModifyWrite, Stable;
+    0x34 => nop, nop, ZeroPageX, 4, Read, Nop;
+    0x35 => and, and, ZeroPageX, 4, Read;
+    0x36 => rol, rol_mem, ZeroPageX, 6, ReadModifyWrite;
+    0x37 => rla, rla, ZeroPageX, 6, ReadModifyWrite, Stable;
+    0x38 => sec, sec, Implied, 2, Other;
+    0x39 => and, and, AbsoluteY, 4, Read;
+    0x3A => nop, nop, Implied, 2, Other, Nop;
+    0x3B => rla, rla, AbsoluteY, 7, ReadModifyWrite, Stable;
+    0x3C => nop, nop, AbsoluteX, 4, Read, Nop;
+    0x3D => and, and, AbsoluteX, 4, Read;
+    0x3E => rol, rol_mem, AbsoluteX, 7, ReadModifyWrite;
+    0x3F => rla, rla, AbsoluteX, 7, ReadModifyWrite, Stable;
+    0x40 => rti, rti, Implied, 6, Other;
+    0x41 => eor, eor, IndirectX, 6, Read;
+    0x42 => jam, jam, Implied, 2, Other, Jam;
+    0x43 => sre, sre, IndirectX, 8, ReadModifyWrite, Stable;
+    0x44 => nop, nop, ZeroPage, 3, Read, Nop;
+    0x45 => eor, eor, ZeroPage, 3, Read;
+    0x46 => lsr, lsr_mem, ZeroPage, 5, ReadModifyWrite;
+    0x47 => sre, sre, ZeroPage, 5, ReadModifyWrite, Stable;
+    0x48 => pha, pha, Implied, 3, Other;
+    0x49 => eor, eor, Immediate, 2, Read;
+    0x4A => lsr, lsr_acc, Accumulator, 2, Other;
+    0x4B => alr, alr, Immediate, 2, Read, Stable;
+    0x4C => jmp, jmp, Absolute, 3, Other;
+    0x4D => eor, eor, Absolute, 4, Read;
+    0x4E => lsr, lsr_mem, Absolute, 6, ReadModifyWrite;
+    0x4F => sre, sre, Absolute, 6, ReadModifyWrite, Stable;
+    0x50 => bvc, bvc, Relative, 2, Other;
+    0x51 => eor, eor, IndirectY, 5, Read;
+    0x52 => jam, jam, Implied, 2, Other, Jam;
+    0x53 => sre, sre, IndirectY, 8, ReadModifyWrite, Stable;
+    0x54 => nop, nop, ZeroPageX, 4, Read, Nop;
+    0x55 => eor, eor, ZeroPageX, 4, Read;
+    0x56 => lsr, lsr_mem, ZeroPageX, 6, ReadModifyWrite;
+    0x57 => sre, sre, ZeroPageX, 6, ReadModifyWrite, Stable;
+    0x58 => cli, cli, Implied, 2, Other;
+    0x59 => eor, eor, AbsoluteY, 4, Read;
+    0x5A => nop, nop, Implied, 2, Other, Nop;
+    0x5B => sre, sre, AbsoluteY, 7, ReadModifyWrite, Stable;
+    0x5C => nop, nop, AbsoluteX, 4, Read, Nop;
+    0x5D => eor, eor, AbsoluteX, 4, Read;
+    0x5E => lsr, lsr_mem, AbsoluteX, 7, ReadModifyWrite;
+    0x5F => sre, sre, AbsoluteX, 7, ReadModifyWrite, Stable;
+    0x60 => rts, rts, Implied, 6, Other;
+    0x61 => adc, adc, IndirectX, 6, Read;
+    0x62 => jam, jam, Implied, 2, Other, Jam;
+    0x63 => rra, rra, IndirectX, 8, ReadModifyWrite, Stable;
+    0x64 => nop, nop, ZeroPage, 3, Read, Nop;
+    0x65 => adc, adc, ZeroPage, 3, Read;
+    0x66 => ror, ror_mem, ZeroPage, 5, ReadModifyWrite;
+    0x67 => rra, rra, ZeroPage, 5, ReadModifyWrite, Stable;
+    0x68 => pla, pla, Implied, 4, Other;
+    0x69 => adc, adc, Immediate, 2, Read;
+    0x6A => ror, ror_acc, Accumulator, 2, Other;
+    0x6B => arr, arr, Immediate, 2, Read, Stable;
+    0x6C => jmp, jmp, Indirect, 5, Other;
+    0x6D => adc, adc, Absolute, 4, Read;
+    0x6E => ror, ror_mem, Absolute, 6, ReadModifyWrite;
+    0x6F => rra, rra, Absolute, 6, ReadModifyWrite, Stable;
+    0x70 => bvs, bvs, Relative, 2, Other;
+    0x71 => adc, adc, IndirectY, 5, Read;
+    0x72 => jam, jam, Implied, 2, Other, Jam;
+    0x73 => rra, rra, IndirectY, 8, ReadModifyWrite, Stable;
+    0x74 => nop, nop, ZeroPageX, 4, Read, Nop;
+    0x75 => adc, adc, ZeroPageX, 4, Read;
+    0x76 => ror, ror_mem, ZeroPageX, 6, ReadModifyWrite;
+    0x77 => rra, rra, ZeroPageX, 6, ReadModifyWrite, Stable;
+    0x78 => sei, sei, Implied, 2, Other;
+    0x79 => adc, adc, AbsoluteY, 4, Read;
+    0x7A => nop, nop, Implied, 2, Other, Nop;
+    0x7B => rra, rra, AbsoluteY, 7, ReadModifyWrite, Stable;
+    0x7C => nop, nop, AbsoluteX, 4, Read, Nop;
+    0x7D => adc, adc, AbsoluteX, 4, Read;
+    0x7E => ror, ror_mem, AbsoluteX, 7, ReadModifyWrite;
+    0x7F => rra, rra, AbsoluteX, 7, ReadModifyWrite, Stable;
+    0x80 => nop, nop, Immediate, 2, Read, Nop;
+    0x81 => sta, sta, IndirectX, 6, Write;
+    0x82 => nop, nop, Immediate, 2, Read, Nop;
+    0x83 => sax, sax, IndirectX, 6, Write, Stable;
+    0x84 => sty, sty, ZeroPage, 3, Write;
+    0x85 => sta, sta, ZeroPage, 3, Write;
+    0x86 => stx, stx, ZeroPage, 3, Write;
+    0x87 => sax, sax, ZeroPage, 3, Write, Stable;
+    0x88 => dey, dey, Implied, 2, Other;
+    0x89 => nop, nop, Immediate, 2, Read, Nop;
+    0x8A => txa, txa, Implied, 2, Other;
+    0x8B => ane, ane, Immediate, 2, Read, Unstable;
+    0x8C => sty, sty, Absolute, 4, Write;
+    0x8D => sta, sta, Absolute, 4, Write;
+    0x8E => stx, stx, Absolute, 4, Write;
+    0x8F => sax, sax, Absolute, 4, Write, Stable;
+    0x90 => bcc, bcc, Relative, 2, Other;
+    0x91 => sta, sta, IndirectY, 6, Write;
+    0x92 => jam, jam, Implied, 2, Other, Jam;
+    0x93 => sha, sha, IndirectY, 6, Write, Unstable;
+    0x94 => sty, sty, ZeroPageX, 4, Write;
+    0x95 => sta, sta, ZeroPageX, 4, Write;
+    0x96 => stx, stx, ZeroPageY, 4, Write;
+    0x97 => sax, sax, ZeroPageY, 4, Write, Stable;
+    0x98 => tya, tya, Implied, 2, Other;
+    0x99 => sta, sta, AbsoluteY, 5, Write;
+    0x9A => txs, txs, Implied, 2, Other;
+    0x9B => tas, tas, AbsoluteY, 5, Write, Unstable;
+    0x9C => shy, shy, AbsoluteX, 5, Write, Unstable;
+    0x9D => sta, sta, AbsoluteX, 5, Write;
+    0x9E => shx, shx, AbsoluteY, 5, Write, Unstable;
+    0x9F => sha, sha, AbsoluteY, 5, Write, Unstable;
+    0xA0 => ldy, ldy, Immediate, 2, Read;
+    0xA1 => lda, lda, IndirectX, 6, Read;
+    0xA2 => ldx, ldx, Immediate, 2, Read;
+    0xA3 => lax, lax, IndirectX, 6, Read, Stable;
+    0xA4 => ldy, ldy, ZeroPage, 3, Read;
+    0xA5 => lda, lda, ZeroPage, 3, Read;
+    0xA6 => ldx, ldx, ZeroPage, 3, Read;
+    0xA7 => lax, lax, ZeroPage, 3, Read, Stable;
+    0xA8 => tay, tay, Implied, 2, Other;
+    0xA9 => lda, lda, Immediate, 2, Read;
+    0xAA => tax, tax, Implied, 2, Other;
+    0xAB => lxa, lxa, Immediate, 2, Read, Unstable;
+    0xAC => ldy, ldy, Absolute, 4, Read;
+    0xAD => lda, lda, Absolute, 4, Read;
+    0xAE => ldx, ldx, Absolute, 4, Read;
+    0xAF => lax, lax, Absolute, 4, Read, Stable;
+    0xB0 => bcs, bcs, Relative, 2, Other;
+    0xB1 => lda, lda, IndirectY, 5, Read;
+    0xB2 => jam, jam, Implied, 2, Other, Jam;
+    0xB3 => lax, lax, IndirectY, 5, Read, Stable;
+    0xB4 => ldy, ldy, ZeroPageX, 4, Read;
+    0xB5 => lda, lda, ZeroPageX, 4, Read;
+    0xB6 => ldx, ldx, ZeroPageY, 4, Read;
+    0xB7 => lax, lax, ZeroPageY, 4, Read, Stable;
+    0xB8 => clv, clv, Implied, 2, Other;
+    0xB9 => lda, lda, AbsoluteY, 4, Read;
+    0xBA => tsx, tsx, Implied, 2, Other;
+    0xBB => las, las, AbsoluteY, 4, Read, Unstable;
+    0xBC => ldy, ldy, AbsoluteX, 4, Read;
+    0xBD => lda, lda, AbsoluteX, 4, Read;
+    0xBE => ldx, ldx, AbsoluteY, 4, Read;
+    0xBF => lax, lax, AbsoluteY, 4, Read, Stable;
+    0xC0 => cpy, cpy, Immediate, 2, Read;
+    0xC1 => cmp, cmp, IndirectX, 6, Read;
+    0xC2 => nop, nop, Immediate, 2, Read, Nop;
+    0xC3 => dcp, dcp, IndirectX, 8, ReadModifyWrite, Stable;
+    0xC4 => cpy, cpy, ZeroPage, 3, Read;
+    0xC5 => cmp, cmp, ZeroPage, 3, Read;
+    0xC6 => dec, dec, ZeroPage, 5, ReadModifyWrite;
+    0xC7 => dcp, dcp, ZeroPage, 5, ReadModifyWrite, Stable;
+    0xC8 => iny, iny, Implied, 2, Other;
+    0xC9 => cmp, cmp, Immediate, 2, Read;
+    0xCA => dex, dex, Implied, 2, Other;
+    0xCB => sbx, sbx, Immediate, 2, Read, Stable;
+    0xCC => cpy, cpy, Absolute, 4, Read;
+    0xCD => cmp, cmp, Absolute, 4, Read;
+    0xCE => dec, dec, Absolute, 6, ReadModifyWrite;
+    0xCF => dcp, dcp, Absolute, 6, ReadModifyWrite, Stable;
+    0xD0 => bne, bne, Relative, 2, Other;
+    0xD1 => cmp, cmp, IndirectY, 5, Read;
+    0xD2 => jam, jam, Implied, 2, Other, Jam;
+    0xD3 => dcp, dcp, IndirectY, 8, ReadModifyWrite, Stable;
+    0xD4 => nop, nop, ZeroPageX, 4, Read, Nop;
+    0xD5 => cmp, cmp, ZeroPageX, 4, Read;
+    0xD6 => dec, dec, ZeroPageX, 6, ReadModifyWrite;
+    0xD7 => dcp, dcp, ZeroPageX, 6, ReadModifyWrite, Stable;
+    0xD8 => cld, cld, Implied, 2, Other;
+    0xD9 => cmp, cmp, AbsoluteY, 4, Read;
+    0xDA => nop, nop, Implied, 2, Other, Nop;
+    0xDB => dcp, dcp, AbsoluteY, 7, ReadModifyWrite, Stable;
+    0xDC => nop, nop, AbsoluteX, 4, Read, Nop;
+    0xDD => cmp, cmp, AbsoluteX, 4, Read;
+    0xDE => dec, dec, AbsoluteX, 7, ReadModifyWrite;
+    0xDF => dcp, dcp, AbsoluteX, 7, ReadModifyWrite, Stable;
+    0xE0 => cpx, cpx, Immediate, 2, Read;
+    0xE1 => sbc, sbc, IndirectX, 6, Read;
+    0xE2 => nop, nop, Immediate, 2, Read, Nop;
+    0xE3 => isc, isc, IndirectX, 8, ReadModifyWrite, Stable;
+    0xE4 => cpx, cpx, ZeroPage, 3, Read;
+    0xE5 => sbc, sbc, ZeroPage, 3, Read;
+    0xE6 => inc, inc, ZeroPage, 5, ReadModifyWrite;
+    0xE7 => isc, isc, ZeroPage, 5, ReadModifyWrite, Stable;
+    0xE8 => inx, inx, Implied, 2, Other;
+    0xE9 => sbc, sbc, Immediate, 2, Read;
+    0xEA => nop, nop, Implied, 2, Other;
+    0xEB => sbc, sbc, Immediate, 2, Read, Stable;
+    0xEC => cpx, cpx, Absolute, 4, Read;
+    0xED => sbc, sbc, Absolute, 4, Read;
+    0xEE => inc, inc, Absolute, 6, ReadModifyWrite;
+    0xEF => isc, isc, Absolute, 6, ReadModifyWrite, Stable;
+    0xF0 => beq, beq, Relative, 2, Other;
+    0xF1 => sbc, sbc, IndirectY, 5, Read;
+    0xF2 => jam, jam, Implied, 2, Other, Jam;
+    0xF3 => isc, isc, IndirectY, 8, ReadModifyWrite, Stable;
+    0xF4 => nop, nop, ZeroPageX, 4, Read, Nop;
+    0xF5 => sbc, sbc, ZeroPageX, 4, Read;
+    0xF6 => inc, inc, ZeroPageX, 6, ReadModifyWrite;
+    0xF7 => isc, isc, ZeroPageX, 6, ReadModifyWrite, Stable;
+    0xF8 => sed, sed, Implied, 2, Other;
+    0xF9 => sbc, sbc, AbsoluteY, 4, Read;
+    0xFA => nop, nop, Implied, 2, Other, Nop;
+    0xFB => isc, isc, AbsoluteY, 7, ReadModifyWrite, Stable;
+    0xFC => nop, nop, AbsoluteX, 4, Read, Nop;
+    0xFD => sbc, sbc, AbsoluteX, 4, Read;
+    0xFE => inc, inc, AbsoluteX, 7, ReadModifyWrite;
+    0xFF => isc, isc, AbsoluteX, 7, ReadModifyWrite, Stable;
+}
@@ -0,0 +1,257 @@
+use crate::opcodes::OPCODES;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+/// A subroutine or interrupt handler that hasn't returned yet
+struct Frame {
+    /// Where it was entered
+    addr: u16,
+    /// SP before the JSR or interrupt pushed anything, RTS/RTI bring it back
+    sp: u8,
+    /// `Profiler::cycles` when it was entered
+    start: u64,
+    /// Inclusive cycles of the calls it made that returned
+    children: u64,
+}
+
+/// Totals for everything entered at one address
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallStats {
+    pub calls: u64,
+    /// Cycles from entry to return, calls made from it included
+    pub inclusive: u64,
+    /// The same without the calls it made
+    pub exclusive: u64,
+}
+
+/// Per-PC and per-opcode execution counts plus a call graph built from
+/// JSR/RTS, interrupts and RTI. Set `Cpu::profiler` to start collecting.
+/// Subroutines are told apart by entry address and end when SP gets back
+/// to where it was before they were entered, by RTS or by code that drops
+/// its return address and carries on, so open calls can't outnumber what
+/// fits on the stack. Code that uses RTS as a jump confuses the call graph
+/// but nothing else.
+pub struct Profiler {
+    /// Per address: instructions that started there and their cycles
+    executions: Vec<u64>,
+    cycles_at: Vec<u64>,
+    /// Last opcode run at each address, to tell how many bytes it covers
+    opcodes_at: Vec<u8>,
+    /// Per opcode: executions and cycles
+    opcodes: [(u64, u64); 256],
+    /// Cycles seen so far
+    cycles: u64,
+    interrupts: u64,
+    stack: Vec<Frame>,
+    /// Inclusive cycles of top level calls that returned
+    root_children: u64,
+    calls: HashMap<u16, CallStats>,
+    /// Exclusive cycles per call stack, for `write_folded`
+    folded: HashMap<Vec<u16>, u64>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler {
+            executions: vec![0; 0x10000],
+            cycles_at: vec![0; 0x10000],
+            opcodes_at: vec![0; 0x10000],
+            opcodes: [(0, 0); 256],
+            cycles: 0,
+            interrupts: 0,
+            stack: Vec::new(),
+            root_children: 0,
+            calls: HashMap::new(),
+            folded: HashMap::new(),
+        }
+    }
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called by `Cpu::clock` once per instruction with where it started,
+    /// its opcode and cycles, SP before and after and PC after it ran
+    pub(crate) fn instruction(&mut self, addr: u16, opcode: u8, cycles: u8, sp: (u8, u8), pc: u16) {
+        let before = self.cycles;
+        self.cycles += cycles as u64;
+        self.executions[addr as usize] += 1;
+        self.cycles_at[addr as usize] += cycles as u64;
+        self.opcodes_at[addr as usize] = opcode;
+        self.opcodes[opcode as usize].0 += 1;
+        self.opcodes[opcode as usize].1 += cycles as u64;
+
+        match opcode {
+            // JSR's cycles are the caller's, BRK's the handler's like an
+            // interrupt sequence, RTS and RTI the callee's
+            0x20 => self.enter(pc, sp.0, self.cycles),
+            0x00 => self.enter(pc, sp.0, before),
+            _ => {}
+        }
+        self.leave(sp.1);
+    }
+
+    /// Called by `Cpu::clock` when an IRQ or NMI starts its handler at `pc`
+    pub(crate) fn interrupt(&mut self, cycles: u8, sp: u8, pc: u16) {
+        self.interrupts += 1;
+        self.enter(pc, sp, self.cycles);
+        self.cycles += cycles as u64;
+    }
+
+    fn enter(&mut self, addr: u16, sp: u8, start: u64) {
+        self.stack.push(Frame {
+            addr,
+            sp,
+            start,
+            children: 0,
+        });
+    }
+
+    /// Ends every frame entered at `sp` (as it is after the instruction) or
+    /// deeper: RTS and RTI return from them, anything else raising SP that
+    /// far has thrown their return addresses away
+    fn leave(&mut self, sp: u8) {
+        while let Some(frame) = self.stack.last() {
+            if frame.sp > sp {
+                break;
+            }
+            let frame = self.stack.pop().unwrap();
+            let inclusive = self.cycles - frame.start;
+            let exclusive = inclusive - frame.children;
+
+            let stats = self.calls.entry(frame.addr).or_default();
+            stats.calls += 1;
+            stats.inclusive += inclusive;
+            stats.exclusive += exclusive;
+
+            let mut path: Vec<u16> = self.stack.iter().map(|f| f.addr).collect();
+            path.push(frame.addr);
+            *self.folded.entry(path).or_default() += exclusive;
+
+            match self.stack.last_mut() {
+                Some(parent) => parent.children += inclusive,
+                None => self.root_children += inclusive,
+            }
+        }
+    }
+
+    /// Instruction cycles seen, interrupt sequences included
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn interrupts(&self) -> u64 {
+        self.interrupts
+    }
+
+    /// Instructions that started at `addr`
+    pub fn executions(&self, addr: u16) -> u64 {
+        self.executions[addr as usize]
+    }
+
+    /// Cycles spent in instructions that started at `addr`
+    pub fn cycles_at(&self, addr: u16) -> u64 {
+        self.cycles_at[addr as usize]
+    }
+
+    /// Executions and cycles of `opcode`
+    pub fn opcode(&self, opcode: u8) -> (u64, u64) {
+        self.opcodes[opcode as usize]
+    }
+
+    /// Whether `addr` held an opcode or operand of an instruction that ran
+    pub fn covered(&self, addr: u16) -> bool {
+        (0..3).any(|back| {
+            let start = addr.wrapping_sub(back) as usize;
+            self.executions[start] > 0
+                && OPCODES[self.opcodes_at[start] as usize].bytes as u16 > back
+        })
+    }
+
+    /// Per subroutine or handler entry address, for the calls that returned
+    pub fn calls(&self) -> &HashMap<u16, CallStats> {
+        &self.calls
+    }
+
+    /// `address,opcode,mnemonic,executions,cycles` for every address an
+    /// instruction started at
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "address,opcode,mnemonic,executions,cycles")?;
+        for addr in 0..0x10000 {
+            if self.executions[addr] > 0 {
+                let opcode = self.opcodes_at[addr];
+                writeln!(
+                    out,
+                    "{:04X},{:02X},{},{},{}",
+                    addr,
+                    opcode,
+                    OPCODES[opcode as usize].mnemonic,
+                    self.executions[addr],
+                    self.cycles_at[addr]
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `opcode,mnemonic,mode,executions,cycles` for every opcode that ran
+    pub fn write_opcode_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "opcode,mnemonic,mode,executions,cycles")?;
+        for (spec, &(executions, cycles)) in OPCODES.iter().zip(&self.opcodes) {
+            if executions > 0 {
+                writeln!(
+                    out,
+                    "{:02X},{},{:?},{},{}",
+                    spec.opcode, spec.mnemonic, spec.mode, executions, cycles
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `address,calls,inclusive,exclusive` sorted by inclusive cycles
+    pub fn write_calls_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut calls: Vec<_> = self.calls.iter().collect();
+        calls.sort_by_key(|&(addr, stats)| (std::cmp::Reverse(stats.inclusive), *addr));
+
+        writeln!(out, "address,calls,inclusive,exclusive")?;
+        for (addr, stats) in calls {
+            writeln!(
+                out,
+                "{:04X},{},{},{}",
+                addr, stats.calls, stats.inclusive, stats.exclusive
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Exclusive cycles per call stack in the folded format flamegraph.pl
+    /// and inferno read (`root;$C000;$C1F0 1234`). Calls still running
+    /// count up to now.
+    pub fn write_folded(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut folded = self.folded.clone();
+        let mut child = 0;
+        for (depth, frame) in self.stack.iter().enumerate().rev() {
+            let inclusive = self.cycles - frame.start;
+            let path = self.stack[..=depth].iter().map(|f| f.addr).collect();
+            *folded.entry(path).or_default() += inclusive - frame.children - child;
+            child = inclusive;
+        }
+        folded.insert(Vec::new(), self.cycles - self.root_children - child);
+
+        let mut stacks: Vec<_> = folded.into_iter().filter(|&(_, c)| c > 0).collect();
+        stacks.sort();
+        for (path, cycles) in stacks {
+            write!(out, "root")?;
+            for addr in path {
+                write!(out, ";${:04X}", addr)?;
+            }
+            writeln!(out, " {}", cycles)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use crate::cpu::{Cpu, CpuError};
+use std::{collections::VecDeque, mem::size_of};
+
+/// A byte a write replaced, found again by its place in `Banks` storage so
+/// it goes back where it came from even after a bank switch
+#[derive(Clone, Copy)]
+pub(crate) struct Undo {
+    pub(crate) addr: u16,
+    pub(crate) index: usize,
+    pub(crate) old: u8,
+}
+
+/// How a CPU page was mapped before a bank switch changed it
+#[derive(Clone, Copy)]
+pub(crate) struct Remap {
+    pub(crate) page: u8,
+    pub(crate) read: usize,
+    pub(crate) write: usize,
+}
+
+/// CPU state from before an instruction ran
+#[derive(Clone, Copy)]
+pub(crate) struct Registers {
+    pc: u16,
+    sp: u8,
+    status: u8,
+    a: u8,
+    x: u8,
+    y: u8,
+    nmi_pending: bool,
+    irq_poll_disabled: Option<bool>,
+    halted: Option<CpuError>,
+}
+
+/// What it takes to undo one instruction (or interrupt sequence)
+struct Entry {
+    registers: Registers,
+    /// In the order they happened
+    writes: Vec<Undo>,
+    remaps: Vec<Remap>,
+}
+
+impl Entry {
+    fn size(&self) -> usize {
+        size_of::<Entry>()
+            + self.writes.capacity() * size_of::<Undo>()
+            + self.remaps.capacity() * size_of::<Remap>()
+    }
+}
+
+/// Undo logs of the most recent instructions, for `Cpu::step_back` and
+/// `Cpu::rewind_to_write`. Set `Cpu::rewind` to start recording; the
+/// oldest instructions are forgotten once the logs outgrow the limit.
+/// Only the CPU and banked memory (its contents and how it's mapped) go
+/// back: devices, bank switch registers and observers like `Profiler` keep
+/// their state.
+pub struct Rewind {
+    entries: VecDeque<Entry>,
+    /// Bytes the entries may take up
+    limit: usize,
+    used: usize,
+}
+
+impl Rewind {
+    /// Keeps as many instructions as fit in about `limit` bytes
+    pub fn new(limit: usize) -> Self {
+        Rewind {
+            entries: VecDeque::new(),
+            limit,
+            used: 0,
+        }
+    }
+
+    /// Instructions that can be undone
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Bytes the undo logs take up
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.used = 0;
+    }
+
+    fn push(&mut self, entry: Entry) {
+        self.used += entry.size();
+        self.entries.push_back(entry);
+        while self.used > self.limit {
+            match self.entries.pop_front() {
+                Some(oldest) => self.used -= oldest.size(),
+                None => break,
+            }
+        }
+    }
+
+    fn pop(&mut self) -> Option<Entry> {
+        let entry = self.entries.pop_back()?;
+        self.used -= entry.size();
+        Some(entry)
+    }
+}
+
+impl Cpu {
+    /// Called by `Cpu::clock` before an instruction when rewinding is on
+    pub(crate) fn begin_undo(&mut self) -> Option<Registers> {
+        self.rewind.as_ref()?;
+        self.memory.start_undo();
+        Some(Registers {
+            pc: self.pc,
+            sp: self.sp,
+            status: self.status,
+            a: self.a,
+            x: self.x,
+            y: self.y,
+            nmi_pending: self.nmi_pending,
+            irq_poll_disabled: self.irq_poll_disabled,
+            halted: self.halted,
+        })
+    }
+
+    /// Called by `Cpu::clock` once the instruction has run
+    pub(crate) fn end_undo(&mut self, registers: Registers) {
+        let (writes, remaps) = self.memory.take_undo();
+        if let Some(rewind) = self.rewind.as_deref_mut() {
+            rewind.push(Entry {
+                registers,
+                writes,
+                remaps,
+            });
+        }
+    }
+
+    /// Undoes up to `count` instructions, returns how many it could. The
+    /// CPU is left between instructions, as `step` leaves it.
+    pub fn step_back(&mut self, count: usize) -> usize {
+        for undone in 0..count {
+            let Some(entry) = self.rewind.as_deref_mut().and_then(Rewind::pop) else {
+                return undone;
+            };
+            self.memory.undo(&entry.writes, &entry.remaps);
+
+            let r = entry.registers;
+            (self.pc, self.sp, self.status) = (r.pc, r.sp, r.status);
+            (self.a, self.x, self.y) = (r.a, r.x, r.y);
+            self.nmi_pending = r.nmi_pending;
+            self.irq_poll_disabled = r.irq_poll_disabled;
+            self.halted = r.halted;
+            self.cycles = 0;
+        }
+        count
+    }
+
+    /// Goes back to just before the last recorded instruction that wrote
+    /// `addr`, so the next `step` writes it again. Returns how many
+    /// instructions were undone, `None` (with nothing undone) when no
+    /// recorded instruction wrote there. Writes through a mirror of `addr`
+    /// count, as they land on the same byte.
+    pub fn rewind_to_write(&mut self, addr: u16) -> Option<usize> {
+        let addr = self.memory.masked(addr);
+        let index = self.memory.banks.write_index(addr);
+        let count = self
+            .rewind
+            .as_deref()?
+            .entries
+            .iter()
+            .rev()
+            .position(|entry| {
+                entry
+                    .writes
+                    .iter()
+                    .any(|undo| undo.addr == addr || Some(undo.index) == index)
+            })?
+            + 1;
+        Some(self.step_back(count))
+    }
+}
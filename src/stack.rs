@@ -0,0 +1,196 @@
+use std::fmt;
+
+/// Something `StackChecker` found wrong, `pc` is where the instruction (or
+/// interrupt sequence) started
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StackIssue {
+    /// A push took SP from $00 round to $FF
+    Overflow { pc: u16 },
+    /// A pull took SP from $FF round to $00
+    Underflow { pc: u16 },
+    /// RTS or RTI with no JSR or interrupt left to return from at this
+    /// depth, e.g. an RTS used as a jump through a pushed address
+    Unmatched { pc: u16, target: u16 },
+    /// RTS from an interrupt or BRK, or RTI from a JSR. `call` is where
+    /// that call was made.
+    Mismatched { pc: u16, call: u16 },
+    /// The return address on the stack isn't the one the call pushed
+    WrongReturn { pc: u16, expected: u16, target: u16 },
+}
+
+impl fmt::Display for StackIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StackIssue::Overflow { pc } => write!(f, "${:04X}: stack overflow", pc),
+            StackIssue::Underflow { pc } => write!(f, "${:04X}: stack underflow", pc),
+            StackIssue::Unmatched { pc, target } => {
+                write!(f, "${:04X}: return to ${:04X} without a call", pc, target)
+            }
+            StackIssue::Mismatched { pc, call } => {
+                write!(f, "${:04X}: wrong return for the call at ${:04X}", pc, call)
+            }
+            StackIssue::WrongReturn {
+                pc,
+                expected,
+                target,
+            } => write!(
+                f,
+                "${:04X}: return to ${:04X} instead of ${:04X}",
+                pc, target, expected
+            ),
+        }
+    }
+}
+
+/// A JSR, BRK or interrupt that hasn't returned yet
+struct Frame {
+    /// Where the call was made
+    call: u16,
+    /// Where RTS (one past the pushed address) or RTI should land
+    target: u16,
+    interrupt: bool,
+    /// SP before the call pushed anything
+    sp: u8,
+}
+
+/// Watches SP and the return addresses on the stack. Set `Cpu::stack_checker`
+/// to start checking; issues are collected rather than stopping the CPU.
+/// Calls are matched to returns by SP, so frames a routine drops (by
+/// pulling its return address, or resetting SP) are forgotten quietly.
+/// Depth counts from SP as it was when checking started or at the last
+/// `Cpu::reset`, or from higher up once SP has been there. After SP wraps
+/// (reported as an overflow or underflow) depth isn't measured until it
+/// wraps back or TXS sets it.
+#[derive(Default)]
+pub struct StackChecker {
+    frames: Vec<Frame>,
+    issues: Vec<StackIssue>,
+    /// SP with nothing on the stack
+    top: Option<u8>,
+    max_depth: u8,
+    /// Pushes that wrapped SP past $00, less pulls that wrapped it back
+    wraps: i32,
+}
+
+impl StackChecker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Called by `Cpu::clock` once per instruction with where it started,
+    /// its opcode, SP before and after and PC after it ran
+    pub(crate) fn instruction(&mut self, pc: u16, opcode: u8, sp: (u8, u8), next: u16) {
+        let wraps = self.wraps;
+        match opcode {
+            // PHA, PHP
+            0x48 | 0x08 => self.pushed(pc, sp),
+            // PLA, PLP
+            0x68 | 0x28 => self.pulled(pc, sp),
+            // JSR and BRK push the address of their last byte and one past
+            // their padding byte
+            0x20 => self.call(pc, pc.wrapping_add(3), false, sp),
+            0x00 => self.call(pc, pc.wrapping_add(2), true, sp),
+            0x60 => self.ret(pc, next, false, sp),
+            0x40 => self.ret(pc, next, true, sp),
+            // TXS puts SP somewhere new, wrapped or not
+            0x9A => self.wraps = 0,
+            _ => {}
+        }
+        self.track(sp, wraps);
+    }
+
+    /// Called by `Cpu::clock` when an IRQ or NMI interrupts the instruction
+    /// at `pc`
+    pub(crate) fn interrupt(&mut self, pc: u16, sp: (u8, u8)) {
+        let wraps = self.wraps;
+        self.call(pc, pc, true, sp);
+        self.track(sp, wraps);
+    }
+
+    /// Called by `Cpu::reset`, which leaves the stack empty at `sp`
+    pub(crate) fn reset(&mut self, sp: u8) {
+        self.frames.clear();
+        self.top = Some(sp);
+        self.wraps = 0;
+    }
+
+    /// `wraps` is what it was before the instruction. SP past either end
+    /// of the page, or just back from there, says nothing about the depth.
+    fn track(&mut self, (before, after): (u8, u8), wraps: i32) {
+        if wraps != 0 || self.wraps != 0 {
+            return;
+        }
+        let top = self.top.unwrap_or(before).max(before).max(after);
+        self.top = Some(top);
+        self.max_depth = self.max_depth.max(top - before.min(after));
+    }
+
+    fn pushed(&mut self, pc: u16, (before, after): (u8, u8)) {
+        if after > before {
+            self.issues.push(StackIssue::Overflow { pc });
+            self.wraps += 1;
+        }
+    }
+
+    fn pulled(&mut self, pc: u16, (before, after): (u8, u8)) {
+        if after < before {
+            self.issues.push(StackIssue::Underflow { pc });
+            self.wraps -= 1;
+        }
+    }
+
+    fn call(&mut self, pc: u16, target: u16, interrupt: bool, sp: (u8, u8)) {
+        self.pushed(pc, sp);
+        self.frames.push(Frame {
+            call: pc,
+            target,
+            interrupt,
+            sp: sp.0,
+        });
+    }
+
+    fn ret(&mut self, pc: u16, target: u16, interrupt: bool, sp: (u8, u8)) {
+        self.pulled(pc, sp);
+        let after = sp.1;
+        while self.frames.last().is_some_and(|frame| frame.sp < after) {
+            self.frames.pop();
+        }
+
+        let issue = match self.frames.last() {
+            Some(frame) if frame.sp == after => {
+                let frame = self.frames.pop().unwrap();
+                if frame.interrupt != interrupt {
+                    Some(StackIssue::Mismatched {
+                        pc,
+                        call: frame.call,
+                    })
+                } else if frame.target != target {
+                    Some(StackIssue::WrongReturn {
+                        pc,
+                        expected: frame.target,
+                        target,
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => Some(StackIssue::Unmatched { pc, target }),
+        };
+        self.issues.extend(issue);
+    }
+
+    /// Everything found so far, in order
+    pub fn issues(&self) -> &[StackIssue] {
+        &self.issues
+    }
+
+    /// Most bytes the stack held at once
+    pub fn max_depth(&self) -> u8 {
+        self.max_depth
+    }
+
+    /// Calls and interrupts that haven't returned
+    pub fn depth(&self) -> usize {
+        self.frames.len()
+    }
+}
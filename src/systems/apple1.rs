@@ -0,0 +1,127 @@
+use crate::{
+    bus::{Line, Mapping},
+    cpu::Cpu,
+    devices::{pia::Pia, serial::SerialHost},
+};
+use std::{cell::RefCell, rc::Rc};
+
+// Information grabbed from the Apple-1 Operation Manual (1976)
+
+/// Steve Wozniak's monitor, as listed in the Apple-1 manual. Lives at $FF00.
+#[rustfmt::skip]
+pub const WOZMON: [u8; 256] = [
+    0xD8, 0x58, 0xA0, 0x7F, 0x8C, 0x12, 0xD0, 0xA9, 0xA7, 0x8D, 0x11, 0xD0, 0x8D, 0x13, 0xD0, 0xC9,
+    0xDF, 0xF0, 0x13, 0xC9, 0x9B, 0xF0, 0x03, 0xC8, 0x10, 0x0F, 0xA9, 0xDC, 0x20, 0xEF, 0xFF, 0xA9,
+    0x8D, 0x20, 0xEF, 0xFF, 0xA0, 0x01, 0x88, 0x30, 0xF6, 0xAD, 0x11, 0xD0, 0x10, 0xFB, 0xAD, 0x10,
+    0xD0, 0x99, 0x00, 0x02, 0x20, 0xEF, 0xFF, 0xC9, 0x8D, 0xD0, 0xD4, 0xA0, 0xFF, 0xA9, 0x00, 0xAA,
+    0x0A, 0x85, 0x2B, 0xC8, 0xB9, 0x00, 0x02, 0xC9, 0x8D, 0xF0, 0xD4, 0xC9, 0xAE, 0x90, 0xF4, 0xF0,
+    0xF0, 0xC9, 0xBA, 0xF0, 0xEB, 0xC9, 0xD2, 0xF0, 0x3B, 0x86, 0x28, 0x86, 0x29, 0x84, 0x2A, 0xB9,
+    0x00, 0x02, 0x49, 0xB0, 0xC9, 0x0A, 0x90, 0x06, 0x69, 0x88, 0xC9, 0xFA, 0x90, 0x11, 0x0A, 0x0A,
+    0x0A, 0x0A, 0xA2, 0x04, 0x0A, 0x26, 0x28, 0x26, 0x29, 0xCA, 0xD0, 0xF8, 0xC8, 0xD0, 0xE0, 0xC4,
+    0x2A, 0xF0, 0x97, 0x24, 0x2B, 0x50, 0x10, 0xA5, 0x28, 0x81, 0x26, 0xE6, 0x26, 0xD0, 0xB5, 0xE6,
+    0x27, 0x4C, 0x44, 0xFF, 0x6C, 0x24, 0x00, 0x30, 0x2B, 0xA2, 0x02, 0xB5, 0x27, 0x95, 0x25, 0x95,
+    0x23, 0xCA, 0xD0, 0xF7, 0xD0, 0x14, 0xA9, 0x8D, 0x20, 0xEF, 0xFF, 0xA5, 0x25, 0x20, 0xDC, 0xFF,
+    0xA5, 0x24, 0x20, 0xDC, 0xFF, 0xA9, 0xBA, 0x20, 0xEF, 0xFF, 0xA9, 0xA0, 0x20, 0xEF, 0xFF, 0xA1,
+    0x24, 0x20, 0xDC, 0xFF, 0x86, 0x2B, 0xA5, 0x24, 0xC5, 0x28, 0xA5, 0x25, 0xE5, 0x29, 0xB0, 0xC1,
+    0xE6, 0x24, 0xD0, 0x02, 0xE6, 0x25, 0xA5, 0x24, 0x29, 0x07, 0x10, 0xC8, 0x48, 0x4A, 0x4A, 0x4A,
+    0x4A, 0x20, 0xE5, 0xFF, 0x68, 0x29, 0x0F, 0x09, 0xB0, 0xC9, 0xBA, 0x90, 0x02, 0x69, 0x06, 0x2C,
+    0x12, 0xD0, 0x30, 0xFB, 0x8D, 0x12, 0xD0, 0x60, 0x00, 0x00, 0x00, 0x0F, 0x00, 0xFF, 0x00, 0x00,
+];
+
+/// Apple-1 CPU clock
+pub const CLOCK_HZ: u32 = 1_022_727;
+
+/// A minimal Apple-1: RAM, Wozmon at $FF00 and the 6821 PIA at $D010-$D013
+/// connecting the keyboard and display to a `SerialHost` acting as terminal.
+pub struct Apple1 {
+    pub cpu: Cpu,
+    pub pia: Rc<RefCell<Pia>>,
+    terminal: Box<dyn SerialHost>,
+}
+
+impl Apple1 {
+    pub fn new(terminal: impl SerialHost + 'static) -> Self {
+        let mut cpu = Cpu::new();
+        let pia = Rc::new(RefCell::new(Pia::new()));
+
+        cpu.memory.map_rom(0xFF00, &WOZMON);
+        let id = cpu.memory.map(
+            Mapping {
+                mirror_mask: 0x0003,
+                ..Mapping::new(0xD010, 0xD013)
+            },
+            pia.clone(),
+        );
+        // The PIA's IRQ pins aren't connected on the Apple-1
+        cpu.memory.connect(id, Line::None);
+        // PB7 reads the display's busy line, which we never hold high
+        pia.borrow_mut().set_port_b(0x7F);
+
+        Apple1 {
+            cpu,
+            pia,
+            terminal: Box::new(terminal),
+        }
+    }
+
+    /// Presses RESET
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    /// Runs one instruction and services the keyboard and display,
+    /// returns the cycles it took
+    pub fn step(&mut self) -> u32 {
+        let cycles = self.cpu.step();
+        self.service_display();
+        self.service_keyboard();
+        cycles
+    }
+
+    /// Runs for at least `cycles` CPU cycles
+    pub fn run(&mut self, cycles: u64) {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.step() as u64;
+        }
+    }
+
+    /// Writing to the display port pulls CB2 (DA) low, the terminal takes
+    /// the character and acknowledges on CB1 (RDA)
+    fn service_display(&mut self) {
+        let mut pia = self.pia.borrow_mut();
+        if pia.cb2() {
+            return;
+        }
+
+        let ch = pia.port_b() & 0x7F;
+        match ch {
+            b'\r' => self.terminal.transmit(b'\n'),
+            0x20..=0x7E => self.terminal.transmit(ch),
+            _ => {}
+        }
+
+        pia.set_cb1(false);
+        pia.set_cb1(true);
+    }
+
+    /// Hands the next key to the PIA once the last one has been read
+    fn service_keyboard(&mut self) {
+        let mut pia = self.pia.borrow_mut();
+        if pia.a.control & 0x80 != 0 {
+            return;
+        }
+
+        let key = match self.terminal.receive() {
+            Some(b'\n') => b'\r',
+            Some(0x08 | 0x7F) => b'_',
+            Some(key) => key.to_ascii_uppercase(),
+            None => return,
+        };
+
+        // The Apple-1 keyboard sets bit 7 and strobes CA1
+        pia.set_port_a(key | 0x80);
+        pia.set_ca1(false);
+        pia.set_ca1(true);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::bus::Device;
+use std::{error::Error, fmt};
+
+// Information grabbed from Kevin Horton's 2600 bank switching notes
+
+const BANK_SIZE: usize = 0x1000;
+
+/// How the cartridge fits more than 4K behind the 6507's 4K window.
+/// F8, F6 and F4 switch 4K banks whenever one of the addresses ending in
+/// their hotspot range is accessed, read or write.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BankSwitching {
+    /// 2K or 4K, 2K images show up twice
+    None,
+    /// 8K, hotspots $1FF8-$1FF9
+    F8,
+    /// 16K, hotspots $1FF6-$1FF9
+    F6,
+    /// 32K, hotspots $1FF4-$1FFB
+    F4,
+}
+
+impl BankSwitching {
+    /// Picks the scheme from the image size, as there's no header
+    pub fn detect(size: usize) -> Option<Self> {
+        match size {
+            0x0800 | 0x1000 => Some(BankSwitching::None),
+            0x2000 => Some(BankSwitching::F8),
+            0x4000 => Some(BankSwitching::F6),
+            0x8000 => Some(BankSwitching::F4),
+            _ => None,
+        }
+    }
+
+    /// Offset of the first hotspot into the 4K window
+    fn first_hotspot(self) -> Option<u16> {
+        match self {
+            BankSwitching::None => None,
+            BankSwitching::F8 => Some(0xFF8),
+            BankSwitching::F6 => Some(0xFF6),
+            BankSwitching::F4 => Some(0xFF4),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CartridgeError {
+    /// No bank switching scheme we know uses this many bytes
+    UnsupportedSize(usize),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::UnsupportedSize(n) => {
+                write!(f, "no supported cartridge is {} bytes", n)
+            }
+        }
+    }
+}
+
+impl Error for CartridgeError {}
+
+/// A game cartridge, mapped at $1000-$1FFF with a mirror mask of 0x0FFF
+pub struct Cartridge {
+    pub rom: Vec<u8>,
+    pub bank_switching: BankSwitching,
+    /// 4K bank showing in the window
+    pub bank: usize,
+}
+
+impl Cartridge {
+    pub fn new(rom: &[u8]) -> Result<Self, CartridgeError> {
+        let bank_switching =
+            BankSwitching::detect(rom.len()).ok_or(CartridgeError::UnsupportedSize(rom.len()))?;
+        let mut cartridge = Cartridge {
+            rom: rom.to_vec(),
+            bank_switching,
+            bank: 0,
+        };
+        cartridge.reset();
+        Ok(cartridge)
+    }
+
+    pub fn banks(&self) -> usize {
+        self.rom.len().div_ceil(BANK_SIZE)
+    }
+
+    /// Back to the last bank, which is where most games keep their reset code
+    pub fn reset(&mut self) {
+        self.bank = self.banks() - 1;
+    }
+
+    fn switch(&mut self, addr: u16) {
+        if let Some(first) = self.bank_switching.first_hotspot() {
+            if addr >= first && ((addr - first) as usize) < self.banks() {
+                self.bank = (addr - first) as usize;
+            }
+        }
+    }
+}
+
+impl Device for Cartridge {
+    fn read(&mut self, addr: u16) -> u8 {
+        let offset = self.bank * BANK_SIZE + addr as usize;
+        let value = self.rom[offset % self.rom.len()];
+        self.switch(addr);
+        value
+    }
+
+    fn write(&mut self, addr: u16, _value: u8) {
+        self.switch(addr);
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        let offset = self.bank * BANK_SIZE + addr as usize;
+        Some(self.rom[offset % self.rom.len()])
+    }
+}
@@ -0,0 +1,164 @@
+pub mod cartridge;
+pub mod tia;
+
+use crate::{
+    bus::{Device, Line, Mapping},
+    cpu::Cpu,
+    devices::riot::Riot,
+};
+use cartridge::{Cartridge, CartridgeError};
+use std::{cell::RefCell, rc::Rc};
+use tia::Tia;
+
+// Information grabbed from the Stella Programmer's Guide
+
+/// NTSC CPU clock
+pub const CLOCK_HZ: u32 = 1_193_182;
+
+/// Console switches on RIOT port B (SWCHB), active low
+pub const SWITCH_RESET: u8 = 0x01;
+pub const SWITCH_SELECT: u8 = 0x02;
+pub const SWITCH_COLOR: u8 = 0x08;
+pub const SWITCH_P0_DIFFICULTY: u8 = 0x40;
+pub const SWITCH_P1_DIFFICULTY: u8 = 0x80;
+
+/// Decodes the lower 4K the way the 2600's board does: A7 low selects the
+/// TIA, A7 high selects the RIOT, whose RAM or I/O side is picked by A9
+struct ChipSelect {
+    tia: Rc<RefCell<Tia>>,
+    riot: Rc<RefCell<Riot>>,
+}
+
+impl Device for ChipSelect {
+    fn read(&mut self, addr: u16) -> u8 {
+        match (addr & 0x80 != 0, addr & 0x200 != 0) {
+            (false, _) => self.tia.borrow_mut().read(addr),
+            (true, false) => self.riot.borrow().ram[(addr & 0x7F) as usize],
+            (true, true) => self.riot.borrow_mut().read(addr & 0x1F),
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match (addr & 0x80 != 0, addr & 0x200 != 0) {
+            (false, _) => self.tia.borrow_mut().write(addr, value),
+            (true, false) => self.riot.borrow_mut().ram[(addr & 0x7F) as usize] = value,
+            (true, true) => self.riot.borrow_mut().write(addr & 0x1F, value),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.tia.borrow_mut().tick();
+        self.riot.borrow_mut().tick();
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        // Only the RAM reads without side effects
+        (addr & 0x280 == 0x080).then(|| self.riot.borrow().ram[(addr & 0x7F) as usize])
+    }
+}
+
+/// The CPU side of an Atari 2600: a 6507 with the TIA register file, the
+/// 6532 RIOT (128 bytes of RAM, joysticks, console switches, timer) and the
+/// cartridge. Nothing is drawn, but WSYNC halts the CPU and the beam timing
+/// is kept, so a kernel runs at its real pace and its scanlines can be
+/// counted.
+pub struct Atari2600 {
+    pub cpu: Cpu,
+    pub tia: Rc<RefCell<Tia>>,
+    pub riot: Rc<RefCell<Riot>>,
+    pub cartridge: Rc<RefCell<Cartridge>>,
+    /// CPU cycles since power on
+    pub cycles: u64,
+}
+
+impl Atari2600 {
+    pub fn new(cartridge: Cartridge) -> Self {
+        let mut cpu = Cpu::new_6507();
+        let tia = Rc::new(RefCell::new(Tia::new()));
+        let riot = Rc::new(RefCell::new(Riot::new()));
+        let cartridge = Rc::new(RefCell::new(cartridge));
+
+        // The 6507 has no interrupt inputs, so nothing gets connected
+        let id = cpu.memory.map(
+            Mapping::new(0x0000, 0x0FFF),
+            ChipSelect {
+                tia: tia.clone(),
+                riot: riot.clone(),
+            },
+        );
+        cpu.memory.connect(id, Line::None);
+        let id = cpu.memory.map(
+            Mapping {
+                mirror_mask: 0x0FFF,
+                ..Mapping::new(0x1000, 0x1FFF)
+            },
+            cartridge.clone(),
+        );
+        cpu.memory.connect(id, Line::None);
+
+        Atari2600 {
+            cpu,
+            tia,
+            riot,
+            cartridge,
+            cycles: 0,
+        }
+    }
+
+    /// Loads a raw cartridge image, picking the bank switching by its size
+    pub fn from_rom(data: &[u8]) -> Result<Self, CartridgeError> {
+        Ok(Self::new(Cartridge::new(data)?))
+    }
+
+    pub fn reset(&mut self) {
+        self.cartridge.borrow_mut().reset();
+        self.cpu.reset();
+    }
+
+    /// Runs one instruction, and the rest of the scanline if it wrote
+    /// WSYNC, returns the cycles it took
+    pub fn step(&mut self) -> u32 {
+        let mut cycles = self.cpu.step();
+
+        // The CPU sits on its next opcode fetch while RDY is low
+        loop {
+            let rdy = self.tia.borrow().rdy();
+            self.cpu.set_rdy(rdy);
+            if rdy {
+                break;
+            }
+            self.cpu.clock();
+            cycles += 1;
+        }
+
+        self.cycles += cycles as u64;
+        cycles
+    }
+
+    /// Runs until the kernel starts its next frame (turns VSYNC on),
+    /// returns the cycles it took
+    pub fn run_frame(&mut self) -> u64 {
+        let frame = self.tia.borrow().frame;
+        let mut cycles = 0;
+        while self.tia.borrow().frame == frame {
+            cycles += self.step() as u64;
+        }
+        cycles
+    }
+
+    /// Joystick directions on RIOT port A (SWCHA), active low with
+    /// player 0 in the upper nybble
+    pub fn set_joysticks(&mut self, value: u8) {
+        self.riot.borrow_mut().set_port_a(value);
+    }
+
+    /// Console switches (see the `SWITCH_*` bits), active low
+    pub fn set_switches(&mut self, value: u8) {
+        self.riot.borrow_mut().set_port_b(value);
+    }
+
+    /// Fire button of `player` (0 or 1)
+    pub fn set_fire(&mut self, player: usize, pressed: bool) {
+        self.tia.borrow_mut().inputs[4 + player] = if pressed { 0x00 } else { 0x80 };
+    }
+}
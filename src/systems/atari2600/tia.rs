@@ -0,0 +1,116 @@
+use crate::bus::Device;
+
+// Information grabbed from the Stella Programmer's Guide
+
+/// Write registers (A0-A5)
+pub const VSYNC: u16 = 0x00;
+pub const VBLANK: u16 = 0x01;
+pub const WSYNC: u16 = 0x02;
+pub const RSYNC: u16 = 0x03;
+
+/// Read registers (A0-A3)
+pub const INPT4: u16 = 0x0C;
+pub const INPT5: u16 = 0x0D;
+
+/// VSYNC/VBLANK bits
+pub const VSYNC_ON: u8 = 0x02;
+pub const VBLANK_ON: u8 = 0x02;
+
+/// Color clocks per scanline, the first 68 are horizontal blank
+pub const CLOCKS_PER_SCANLINE: u16 = 228;
+pub const HBLANK_CLOCKS: u16 = 68;
+/// The TIA runs at three times the CPU clock
+pub const CLOCKS_PER_CPU_CYCLE: u16 = 3;
+
+/// TIA register file and beam timing without any graphics or sound.
+/// Writes are kept in `registers`, collision latches read as 0 and the
+/// input ports read whatever was set from outside. Writing WSYNC pulls the
+/// CPU's RDY line low until the end of the scanline, and VSYNC marks the
+/// start of a frame so a kernel's scanlines can be counted.
+pub struct Tia {
+    /// Last value written to each register
+    pub registers: [u8; 0x40],
+    /// INPT0-INPT5, only bit 7 is driven
+    pub inputs: [u8; 6],
+
+    /// Color clock within the scanline
+    pub clock: u16,
+    /// Scanlines since VSYNC was last turned on
+    pub scanline: u16,
+    /// Scanlines the last complete frame took
+    pub frame_scanlines: u16,
+    /// Frames started since power on
+    pub frame: u64,
+
+    /// RDY is held low until the end of the line
+    wsync: bool,
+}
+
+impl Tia {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// RDY output (false = halt the CPU)
+    pub fn rdy(&self) -> bool {
+        !self.wsync
+    }
+
+    pub fn vsync(&self) -> bool {
+        self.registers[VSYNC as usize] & VSYNC_ON != 0
+    }
+
+    pub fn vblank(&self) -> bool {
+        self.registers[VBLANK as usize] & VBLANK_ON != 0
+    }
+}
+
+impl Default for Tia {
+    fn default() -> Self {
+        Tia {
+            registers: [0; 0x40],
+            // Paddles grounded, fire buttons released
+            inputs: [0x00, 0x00, 0x00, 0x00, 0x80, 0x80],
+            clock: 0,
+            scanline: 0,
+            frame_scanlines: 0,
+            frame: 0,
+            wsync: false,
+        }
+    }
+}
+
+impl Device for Tia {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr & 0x0F {
+            addr @ 0x08..=INPT5 => self.inputs[addr as usize - 0x08],
+            // Collision latches, nothing is drawn so nothing collides
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        let addr = addr & 0x3F;
+        match addr {
+            // A frame starts when VSYNC is turned on
+            VSYNC if value & VSYNC_ON != 0 && !self.vsync() => {
+                self.frame_scanlines = self.scanline;
+                self.scanline = 0;
+                self.frame += 1;
+            }
+            WSYNC => self.wsync = true,
+            RSYNC => self.clock = 0,
+            _ => {}
+        }
+        self.registers[addr as usize] = value;
+    }
+
+    fn tick(&mut self) {
+        self.clock += CLOCKS_PER_CPU_CYCLE;
+        if self.clock >= CLOCKS_PER_SCANLINE {
+            self.clock -= CLOCKS_PER_SCANLINE;
+            self.scanline = self.scanline.wrapping_add(1);
+            self.wsync = false;
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    banking::{BankId, BankSwitch, Banks, BASE_RAM},
+    bus::{Device, Line, Mapping},
+    cpu::Cpu,
+    devices::{cia::Cia, sid::Sid, vic::Vic},
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    fs, io,
+    path::Path,
+    rc::Rc,
+};
+
+// Information grabbed from https://www.c64-wiki.com/wiki/Bank_Switching
+// and the C64 Programmer's Reference Guide
+
+/// PAL C64 CPU clock
+pub const CLOCK_HZ: u32 = 985_248;
+
+/// Screen RAM the KERNAL sets up at power on
+pub const SCREEN: u16 = 0x0400;
+pub const SCREEN_COLUMNS: usize = 40;
+pub const SCREEN_ROWS: usize = 25;
+
+/// KERNAL keyboard buffer and its fill count
+const KEYBOARD_BUFFER: usize = 0x0277;
+const KEYBOARD_COUNT: usize = 0x00C6;
+const KEYBOARD_BUFFER_SIZE: usize = 10;
+
+/// Processor port lines the PLA decodes
+const LORAM: u8 = 0x01;
+const HIRAM: u8 = 0x02;
+const CHAREN: u8 = 0x04;
+
+/// The 6510's built-in I/O port at $00 (direction) and $01 (data).
+/// Its low three lines drive the PLA, which picks what shows up at
+/// $A000-$BFFF, $D000-$DFFF and $E000-$FFFF. Writes always land in RAM.
+pub struct ProcessorPort {
+    pub ddr: u8,
+    pub data: u8,
+    /// Levels on the lines set as inputs: pull-ups on the PLA lines and
+    /// the cassette sense (no button pressed)
+    pins: u8,
+    basic: BankId,
+    kernal: BankId,
+    chargen: BankId,
+    /// The I/O chips answer at $D000-$DFFF
+    io: Rc<Cell<bool>>,
+}
+
+impl ProcessorPort {
+    /// Lines as seen on the pins
+    pub fn value(&self) -> u8 {
+        (self.data & self.ddr) | (self.pins & !self.ddr)
+    }
+
+    /// All lines back to inputs, which the pull-ups read as the default
+    /// BASIC + I/O + KERNAL configuration
+    pub fn reset(&mut self, banks: &mut Banks) {
+        self.ddr = 0;
+        self.data = 0;
+        self.apply(banks);
+    }
+
+    fn apply(&mut self, banks: &mut Banks) {
+        let lines = self.value();
+        let loram = lines & LORAM != 0;
+        let hiram = lines & HIRAM != 0;
+        let charen = lines & CHAREN != 0;
+
+        if loram && hiram {
+            banks.map_read(0xA0, 0x20, self.basic, 0);
+        } else {
+            banks.map_read(0xA0, 0x20, BASE_RAM, 0xA0);
+        }
+
+        if hiram {
+            banks.map_read(0xE0, 0x20, self.kernal, 0);
+        } else {
+            banks.map_read(0xE0, 0x20, BASE_RAM, 0xE0);
+        }
+
+        // With both LORAM and HIRAM low it's RAM everywhere
+        let any_rom = loram || hiram;
+        if any_rom && !charen {
+            banks.map_read(0xD0, 0x10, self.chargen, 0);
+        } else {
+            banks.map_read(0xD0, 0x10, BASE_RAM, 0xD0);
+        }
+        self.io.set(any_rom && charen);
+    }
+}
+
+impl BankSwitch for ProcessorPort {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8) {
+        if addr == 0 {
+            self.ddr = value;
+        } else {
+            self.data = value;
+        }
+        // The CPU still drives the bus, so RAM underneath gets it too
+        banks.write(addr, value);
+        self.apply(banks);
+    }
+
+    fn read(&mut self, addr: u16) -> Option<u8> {
+        Some(if addr == 0 { self.ddr } else { self.value() })
+    }
+
+    fn answers_reads(&self) -> bool {
+        true
+    }
+}
+
+/// An I/O chip that only answers while the PLA maps the I/O area in
+struct IoChip<D> {
+    chip: D,
+    io: Rc<Cell<bool>>,
+}
+
+impl<D> IoChip<D> {
+    fn new(chip: D, io: &Rc<Cell<bool>>) -> Self {
+        IoChip {
+            chip,
+            io: io.clone(),
+        }
+    }
+}
+
+impl<D: Device> Device for IoChip<D> {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.chip.read(addr)
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.chip.write(addr, value)
+    }
+
+    fn tick(&mut self) {
+        self.chip.tick()
+    }
+
+    fn interrupt(&self) -> bool {
+        self.chip.interrupt()
+    }
+
+    fn selected(&self) -> bool {
+        self.io.get()
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        self.chip.peek(addr)
+    }
+}
+
+/// 1K x 4 bit colour RAM, the upper nybble isn't connected
+pub struct ColorRam {
+    pub data: [u8; 0x400],
+}
+
+impl Device for ColorRam {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.data[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        self.data[addr as usize] = value & 0x0F;
+    }
+
+    fn peek(&self, addr: u16) -> Option<u8> {
+        Some(self.data[addr as usize])
+    }
+}
+
+/// The CPU side of a Commodore 64: 64K of RAM, the BASIC, KERNAL and
+/// character ROMs banked in by the processor port, both CIAs, colour RAM
+/// and register-only VIC-II and SID. There's no video, the screen is read
+/// back as text from screen RAM.
+pub struct C64 {
+    pub cpu: Cpu,
+    pub port: Rc<RefCell<ProcessorPort>>,
+    /// CIA 1 at $DC00: keyboard, joysticks, IRQ
+    pub cia1: Rc<RefCell<Cia>>,
+    /// CIA 2 at $DD00: serial bus, user port, NMI
+    pub cia2: Rc<RefCell<Cia>>,
+    pub vic: Rc<RefCell<Vic>>,
+    pub sid: Rc<RefCell<Sid>>,
+    pub color_ram: Rc<RefCell<ColorRam>>,
+    /// PETSCII waiting to go into the KERNAL keyboard buffer
+    keys: VecDeque<u8>,
+}
+
+impl C64 {
+    pub fn new(basic: &[u8], kernal: &[u8], chargen: &[u8]) -> Self {
+        assert_eq!(basic.len(), 0x2000, "BASIC ROM must be 8K");
+        assert_eq!(kernal.len(), 0x2000, "KERNAL ROM must be 8K");
+        assert_eq!(chargen.len(), 0x1000, "character ROM must be 4K");
+
+        let mut cpu = Cpu::new();
+        let banks = &mut cpu.memory.banks;
+        let io = Rc::new(Cell::new(true));
+        let port = Rc::new(RefCell::new(ProcessorPort {
+            ddr: 0,
+            data: 0,
+            pins: 0x17,
+            basic: banks.add_rom(basic),
+            kernal: banks.add_rom(kernal),
+            chargen: banks.add_rom(chargen),
+            io: io.clone(),
+        }));
+        port.borrow_mut().reset(banks);
+        cpu.memory
+            .map_bank_switch(Mapping::new(0x0000, 0x0001), port.clone());
+
+        let cia1 = Rc::new(RefCell::new(Cia::new()));
+        let cia2 = Rc::new(RefCell::new(Cia::new()));
+        let vic = Rc::new(RefCell::new(Vic::new()));
+        let sid = Rc::new(RefCell::new(Sid::new()));
+        let color_ram = Rc::new(RefCell::new(ColorRam { data: [0; 0x400] }));
+
+        let region = |start: u16, end: u16, mirror_mask| Mapping {
+            mirror_mask,
+            ..Mapping::new(start, end)
+        };
+        let memory = &mut cpu.memory;
+        memory.map(
+            region(0xD000, 0xD3FF, 0x003F),
+            IoChip::new(vic.clone(), &io),
+        );
+        let id = memory.map(
+            region(0xD400, 0xD7FF, 0x001F),
+            IoChip::new(sid.clone(), &io),
+        );
+        memory.connect(id, Line::None);
+        let id = memory.map(
+            region(0xD800, 0xDBFF, 0x03FF),
+            IoChip::new(color_ram.clone(), &io),
+        );
+        memory.connect(id, Line::None);
+        memory.map(
+            region(0xDC00, 0xDCFF, 0x000F),
+            IoChip::new(cia1.clone(), &io),
+        );
+        let id = memory.map(
+            region(0xDD00, 0xDDFF, 0x000F),
+            IoChip::new(cia2.clone(), &io),
+        );
+        memory.connect(id, Line::Nmi);
+
+        C64 {
+            cpu,
+            port,
+            cia1,
+            cia2,
+            vic,
+            sid,
+            color_ram,
+            keys: VecDeque::new(),
+        }
+    }
+
+    /// Loads `basic`, `kernal` and `chargen` (the names VICE uses) from `dir`
+    pub fn from_dir(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let basic = fs::read(dir.join("basic"))?;
+        let kernal = fs::read(dir.join("kernal"))?;
+        let chargen = fs::read(dir.join("chargen"))?;
+        Ok(Self::new(&basic, &kernal, &chargen))
+    }
+
+    pub fn reset(&mut self) {
+        self.port.borrow_mut().reset(&mut self.cpu.memory.banks);
+        self.cpu.reset();
+    }
+
+    /// Runs one instruction, returns the cycles it took
+    pub fn step(&mut self) -> u32 {
+        self.feed_keyboard();
+        self.cpu.step()
+    }
+
+    /// Runs for at least `cycles` CPU cycles
+    pub fn run(&mut self, cycles: u64) {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.step() as u64;
+        }
+    }
+
+    /// Types `text` through the KERNAL keyboard buffer, `\n` is RETURN
+    pub fn type_text(&mut self, text: &str) {
+        self.keys.extend(text.bytes().map(|ch| match ch {
+            b'\n' => 0x0D,
+            _ => ch.to_ascii_uppercase(),
+        }));
+    }
+
+    /// Screen RAM as text, one line per row with trailing blanks trimmed.
+    /// Reverse video (the cursor) is ignored and graphics show up as '?'.
+    pub fn screen_text(&self) -> String {
+        let screen = &self.cpu.memory.banks.bank(BASE_RAM)[SCREEN as usize..];
+        screen
+            .chunks(SCREEN_COLUMNS)
+            .take(SCREEN_ROWS)
+            .map(|row| {
+                let line: String = row.iter().map(|&code| screen_code_to_char(code)).collect();
+                line.trim_end().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Tops the keyboard buffer up once the KERNAL has emptied it, so
+    /// nothing is pulled out from under its interrupt handler
+    fn feed_keyboard(&mut self) {
+        if self.keys.is_empty() || self.cpu.memory[KEYBOARD_COUNT] != 0 {
+            return;
+        }
+
+        let count = self.keys.len().min(KEYBOARD_BUFFER_SIZE);
+        for (i, key) in self.keys.drain(..count).enumerate() {
+            self.cpu.memory[KEYBOARD_BUFFER + i] = key;
+        }
+        self.cpu.memory[KEYBOARD_COUNT] = count as u8;
+    }
+}
+
+/// Uppercase/graphics character set
+fn screen_code_to_char(code: u8) -> char {
+    match code & 0x7F {
+        0x00 => '@',
+        code @ 0x01..=0x1A => (b'A' + code - 1) as char,
+        0x1B => '[',
+        0x1C => '£',
+        0x1D => ']',
+        0x1E => '↑',
+        0x1F => '←',
+        code @ 0x20..=0x3F => code as char,
+        _ => '?',
+    }
+}
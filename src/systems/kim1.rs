@@ -0,0 +1,237 @@
+use crate::{
+    bus::{Line, Mapping},
+    cpu::Cpu,
+    devices::{rriot::Rriot, serial::SerialHost},
+};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+// Information grabbed from the KIM-1 User Manual and its monitor listing
+
+/// KIM-1 CPU clock
+pub const CLOCK_HZ: u32 = 1_000_000;
+
+/// CPU cycles per bit on the TTY line (1200 baud)
+pub const TTY_BIT_CYCLES: u32 = CLOCK_HZ / 1200;
+
+/// Typed after reset so the monitor can time its start bit
+const RUBOUT: u8 = 0x7F;
+
+/// Keypad codes past the hex digits, as returned by the monitor's GETKEY
+pub const KEY_AD: u8 = 0x10;
+pub const KEY_DA: u8 = 0x11;
+pub const KEY_PLUS: u8 = 0x12;
+pub const KEY_GO: u8 = 0x13;
+pub const KEY_PC: u8 = 0x14;
+
+/// A KIM-1: 1K of RAM, the 6530-002 (monitor) and 6530-003 (tape) RRIOTs,
+/// the keypad and six digit display, and a TTY on a `SerialHost`.
+///
+/// The monitor bit-bangs the TTY through PA7 (in) and PB0 (out) of the
+/// 6530-002, with delay loops calibrated against a RUBOUT typed after
+/// reset. The terminal end of the line sends and samples frames of one
+/// start bit, eight data bits and two stop bits at `TTY_BIT_CYCLES`.
+pub struct Kim1 {
+    pub cpu: Cpu,
+    /// 6530-002, I/O and timer at $1740
+    pub rriot_002: Rc<RefCell<Rriot>>,
+    /// 6530-003, I/O and timer at $1700
+    pub rriot_003: Rc<RefCell<Rriot>>,
+    /// The TTY jumper is fitted: the monitor talks to the terminal
+    /// instead of the keypad and display
+    pub tty: bool,
+    /// Keypad key held down (see the `KEY_*` codes)
+    pub key: Option<u8>,
+    /// Segments last lit on each display digit (bit 0 = segment a)
+    pub display: [u8; 6],
+    line: TtyLine,
+}
+
+impl Kim1 {
+    /// `rom_002` and `rom_003` are the 1K mask ROM images of the two RRIOTs
+    pub fn new(rom_002: &[u8], rom_003: &[u8], terminal: impl SerialHost + 'static) -> Self {
+        assert_eq!(rom_002.len(), 0x400, "6530-002 ROM must be 1K");
+        assert_eq!(rom_003.len(), 0x400, "6530-003 ROM must be 1K");
+
+        let mut cpu = Cpu::new();
+        let rriot_002 = Rc::new(RefCell::new(Rriot::new()));
+        let rriot_003 = Rc::new(RefCell::new(Rriot::new()));
+
+        cpu.memory.map_rom(0x1800, rom_003);
+        cpu.memory.map_rom(0x1C00, rom_002);
+        // A13-A15 aren't decoded, so the monitor (and its vectors) shows up
+        // at the top of memory too
+        cpu.memory.map_rom(0xFC00, rom_002);
+
+        for (start, rriot) in [(0x1700, &rriot_003), (0x1740, &rriot_002)] {
+            let id = cpu.memory.map(
+                Mapping {
+                    mirror_mask: 0x000F,
+                    ..Mapping::new(start, start + 0x3F)
+                },
+                rriot.clone(),
+            );
+            // IRQ on PB7 is a jumper option the stock board leaves open
+            cpu.memory.connect(id, Line::None);
+        }
+
+        Kim1 {
+            cpu,
+            rriot_002,
+            rriot_003,
+            tty: true,
+            key: None,
+            display: [0; 6],
+            line: TtyLine {
+                terminal: Box::new(terminal),
+                typed: VecDeque::new(),
+                sending: None,
+                receiving: None,
+            },
+        }
+    }
+
+    /// Presses RS
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    /// Types a RUBOUT ahead of the terminal's input. In TTY mode the
+    /// monitor waits for one after reset to measure the bit rate.
+    pub fn rubout(&mut self) {
+        self.line.typed.push_back(RUBOUT);
+    }
+
+    /// Runs one instruction, returns the cycles it took
+    pub fn step(&mut self) -> u32 {
+        self.drive_keypad();
+
+        let cycles = self.cpu.step();
+        self.latch_display();
+        if self.tty {
+            let mark = self.rriot_002.borrow().port_b() & 0x01 != 0;
+            self.line.clock(cycles, mark);
+        }
+        cycles
+    }
+
+    /// Runs for at least `cycles` CPU cycles
+    pub fn run(&mut self, cycles: u64) {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.step() as u64;
+        }
+    }
+
+    /// PB1-PB4 drive a 74145 decoder: outputs 0-2 scan the keypad rows onto
+    /// PA0-PA6, output 3 goes to PA0 through the TTY jumper and 4-9 select
+    /// the display digits
+    fn selected_row(&self) -> u8 {
+        (self.rriot_002.borrow().port_b() >> 1) & 0x0F
+    }
+
+    fn drive_keypad(&mut self) {
+        let row = self.selected_row();
+        let mut pins = 0xFF;
+        // PA7 is the TTY input
+        if self.tty && !self.line.level() {
+            pins &= !0x80;
+        }
+        if let Some(key) = self.key {
+            if key / 7 == row {
+                pins &= !(1 << (key % 7));
+            }
+        }
+        if row == 3 && self.tty {
+            pins &= !0x01;
+        }
+        self.rriot_002.borrow_mut().set_port_a(pins);
+    }
+
+    /// The monitor lights one digit at a time and blanks it before moving
+    /// on, so keep the last pattern shown on each
+    fn latch_display(&mut self) {
+        let row = self.selected_row();
+        let rriot = self.rriot_002.borrow();
+        let segments = rriot.port_a() & rriot.ddra & 0x7F;
+        if (4..=9).contains(&row) && segments != 0 {
+            self.display[(row - 4) as usize] = segments;
+        }
+    }
+}
+
+/// Terminal end of the TTY line
+struct TtyLine {
+    terminal: Box<dyn SerialHost>,
+    /// Sent ahead of the terminal's input
+    typed: VecDeque<u8>,
+    /// Character going to the KIM, and cycles since its start bit
+    sending: Option<(u8, u32)>,
+    /// Character coming from the KIM: cycles since its start bit, bits
+    /// sampled so far (start bit included) and the data
+    receiving: Option<(u32, u32, u8)>,
+}
+
+impl TtyLine {
+    /// Level driven onto PA7 (true = mark)
+    fn level(&self) -> bool {
+        match self.sending {
+            Some((ch, elapsed)) => match elapsed / TTY_BIT_CYCLES {
+                0 => false,
+                bit @ 1..=8 => ch >> (bit - 1) & 0x01 != 0,
+                _ => true,
+            },
+            None => true,
+        }
+    }
+
+    /// Moves the line on by `cycles`, with PB0 at `mark` since they started
+    fn clock(&mut self, cycles: u32, mark: bool) {
+        if let Some((ch, elapsed)) = self.sending {
+            let elapsed = elapsed + cycles;
+            self.sending = (elapsed < 11 * TTY_BIT_CYCLES).then_some((ch, elapsed));
+        }
+        if self.sending.is_none() {
+            self.sending = self.next_char().map(|ch| (ch, 0));
+        }
+
+        match self.receiving {
+            None if !mark => self.receiving = Some((0, 0, 0)),
+            None => {}
+            Some((elapsed, mut bits, mut data)) => {
+                let elapsed = elapsed + cycles;
+                // Sample each bit in its middle
+                while elapsed >= TTY_BIT_CYCLES / 2 + bits * TTY_BIT_CYCLES {
+                    match bits {
+                        // A glitch rather than a start bit
+                        0 if mark => {
+                            self.receiving = None;
+                            return;
+                        }
+                        0 => {}
+                        1..=8 => data |= (mark as u8) << (bits - 1),
+                        _ => {
+                            // Frames without a stop bit are dropped
+                            if mark {
+                                self.terminal.transmit(data & 0x7F);
+                            }
+                            self.receiving = None;
+                            return;
+                        }
+                    }
+                    bits += 1;
+                }
+                self.receiving = Some((elapsed, bits, data));
+            }
+        }
+    }
+
+    fn next_char(&mut self) -> Option<u8> {
+        if let Some(ch) = self.typed.pop_front() {
+            return Some(ch);
+        }
+        match self.terminal.receive()? {
+            b'\n' => Some(b'\r'),
+            ch => Some(ch.to_ascii_uppercase() & 0x7F),
+        }
+    }
+}
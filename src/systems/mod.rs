@@ -0,0 +1,6 @@
+// Complete machines built around the `Cpu`
+pub mod apple1;
+pub mod atari2600;
+pub mod c64;
+pub mod kim1;
+pub mod nes;
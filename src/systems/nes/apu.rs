@@ -0,0 +1,175 @@
+// Information grabbed from https://www.nesdev.org/wiki/APU_Frame_Counter
+// and https://www.nesdev.org/wiki/APU_Length_Counter
+
+/// Register offsets from $4000
+pub const STATUS: u16 = 0x15;
+pub const FRAME_COUNTER: u16 = 0x17;
+
+/// $4017 bits
+const FRAME_FIVE_STEP: u8 = 0x80;
+const FRAME_IRQ_INHIBIT: u8 = 0x40;
+
+/// $4015 read bits
+const STATUS_FRAME_IRQ: u8 = 0x40;
+
+/// Lengths the upper five bits of $4003/$4007/$400B/$400F pick
+const LENGTHS: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+/// Frame counter steps in CPU cycles, the 4-step sequence raises its IRQ
+/// over the last three cycles. Only half frames are kept: quarter frames
+/// clock the envelopes and the linear counter, which we don't have.
+const HALF_1: u32 = 14913;
+const FOUR_STEP_IRQ: u32 = 29828;
+const FOUR_STEP_LAST: u32 = 29829;
+const FOUR_STEP_PERIOD: u32 = 29830;
+const FIVE_STEP_LAST: u32 = 37281;
+const FIVE_STEP_PERIOD: u32 = 37282;
+
+#[derive(Default, Clone, Copy)]
+struct LengthCounter {
+    enabled: bool,
+    halted: bool,
+    count: u8,
+}
+
+impl LengthCounter {
+    fn load(&mut self, value: u8) {
+        if self.enabled {
+            self.count = LENGTHS[value as usize >> 3];
+        }
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.count = 0;
+        }
+    }
+
+    fn clock(&mut self) {
+        if !self.halted && self.count > 0 {
+            self.count -= 1;
+        }
+    }
+}
+
+/// The parts of the 2A03's APU a program can observe without listening:
+/// the frame counter with its IRQ and the length counters of the pulse,
+/// triangle and noise channels. Nothing is synthesized and the DMC is
+/// always idle.
+#[derive(Default)]
+pub struct Apu {
+    /// Pulse 1, pulse 2, triangle, noise
+    lengths: [LengthCounter; 4],
+    five_step: bool,
+    irq_inhibit: bool,
+    irq_flag: bool,
+    /// CPU cycles into the frame counter sequence
+    cycle: u32,
+    /// A $4017 write restarts the sequence after 3 or 4 cycles
+    restart_delay: Option<u8>,
+    odd_cycle: bool,
+}
+
+impl Apu {
+    /// Frame counter IRQ output (true = asserted)
+    pub fn irq(&self) -> bool {
+        self.irq_flag
+    }
+
+    pub fn read(&mut self, addr: u16) -> u8 {
+        if addr != STATUS {
+            return 0;
+        }
+
+        let mut value = 0;
+        for (i, length) in self.lengths.iter().enumerate() {
+            if length.count > 0 {
+                value |= 1 << i;
+            }
+        }
+        if self.irq_flag {
+            value |= STATUS_FRAME_IRQ;
+        }
+        self.irq_flag = false;
+        value
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x00 | 0x04 | 0x0C => self.lengths[addr as usize / 4].halted = value & 0x20 != 0,
+            0x08 => self.lengths[2].halted = value & 0x80 != 0,
+            0x03 | 0x07 | 0x0B | 0x0F => self.lengths[addr as usize / 4].load(value),
+            STATUS => {
+                for (i, length) in self.lengths.iter_mut().enumerate() {
+                    length.set_enabled(value & (1 << i) != 0);
+                }
+            }
+            FRAME_COUNTER => {
+                self.five_step = value & FRAME_FIVE_STEP != 0;
+                self.irq_inhibit = value & FRAME_IRQ_INHIBIT != 0;
+                if self.irq_inhibit {
+                    self.irq_flag = false;
+                }
+                self.restart_delay = Some(if self.odd_cycle { 4 } else { 3 });
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances one CPU cycle
+    pub fn tick(&mut self) {
+        self.odd_cycle = !self.odd_cycle;
+
+        if let Some(delay) = self.restart_delay {
+            if delay <= 1 {
+                self.restart_delay = None;
+                self.cycle = 0;
+                // The 5-step sequence clocks everything right away
+                if self.five_step {
+                    self.clock_half_frame();
+                }
+                return;
+            }
+            self.restart_delay = Some(delay - 1);
+        }
+
+        self.cycle += 1;
+        if self.five_step {
+            match self.cycle {
+                HALF_1 | FIVE_STEP_LAST => self.clock_half_frame(),
+                FIVE_STEP_PERIOD => self.cycle = 0,
+                _ => {}
+            }
+        } else {
+            match self.cycle {
+                HALF_1 => self.clock_half_frame(),
+                FOUR_STEP_IRQ => self.raise_irq(),
+                FOUR_STEP_LAST => {
+                    self.raise_irq();
+                    self.clock_half_frame();
+                }
+                FOUR_STEP_PERIOD => {
+                    self.raise_irq();
+                    self.cycle = 0;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn raise_irq(&mut self) {
+        if !self.irq_inhibit {
+            self.irq_flag = true;
+        }
+    }
+
+    fn clock_half_frame(&mut self) {
+        for length in &mut self.lengths {
+            length.clock();
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use super::mapper;
+use std::{error::Error, fmt};
+
+// Information grabbed from https://www.nesdev.org/wiki/INES
+
+const HEADER_SIZE: usize = 16;
+const TRAINER_SIZE: usize = 512;
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+/// How the PPU's 2K of nametable RAM is laid out over its four nametables
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    SingleScreenLower,
+    SingleScreenUpper,
+    /// The cartridge brings 2K of extra RAM for four distinct nametables
+    FourScreen,
+}
+
+impl Mirroring {
+    /// Offset into nametable RAM for a PPU address in $2000-$3EFF
+    pub fn nametable_offset(self, addr: u16) -> usize {
+        let addr = addr as usize & 0x0FFF;
+        let table = addr / 0x400;
+        let bank = match self {
+            Mirroring::Horizontal => table / 2,
+            Mirroring::Vertical => table % 2,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen => table,
+        };
+        bank * 0x400 + (addr & 0x3FF)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CartridgeError {
+    /// Missing the "NES\x1A" magic
+    BadHeader,
+    /// The file is shorter than its header says
+    Truncated,
+    UnsupportedMapper(u8),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::BadHeader => write!(f, "not an iNES file"),
+            CartridgeError::Truncated => write!(f, "iNES file is truncated"),
+            CartridgeError::UnsupportedMapper(n) => write!(f, "mapper {} is not supported", n),
+        }
+    }
+}
+
+impl Error for CartridgeError {}
+
+/// A game cartridge, as loaded from an iNES file
+pub struct Cartridge {
+    pub prg_rom: Vec<u8>,
+    /// CHR ROM, or 8K of CHR RAM for boards without one
+    pub chr: Vec<u8>,
+    pub chr_is_ram: bool,
+    pub mirroring: Mirroring,
+    /// iNES mapper number
+    pub mapper: u8,
+    /// PRG RAM is battery backed
+    pub battery: bool,
+}
+
+impl Cartridge {
+    pub fn from_ines(data: &[u8]) -> Result<Self, CartridgeError> {
+        if data.len() < HEADER_SIZE || &data[..4] != b"NES\x1A" {
+            return Err(CartridgeError::BadHeader);
+        }
+
+        let prg_size = data[4] as usize * PRG_BANK_SIZE;
+        let chr_size = data[5] as usize * CHR_BANK_SIZE;
+        let flags6 = data[6];
+        let flags7 = data[7];
+
+        let mapper = (flags7 & 0xF0) | (flags6 >> 4);
+        let mirroring = if flags6 & 0x08 != 0 {
+            Mirroring::FourScreen
+        } else if flags6 & 0x01 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+
+        let mut offset = HEADER_SIZE;
+        if flags6 & 0x04 != 0 {
+            offset += TRAINER_SIZE;
+        }
+        let prg_end = offset + prg_size;
+        let chr_end = prg_end + chr_size;
+        if data.len() < chr_end || prg_size == 0 {
+            return Err(CartridgeError::Truncated);
+        }
+
+        let chr_is_ram = chr_size == 0;
+        let chr = if chr_is_ram {
+            vec![0; CHR_BANK_SIZE]
+        } else {
+            data[prg_end..chr_end].to_vec()
+        };
+
+        if !mapper::SUPPORTED.contains(&mapper) {
+            return Err(CartridgeError::UnsupportedMapper(mapper));
+        }
+
+        Ok(Cartridge {
+            prg_rom: data[offset..prg_end].to_vec(),
+            chr,
+            chr_is_ram,
+            mirroring,
+            mapper,
+            battery: flags6 & 0x02 != 0,
+        })
+    }
+}
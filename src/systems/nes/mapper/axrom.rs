@@ -0,0 +1,43 @@
+use super::{Board, Mapper};
+use crate::{banking::Banks, systems::nes::cartridge::Mirroring};
+
+/// Mapper 7: one switchable 32K PRG bank, 8K of CHR RAM and a register bit
+/// picking which nametable fills the screen
+pub struct Axrom {
+    board: Board,
+    mirroring: Mirroring,
+}
+
+impl Axrom {
+    pub fn new(board: Board, banks: &mut Banks) -> Self {
+        board.map_prg(banks, 0x8000, 0x8000, 0);
+        Axrom {
+            board,
+            mirroring: Mirroring::SingleScreenLower,
+        }
+    }
+}
+
+impl Mapper for Axrom {
+    fn write(&mut self, banks: &mut Banks, _addr: u16, value: u8) {
+        self.board
+            .map_prg(banks, 0x8000, 0x8000, (value & 0x07) as usize);
+        self.mirroring = if value & 0x10 != 0 {
+            Mirroring::SingleScreenUpper
+        } else {
+            Mirroring::SingleScreenLower
+        };
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.board.chr_read(0x2000, 0, addr)
+    }
+
+    fn chr_write(&mut self, addr: u16, value: u8) {
+        self.board.chr_write(0x2000, 0, addr, value)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
@@ -0,0 +1,34 @@
+use super::{Board, Mapper};
+use crate::{banking::Banks, systems::nes::cartridge::Mirroring};
+
+/// Mapper 3: NROM's PRG layout with a switchable 8K CHR ROM bank
+pub struct Cnrom {
+    board: Board,
+    chr_bank: usize,
+}
+
+impl Cnrom {
+    pub fn new(board: Board, banks: &mut Banks) -> Self {
+        board.map_prg(banks, 0x8000, 0x4000, 0);
+        board.map_prg(banks, 0xC000, 0x4000, 1);
+        Cnrom { board, chr_bank: 0 }
+    }
+}
+
+impl Mapper for Cnrom {
+    fn write(&mut self, _banks: &mut Banks, _addr: u16, value: u8) {
+        self.chr_bank = value as usize;
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.board.chr_read(0x2000, self.chr_bank, addr)
+    }
+
+    fn chr_write(&mut self, addr: u16, value: u8) {
+        self.board.chr_write(0x2000, self.chr_bank, addr, value)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.board.mirroring
+    }
+}
@@ -0,0 +1,111 @@
+use super::{Board, Mapper};
+use crate::{banking::Banks, systems::nes::cartridge::Mirroring};
+
+// Information grabbed from https://www.nesdev.org/wiki/MMC1
+
+/// Mapper 1: registers loaded one bit at a time through a 5-bit shift
+/// register, with 16K/32K PRG and 4K/8K CHR banking modes
+pub struct Mmc1 {
+    board: Board,
+    /// Shift register, a 1 marks how far it has been filled
+    shift: u8,
+    pub control: u8,
+    pub chr_bank_0: u8,
+    pub chr_bank_1: u8,
+    pub prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(board: Board, banks: &mut Banks) -> Self {
+        let mmc1 = Mmc1 {
+            board,
+            shift: 0x10,
+            // Powers up with the last PRG bank fixed at $C000
+            control: 0x0C,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+        };
+        mmc1.update_prg(banks);
+        mmc1
+    }
+
+    fn update_prg(&self, banks: &mut Banks) {
+        let bank = (self.prg_bank & 0x0F) as usize;
+        match (self.control >> 2) & 0x03 {
+            // 32K at $8000, ignoring the low bit
+            0 | 1 => self.board.map_prg(banks, 0x8000, 0x8000, bank >> 1),
+            // First bank fixed at $8000, switch $C000
+            2 => {
+                self.board.map_prg(banks, 0x8000, 0x4000, 0);
+                self.board.map_prg(banks, 0xC000, 0x4000, bank);
+            }
+            // Switch $8000, last bank fixed at $C000
+            _ => {
+                let last = self.board.prg_banks(banks, 0x4000) - 1;
+                self.board.map_prg(banks, 0x8000, 0x4000, bank);
+                self.board.map_prg(banks, 0xC000, 0x4000, last);
+            }
+        }
+    }
+
+    /// 4K CHR bank and offset within it for a PPU address
+    fn chr_bank(&self, addr: u16) -> (usize, u16) {
+        if self.control & 0x10 == 0 {
+            // 8K mode ignores the low bit
+            let bank = (self.chr_bank_0 & 0x1E) as usize + (addr >= 0x1000) as usize;
+            (bank, addr & 0x0FFF)
+        } else if addr < 0x1000 {
+            (self.chr_bank_0 as usize, addr)
+        } else {
+            (self.chr_bank_1 as usize, addr & 0x0FFF)
+        }
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8) {
+        if value & 0x80 != 0 {
+            self.shift = 0x10;
+            self.control |= 0x0C;
+            self.update_prg(banks);
+            return;
+        }
+
+        let full = self.shift & 0x01 != 0;
+        self.shift = (self.shift >> 1) | ((value & 0x01) << 4);
+        if !full {
+            return;
+        }
+
+        // Fifth write: A13-A14 pick the register
+        let data = self.shift;
+        self.shift = 0x10;
+        match addr & 0x6000 {
+            0x0000 => self.control = data,
+            0x2000 => self.chr_bank_0 = data,
+            0x4000 => self.chr_bank_1 = data,
+            _ => self.prg_bank = data,
+        }
+        self.update_prg(banks);
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        let (bank, offset) = self.chr_bank(addr);
+        self.board.chr_read(0x1000, bank, offset)
+    }
+
+    fn chr_write(&mut self, addr: u16, value: u8) {
+        let (bank, offset) = self.chr_bank(addr);
+        self.board.chr_write(0x1000, bank, offset, value)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+}
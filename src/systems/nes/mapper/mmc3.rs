@@ -0,0 +1,136 @@
+use super::{Board, Mapper};
+use crate::{banking::Banks, systems::nes::cartridge::Mirroring};
+
+// Information grabbed from https://www.nesdev.org/wiki/MMC3
+
+/// Mapper 4: 8K PRG and 1K/2K CHR banks through eight bank registers, and
+/// a scanline counter that raises IRQ
+pub struct Mmc3 {
+    board: Board,
+    /// Register picked by the next bank data write, plus the PRG and CHR
+    /// mode bits
+    pub bank_select: u8,
+    /// R0-R7
+    pub registers: [u8; 8],
+    mirroring: Mirroring,
+
+    pub irq_latch: u8,
+    pub irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+}
+
+impl Mmc3 {
+    pub fn new(board: Board, banks: &mut Banks) -> Self {
+        let mirroring = board.mirroring;
+        let mmc3 = Mmc3 {
+            board,
+            bank_select: 0,
+            registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+        };
+        mmc3.update_prg(banks);
+        mmc3
+    }
+
+    fn update_prg(&self, banks: &mut Banks) {
+        // An 8K ROM is its own second last bank
+        let second_last = self.board.prg_banks(banks, 0x2000).saturating_sub(2);
+        let r6 = (self.registers[6] & 0x3F) as usize;
+        let r7 = (self.registers[7] & 0x3F) as usize;
+
+        let (low, high) = if self.bank_select & 0x40 == 0 {
+            (r6, second_last)
+        } else {
+            (second_last, r6)
+        };
+        self.board.map_prg(banks, 0x8000, 0x2000, low);
+        self.board.map_prg(banks, 0xA000, 0x2000, r7);
+        self.board.map_prg(banks, 0xC000, 0x2000, high);
+        self.board.map_prg(banks, 0xE000, 0x2000, second_last + 1);
+    }
+
+    /// 1K CHR bank for a PPU address
+    fn chr_bank(&self, addr: u16) -> usize {
+        // A12 inversion swaps the 2K and 1K halves
+        let addr = if self.bank_select & 0x80 != 0 {
+            addr ^ 0x1000
+        } else {
+            addr
+        };
+        let slot = (addr >> 10) as usize & 0x07;
+        match slot {
+            0..=3 => (self.registers[slot / 2] & 0xFE) as usize + slot % 2,
+            _ => self.registers[slot - 2] as usize,
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8) {
+        let odd = addr & 0x01 != 0;
+        match (addr & 0xE000, odd) {
+            (0x8000, false) => self.bank_select = value,
+            (0x8000, true) => self.registers[(self.bank_select & 0x07) as usize] = value,
+            (0xA000, false) => {
+                if self.board.mirroring != Mirroring::FourScreen {
+                    self.mirroring = if value & 0x01 != 0 {
+                        Mirroring::Horizontal
+                    } else {
+                        Mirroring::Vertical
+                    };
+                }
+            }
+            // PRG RAM protect isn't emulated
+            (0xA000, true) => {}
+            (0xC000, false) => self.irq_latch = value,
+            (0xC000, true) => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            (0xE000, false) => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            _ => self.irq_enabled = true,
+        }
+        self.update_prg(banks);
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.board
+            .chr_read(0x400, self.chr_bank(addr), addr & 0x3FF)
+    }
+
+    fn chr_write(&mut self, addr: u16, value: u8) {
+        let bank = self.chr_bank(addr);
+        self.board.chr_write(0x400, bank, addr & 0x3FF, value)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn scanline(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+}
@@ -0,0 +1,131 @@
+pub mod axrom;
+pub mod cnrom;
+pub mod mmc1;
+pub mod mmc3;
+pub mod nrom;
+pub mod uxrom;
+
+use super::cartridge::{Cartridge, CartridgeError, Mirroring};
+use crate::banking::{BankId, BankSwitch, Banks};
+use std::{cell::RefCell, rc::Rc};
+
+// Information grabbed from https://www.nesdev.org/wiki/Mapper
+
+/// The cartridge hardware between the console and the ROMs.
+/// PRG ROM lives in the CPU's `Banks` and is remapped on register writes,
+/// CHR and nametable mirroring are looked up by the PPU.
+pub trait Mapper {
+    /// CPU write to $8000-$FFFF
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8);
+
+    /// PPU pattern table read ($0000-$1FFF)
+    fn chr_read(&self, addr: u16) -> u8;
+    /// PPU pattern table write, only CHR RAM takes it
+    fn chr_write(&mut self, addr: u16, value: u8);
+
+    fn mirroring(&self) -> Mirroring;
+
+    /// Clocked once per scanline while the PPU is rendering (where the
+    /// MMC3 would see A12 rise)
+    fn scanline(&mut self) {}
+
+    /// IRQ output (true = asserted)
+    fn irq(&self) -> bool {
+        false
+    }
+}
+
+/// iNES mapper numbers that `new` can build
+pub const SUPPORTED: [u8; 6] = [0, 1, 2, 3, 4, 7];
+
+/// Builds the mapper for `cartridge`, mapping its PRG ROM and 8K of PRG RAM
+/// (at $6000) into `banks`
+pub fn new(
+    cartridge: Cartridge,
+    banks: &mut Banks,
+) -> Result<Rc<RefCell<dyn Mapper>>, CartridgeError> {
+    let mapper = cartridge.mapper;
+    let board = Board::new(cartridge, banks);
+    Ok(match mapper {
+        0 => Rc::new(RefCell::new(nrom::Nrom::new(board, banks))),
+        1 => Rc::new(RefCell::new(mmc1::Mmc1::new(board, banks))),
+        2 => Rc::new(RefCell::new(uxrom::Uxrom::new(board, banks))),
+        3 => Rc::new(RefCell::new(cnrom::Cnrom::new(board, banks))),
+        4 => Rc::new(RefCell::new(mmc3::Mmc3::new(board, banks))),
+        7 => Rc::new(RefCell::new(axrom::Axrom::new(board, banks))),
+        n => return Err(CartridgeError::UnsupportedMapper(n)),
+    })
+}
+
+/// What every board has: PRG ROM in the CPU banks, CHR ROM or RAM and the
+/// mirroring wired on the board
+pub struct Board {
+    prg_rom: BankId,
+    pub chr: Vec<u8>,
+    pub chr_is_ram: bool,
+    pub mirroring: Mirroring,
+}
+
+impl Board {
+    fn new(cartridge: Cartridge, banks: &mut Banks) -> Self {
+        let prg_ram = banks.add_ram(0x2000);
+        banks.map(0x60, 0x20, prg_ram, 0);
+
+        Board {
+            prg_rom: banks.add_rom(&cartridge.prg_rom),
+            chr: cartridge.chr,
+            chr_is_ram: cartridge.chr_is_ram,
+            mirroring: cartridge.mirroring,
+        }
+    }
+
+    /// Number of PRG ROM banks of `size` bytes
+    pub fn prg_banks(&self, banks: &Banks, size: usize) -> usize {
+        (banks.bank_pages(self.prg_rom) * 0x100 / size).max(1)
+    }
+
+    /// Maps PRG ROM bank `bank` (of `size` bytes, wrapping around the ROM)
+    /// at CPU address `addr`. A ROM smaller than `size` shows up mirrored
+    /// across the window.
+    pub fn map_prg(&self, banks: &mut Banks, addr: u16, size: usize, bank: usize) {
+        let pages = size / 0x100;
+        let rom_pages = banks.bank_pages(self.prg_rom);
+        if rom_pages < pages {
+            for first in (0..pages).step_by(rom_pages) {
+                let count = rom_pages.min(pages - first);
+                banks.map((addr >> 8) as u8 + first as u8, count, self.prg_rom, 0);
+            }
+            return;
+        }
+
+        let bank = bank % self.prg_banks(banks, size);
+        banks.map((addr >> 8) as u8, pages, self.prg_rom, bank * pages);
+    }
+
+    /// Reads CHR at `bank` (of `size` bytes, wrapping around CHR) plus `offset`
+    pub fn chr_read(&self, size: usize, bank: usize, offset: u16) -> u8 {
+        self.chr[self.chr_offset(size, bank, offset)]
+    }
+
+    pub fn chr_write(&mut self, size: usize, bank: usize, offset: u16, value: u8) {
+        if self.chr_is_ram {
+            let offset = self.chr_offset(size, bank, offset);
+            self.chr[offset] = value;
+        }
+    }
+
+    fn chr_offset(&self, size: usize, bank: usize, offset: u16) -> usize {
+        let count = (self.chr.len() / size).max(1);
+        ((bank % count) * size + (offset as usize % size)) % self.chr.len()
+    }
+}
+
+/// The mapper as seen from the CPU bus
+pub struct MapperPort(pub Rc<RefCell<dyn Mapper>>);
+
+impl BankSwitch for MapperPort {
+    fn write(&mut self, banks: &mut Banks, addr: u16, value: u8) {
+        // Mapped at $8000, so put A15 back on the offset
+        self.0.borrow_mut().write(banks, addr | 0x8000, value)
+    }
+}
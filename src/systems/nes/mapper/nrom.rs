@@ -0,0 +1,32 @@
+use super::{Board, Mapper};
+use crate::{banking::Banks, systems::nes::cartridge::Mirroring};
+
+/// Mapper 0: 16K or 32K of PRG ROM (16K shows up twice) and 8K of CHR,
+/// no registers
+pub struct Nrom {
+    board: Board,
+}
+
+impl Nrom {
+    pub fn new(board: Board, banks: &mut Banks) -> Self {
+        board.map_prg(banks, 0x8000, 0x4000, 0);
+        board.map_prg(banks, 0xC000, 0x4000, 1);
+        Nrom { board }
+    }
+}
+
+impl Mapper for Nrom {
+    fn write(&mut self, _banks: &mut Banks, _addr: u16, _value: u8) {}
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.board.chr_read(0x2000, 0, addr)
+    }
+
+    fn chr_write(&mut self, addr: u16, value: u8) {
+        self.board.chr_write(0x2000, 0, addr, value)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.board.mirroring
+    }
+}
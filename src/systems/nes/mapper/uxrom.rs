@@ -0,0 +1,35 @@
+use super::{Board, Mapper};
+use crate::{banking::Banks, systems::nes::cartridge::Mirroring};
+
+/// Mapper 2: a switchable 16K PRG bank at $8000 with the last one fixed at
+/// $C000, 8K of CHR RAM
+pub struct Uxrom {
+    board: Board,
+}
+
+impl Uxrom {
+    pub fn new(board: Board, banks: &mut Banks) -> Self {
+        let last = board.prg_banks(banks, 0x4000) - 1;
+        board.map_prg(banks, 0x8000, 0x4000, 0);
+        board.map_prg(banks, 0xC000, 0x4000, last);
+        Uxrom { board }
+    }
+}
+
+impl Mapper for Uxrom {
+    fn write(&mut self, banks: &mut Banks, _addr: u16, value: u8) {
+        self.board.map_prg(banks, 0x8000, 0x4000, value as usize);
+    }
+
+    fn chr_read(&self, addr: u16) -> u8 {
+        self.board.chr_read(0x2000, 0, addr)
+    }
+
+    fn chr_write(&mut self, addr: u16, value: u8) {
+        self.board.chr_write(0x2000, 0, addr, value)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.board.mirroring
+    }
+}
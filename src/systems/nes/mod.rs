@@ -0,0 +1,234 @@
+pub mod apu;
+pub mod cartridge;
+pub mod mapper;
+pub mod ppu;
+
+use crate::{
+    banking::BASE_RAM,
+    bus::{Device, Line, Mapping},
+    cpu::Cpu,
+    opcodes::Variant,
+};
+use apu::Apu;
+use cartridge::{Cartridge, CartridgeError};
+use mapper::{Mapper, MapperPort};
+use ppu::Ppu;
+use std::{cell::RefCell, rc::Rc};
+
+// Information grabbed from https://www.nesdev.org/wiki/CPU_memory_map
+
+/// NTSC CPU clock
+pub const CLOCK_HZ: u32 = 1_789_773;
+
+/// I/O register offsets from $4000
+pub const OAMDMA: u16 = 0x14;
+pub const JOY1: u16 = 0x16;
+pub const JOY2: u16 = 0x17;
+
+/// Controller button bits, in the order they are shifted out
+pub const BUTTON_A: u8 = 0x01;
+pub const BUTTON_B: u8 = 0x02;
+pub const BUTTON_SELECT: u8 = 0x04;
+pub const BUTTON_START: u8 = 0x08;
+pub const BUTTON_UP: u8 = 0x10;
+pub const BUTTON_DOWN: u8 = 0x20;
+pub const BUTTON_LEFT: u8 = 0x40;
+pub const BUTTON_RIGHT: u8 = 0x80;
+
+/// A standard controller: an 8-bit shift register loaded while strobed
+#[derive(Default)]
+pub struct Controller {
+    /// Buttons held down (see the `BUTTON_*` bits)
+    pub buttons: u8,
+    shift: u8,
+    /// Bits shifted out since the last reload, reads past 8 give 1
+    reads: u8,
+}
+
+impl Controller {
+    fn read(&mut self, strobe: bool) -> u8 {
+        if strobe {
+            return self.buttons & 0x01;
+        }
+        if self.reads >= 8 {
+            return 1;
+        }
+        let bit = self.shift & 0x01;
+        self.shift >>= 1;
+        self.reads += 1;
+        bit
+    }
+
+    fn reload(&mut self) {
+        self.shift = self.buttons;
+        self.reads = 0;
+    }
+}
+
+/// The 2A03's registers at $4000-$401F: the APU, OAM DMA and the
+/// controller ports. The interrupt output is the APU frame IRQ.
+#[derive(Default)]
+pub struct Io {
+    pub apu: Apu,
+    pub controllers: [Controller; 2],
+    strobe: bool,
+    /// Page written to OAMDMA, the transfer runs after the instruction
+    dma_page: Option<u8>,
+}
+
+impl Device for Io {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            // The upper bits are open bus, usually the high byte of $4016
+            JOY1 => self.controllers[0].read(self.strobe) | 0x40,
+            JOY2 => self.controllers[1].read(self.strobe) | 0x40,
+            _ => self.apu.read(addr),
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            OAMDMA => self.dma_page = Some(value),
+            JOY1 => {
+                self.strobe = value & 0x01 != 0;
+                if self.strobe {
+                    for controller in &mut self.controllers {
+                        controller.reload();
+                    }
+                }
+            }
+            _ => self.apu.write(addr, value),
+        }
+    }
+
+    fn tick(&mut self) {
+        self.apu.tick();
+
+        // The shift registers follow the buttons for as long as the strobe is high
+        if self.strobe {
+            for controller in &mut self.controllers {
+                controller.reload();
+            }
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.apu.irq()
+    }
+}
+
+/// A headless NES: the CPU with 2K of mirrored RAM, the PPU registers with
+/// vblank/NMI timing, the APU frame counter, controller ports, OAM DMA and
+/// the cartridge.
+/// Nothing is drawn or played, it's meant for running CPU test ROMs.
+pub struct Nes {
+    pub cpu: Cpu,
+    pub ppu: Rc<RefCell<Ppu>>,
+    pub io: Rc<RefCell<Io>>,
+    pub mapper: Rc<RefCell<dyn Mapper>>,
+    /// CPU cycles since power on
+    pub cycles: u64,
+}
+
+impl Nes {
+    /// Fails if there's no mapper for the cartridge's board
+    pub fn new(cartridge: Cartridge) -> Result<Self, CartridgeError> {
+        let mut cpu = Cpu::new();
+        cpu.variant = Variant::Ricoh2A03;
+        let banks = &mut cpu.memory.banks;
+
+        // 2K of RAM mirrored up to $1FFF
+        for mirror in [0x08, 0x10, 0x18] {
+            banks.map(mirror, 0x08, BASE_RAM, 0x00);
+        }
+
+        let mapper = mapper::new(cartridge, banks)?;
+        cpu.memory
+            .map_bank_switch(Mapping::new(0x8000, 0xFFFF), MapperPort(mapper.clone()));
+
+        let ppu = Rc::new(RefCell::new(Ppu::new(mapper.clone())));
+        let io = Rc::new(RefCell::new(Io::default()));
+
+        let id = cpu.memory.map(
+            Mapping {
+                mirror_mask: 0x0007,
+                ..Mapping::new(0x2000, 0x3FFF)
+            },
+            ppu.clone(),
+        );
+        cpu.memory.connect(id, Line::Nmi);
+        let id = cpu.memory.map(
+            Mapping {
+                mirror_mask: 0x001F,
+                ..Mapping::new(0x4000, 0x401F)
+            },
+            io.clone(),
+        );
+        cpu.memory.connect(id, Line::Irq);
+
+        Ok(Nes {
+            cpu,
+            ppu,
+            io,
+            mapper,
+            cycles: 0,
+        })
+    }
+
+    pub fn from_ines(data: &[u8]) -> Result<Self, CartridgeError> {
+        Self::new(Cartridge::from_ines(data)?)
+    }
+
+    /// Presses RESET
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    /// Runs one instruction (and any OAM DMA it started),
+    /// returns the cycles it took
+    pub fn step(&mut self) -> u32 {
+        let mut cycles = self.cpu.step();
+        self.cycles += cycles as u64;
+
+        let dma_page = self.io.borrow_mut().dma_page.take();
+        if let Some(page) = dma_page {
+            let stall = self.oam_dma(page);
+            self.cycles += stall as u64;
+            cycles += stall;
+        }
+
+        let irq = self.mapper.borrow().irq();
+        self.cpu.set_irq(irq);
+        cycles
+    }
+
+    /// Runs until the PPU starts its next vblank, returns the cycles it took
+    pub fn run_frame(&mut self) -> u64 {
+        let frame = self.ppu.borrow().frame;
+        let mut cycles = 0;
+        while self.ppu.borrow().frame == frame {
+            cycles += self.step() as u64;
+        }
+        cycles
+    }
+
+    /// Sets the buttons held on controller `port` (0 or 1)
+    pub fn set_buttons(&mut self, port: usize, buttons: u8) {
+        self.io.borrow_mut().controllers[port].buttons = buttons;
+    }
+
+    /// Copies a page into OAM, halting the CPU for 513 cycles
+    /// (514 when it starts on an odd cycle)
+    fn oam_dma(&mut self, page: u8) -> u32 {
+        let stall = 513 + (self.cycles & 1) as u32;
+
+        for i in 0..=0xFF {
+            let value = self.cpu.memory.read((page as u16) << 8 | i);
+            self.ppu.borrow_mut().write_oam(value);
+        }
+        for _ in 0..stall {
+            self.cpu.memory.tick();
+        }
+        stall
+    }
+}
@@ -0,0 +1,274 @@
+use super::{cartridge::Mirroring, mapper::Mapper};
+use crate::bus::Device;
+use std::{cell::RefCell, rc::Rc};
+
+// Information grabbed from https://www.nesdev.org/wiki/PPU_registers
+// and https://www.nesdev.org/wiki/PPU_rendering
+
+/// Register select values (A0-A2)
+pub const PPUCTRL: u16 = 0x0;
+pub const PPUMASK: u16 = 0x1;
+pub const PPUSTATUS: u16 = 0x2;
+pub const OAMADDR: u16 = 0x3;
+pub const OAMDATA: u16 = 0x4;
+pub const PPUSCROLL: u16 = 0x5;
+pub const PPUADDR: u16 = 0x6;
+pub const PPUDATA: u16 = 0x7;
+
+/// PPUCTRL bits
+pub const CTRL_INCREMENT_32: u8 = 0x04;
+pub const CTRL_NMI: u8 = 0x80;
+
+/// PPUMASK bits
+pub const MASK_BACKGROUND: u8 = 0x08;
+pub const MASK_SPRITES: u8 = 0x10;
+
+/// PPUSTATUS bits
+pub const STATUS_SPRITE_OVERFLOW: u8 = 0x20;
+pub const STATUS_SPRITE_0_HIT: u8 = 0x40;
+pub const STATUS_VBLANK: u8 = 0x80;
+
+pub const DOTS_PER_SCANLINE: u16 = 341;
+pub const SCANLINES: u16 = 262;
+pub const VBLANK_SCANLINE: u16 = 241;
+pub const PRERENDER_SCANLINE: u16 = 261;
+/// Dot the mapper's scanline counter is clocked on
+const MAPPER_DOT: u16 = 260;
+
+/// 2C02 PPU without any rendering.
+/// The registers, VRAM, palette and OAM behave as seen from the CPU and the
+/// dot/scanline counters run at 3 dots per CPU cycle, raising vblank and
+/// NMI on time. Mapped at $2000-$3FFF with a mirror mask of 0x0007, the
+/// interrupt output goes to the CPU's NMI.
+pub struct Ppu {
+    pub ctrl: u8,
+    pub mask: u8,
+    pub status: u8,
+    pub oam_addr: u8,
+    pub oam: [u8; 256],
+    /// Nametable RAM (4K so four-screen boards fit)
+    pub vram: [u8; 0x1000],
+    pub palette: [u8; 32],
+
+    /// Current VRAM address, temporary address, fine X scroll
+    pub v: u16,
+    pub t: u16,
+    pub fine_x: u8,
+    /// First/second write toggle shared by PPUSCROLL and PPUADDR
+    w: bool,
+    /// PPUDATA reads are delayed through this buffer
+    read_buffer: u8,
+
+    pub scanline: u16,
+    pub dot: u16,
+    /// Frames completed, counted at the start of vblank
+    pub frame: u64,
+    odd_frame: bool,
+
+    mapper: Rc<RefCell<dyn Mapper>>,
+}
+
+impl Ppu {
+    pub fn new(mapper: Rc<RefCell<dyn Mapper>>) -> Self {
+        Ppu {
+            ctrl: 0,
+            mask: 0,
+            status: 0,
+            oam_addr: 0,
+            oam: [0; 256],
+            vram: [0; 0x1000],
+            palette: [0; 32],
+            v: 0,
+            t: 0,
+            fine_x: 0,
+            w: false,
+            read_buffer: 0,
+            scanline: 0,
+            dot: 0,
+            frame: 0,
+            odd_frame: false,
+            mapper,
+        }
+    }
+
+    /// NMI output (true = asserted)
+    pub fn nmi(&self) -> bool {
+        self.status & STATUS_VBLANK != 0 && self.ctrl & CTRL_NMI != 0
+    }
+
+    pub fn rendering_enabled(&self) -> bool {
+        self.mask & (MASK_BACKGROUND | MASK_SPRITES) != 0
+    }
+
+    /// OAM DMA writes go through OAMDATA
+    pub fn write_oam(&mut self, value: u8) {
+        self.oam[self.oam_addr as usize] = value;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
+    }
+
+    /// Reads the PPU address space ($0000-$3FFF)
+    pub fn ppu_read(&self, addr: u16) -> u8 {
+        let addr = addr & 0x3FFF;
+        match addr {
+            0x0000..=0x1FFF => self.mapper.borrow().chr_read(addr),
+            0x2000..=0x3EFF => self.vram[self.mirroring().nametable_offset(addr)],
+            _ => self.palette[palette_index(addr)],
+        }
+    }
+
+    /// Writes the PPU address space ($0000-$3FFF)
+    pub fn ppu_write(&mut self, addr: u16, value: u8) {
+        let addr = addr & 0x3FFF;
+        match addr {
+            0x0000..=0x1FFF => self.mapper.borrow_mut().chr_write(addr, value),
+            0x2000..=0x3EFF => self.vram[self.mirroring().nametable_offset(addr)] = value,
+            _ => self.palette[palette_index(addr)] = value & 0x3F,
+        }
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mapper.borrow().mirroring()
+    }
+
+    fn increment_v(&mut self) {
+        let step = if self.ctrl & CTRL_INCREMENT_32 != 0 {
+            32
+        } else {
+            1
+        };
+        self.v = self.v.wrapping_add(step) & 0x7FFF;
+    }
+
+    /// Advances one dot
+    fn dot(&mut self) {
+        self.dot += 1;
+
+        // Odd frames skip the last dot of the pre-render line when rendering
+        if self.scanline == PRERENDER_SCANLINE
+            && self.dot == DOTS_PER_SCANLINE - 1
+            && self.odd_frame
+            && self.rendering_enabled()
+        {
+            self.dot = DOTS_PER_SCANLINE;
+        }
+
+        if self.dot == DOTS_PER_SCANLINE {
+            self.dot = 0;
+            self.scanline += 1;
+            if self.scanline == SCANLINES {
+                self.scanline = 0;
+                self.odd_frame = !self.odd_frame;
+            }
+        }
+
+        // With the usual pattern table setup (background at $0000, sprites
+        // at $1000) this is where A12 rises once per line
+        if self.dot == MAPPER_DOT
+            && (self.scanline < 240 || self.scanline == PRERENDER_SCANLINE)
+            && self.rendering_enabled()
+        {
+            self.mapper.borrow_mut().scanline();
+        }
+
+        if self.dot == 1 {
+            match self.scanline {
+                VBLANK_SCANLINE => {
+                    self.status |= STATUS_VBLANK;
+                    self.frame += 1;
+                }
+                PRERENDER_SCANLINE => {
+                    self.status &= !(STATUS_VBLANK | STATUS_SPRITE_0_HIT | STATUS_SPRITE_OVERFLOW);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Device for Ppu {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            PPUSTATUS => {
+                let value = self.status;
+                self.status &= !STATUS_VBLANK;
+                self.w = false;
+                value
+            }
+            OAMDATA => self.oam[self.oam_addr as usize],
+            PPUDATA => {
+                let addr = self.v & 0x3FFF;
+                let value = if addr >= 0x3F00 {
+                    // Palette reads skip the buffer, which gets the
+                    // nametable byte underneath instead
+                    self.read_buffer = self.ppu_read(addr - 0x1000);
+                    self.ppu_read(addr)
+                } else {
+                    let buffered = self.read_buffer;
+                    self.read_buffer = self.ppu_read(addr);
+                    buffered
+                };
+                self.increment_v();
+                value
+            }
+            // Write-only registers
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            PPUCTRL => {
+                self.ctrl = value;
+                self.t = (self.t & !0x0C00) | ((value as u16 & 0x03) << 10);
+            }
+            PPUMASK => self.mask = value,
+            OAMADDR => self.oam_addr = value,
+            OAMDATA => self.write_oam(value),
+            PPUSCROLL => {
+                if !self.w {
+                    self.t = (self.t & !0x001F) | (value as u16 >> 3);
+                    self.fine_x = value & 0x07;
+                } else {
+                    self.t = (self.t & !0x73E0)
+                        | ((value as u16 & 0x07) << 12)
+                        | ((value as u16 & 0xF8) << 2);
+                }
+                self.w = !self.w;
+            }
+            PPUADDR => {
+                if !self.w {
+                    self.t = (self.t & 0x00FF) | ((value as u16 & 0x3F) << 8);
+                } else {
+                    self.t = (self.t & 0xFF00) | value as u16;
+                    self.v = self.t;
+                }
+                self.w = !self.w;
+            }
+            PPUDATA => {
+                self.ppu_write(self.v, value);
+                self.increment_v();
+            }
+            _ => {}
+        }
+    }
+
+    fn tick(&mut self) {
+        for _ in 0..3 {
+            self.dot();
+        }
+    }
+
+    fn interrupt(&self) -> bool {
+        self.nmi()
+    }
+}
+
+/// $3F10/$3F14/$3F18/$3F1C mirror the background entries below them
+fn palette_index(addr: u16) -> usize {
+    let index = addr as usize & 0x1F;
+    if index & 0x13 == 0x10 {
+        index & 0x0F
+    } else {
+        index
+    }
+}
@@ -0,0 +1,132 @@
+/// What RAM holds at power-up, for `Bus::power_on`. Real chips come up
+/// with whatever their cells settle to, so code that only works on zeroed
+/// RAM is a bug waiting for the right machine.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PowerOn {
+    #[default]
+    Zeros,
+    /// Every byte $FF
+    Ones,
+    /// Pseudo-random bytes, the same for the same seed
+    Random { seed: u64 },
+    /// $00 and $FF in turn, byte by byte
+    Alternating,
+}
+
+impl PowerOn {
+    pub fn fill(self, ram: &mut [u8]) {
+        match self {
+            PowerOn::Zeros => ram.fill(0x00),
+            PowerOn::Ones => ram.fill(0xFF),
+            PowerOn::Random { seed } => {
+                // splitmix64 spreads the seed over the state, so every seed
+                // gives its own pattern; xorshift64 must not start at 0
+                let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                let mut state = (z ^ (z >> 31)).max(1);
+                for byte in ram {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    *byte = (state >> 32) as u8;
+                }
+            }
+            PowerOn::Alternating => {
+                for (i, byte) in ram.iter_mut().enumerate() {
+                    *byte = if i % 2 == 0 { 0x00 } else { 0xFF };
+                }
+            }
+        }
+    }
+}
+
+/// What happens when the CPU reads RAM nothing has initialized
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UninitMode {
+    /// Note it in `UninitChecker::reads` and keep going
+    Warn,
+    /// Also stop the CPU once the instruction is done, `Cpu::run` returns
+    /// `CpuError::UninitializedRead`
+    Stop,
+}
+
+/// A read of RAM before anything was written or loaded there
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UninitRead {
+    /// Where the instruction (or interrupt sequence) doing it started
+    pub pc: u16,
+    pub addr: u16,
+}
+
+/// Shadow bitmap of the RAM addresses that have been initialized, kept by
+/// `Bus` while `Bus::uninit` is set. CPU writes to RAM and `Bus::load` or
+/// index writes count; anything already in memory when the checker was set
+/// up doesn't, so set it up before loading a program. Only banked RAM no
+/// region answers for is checked, ROM and devices always read as
+/// initialized.
+pub struct UninitChecker {
+    pub mode: UninitMode,
+    initialized: Vec<u64>,
+    /// Addresses read during the current instruction, `Cpu::clock` picks
+    /// them up once it knows the PC to blame
+    pending: Vec<u16>,
+    reads: Vec<UninitRead>,
+    /// Addresses already in `reads`
+    reported: Vec<u64>,
+}
+
+impl UninitChecker {
+    pub fn new(mode: UninitMode) -> Self {
+        UninitChecker {
+            mode,
+            initialized: vec![0; 0x10000 / 64],
+            pending: Vec::new(),
+            reads: Vec::new(),
+            reported: vec![0; 0x10000 / 64],
+        }
+    }
+
+    pub fn initialized(&self, addr: u16) -> bool {
+        self.initialized[addr as usize / 64] & (1 << (addr % 64)) != 0
+    }
+
+    pub(crate) fn initialize(&mut self, addr: u16) {
+        self.initialized[addr as usize / 64] |= 1 << (addr % 64);
+    }
+
+    pub(crate) fn read(&mut self, addr: u16) {
+        if !self.initialized(addr) {
+            self.pending.push(addr);
+        }
+    }
+
+    /// Forgets everything, as after a power cycle
+    pub fn clear(&mut self) {
+        self.initialized.fill(0);
+        self.pending.clear();
+        self.reads.clear();
+        self.reported.fill(0);
+    }
+
+    /// Blames the reads since the last call on the instruction at `pc`,
+    /// returns the first of them
+    pub(crate) fn finish(&mut self, pc: u16) -> Option<UninitRead> {
+        let first = self.pending.first().map(|&addr| UninitRead { pc, addr });
+        for addr in self.pending.drain(..) {
+            let (word, bit) = (addr as usize / 64, 1 << (addr % 64));
+            if self.reported[word] & bit == 0 {
+                self.reported[word] |= bit;
+                self.reads.push(UninitRead { pc, addr });
+            }
+        }
+        first
+    }
+
+    /// The first uninitialized read of each address, in order. Later reads
+    /// of an address aren't kept, so a loop over uninitialized RAM doesn't
+    /// pile them up.
+    pub fn reads(&self) -> &[UninitRead] {
+        &self.reads
+    }
+}
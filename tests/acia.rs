@@ -0,0 +1,117 @@
+use cpu6502::{
+    bus::{Device, Mapping},
+    cpu::Cpu,
+    devices::{
+        acia::{self, Acia},
+        serial::{BufferHost, SerialHost, StreamHost},
+    },
+};
+use std::{cell::RefCell, io::Cursor, rc::Rc};
+
+fn tick(acia: &mut Acia, cycles: usize) {
+    for _ in 0..cycles {
+        acia.tick();
+    }
+}
+
+#[test]
+fn transmit_takes_a_character_time() {
+    let host = Rc::new(RefCell::new(BufferHost::new()));
+    let mut acia = Acia::new(host.clone());
+    // 9600 baud, 8N1: 10 bits at 1MHz
+    acia.write(acia::CONTROL, 0x1E);
+    acia.write(acia::COMMAND, 0x0B);
+
+    acia.write(acia::DATA, b'A');
+    assert_eq!(acia.read(acia::STATUS) & acia::STATUS_TDRE, 0);
+
+    tick(&mut acia, 1_000_000 / 9600 * 10 - 1);
+    assert!(host.borrow().output.is_empty());
+    tick(&mut acia, 1);
+    assert_eq!(host.borrow().output, b"A");
+    assert_ne!(acia.read(acia::STATUS) & acia::STATUS_TDRE, 0);
+}
+
+#[test]
+fn receive_interrupt_and_acknowledge() {
+    let host = Rc::new(RefCell::new(BufferHost::new()));
+    let mut acia = Acia::new(host.clone());
+    // DTR on, receiver interrupts enabled
+    acia.write(acia::COMMAND, 0x09);
+    host.borrow_mut().send(b"hi");
+
+    tick(&mut acia, 1);
+    assert!(acia.irq());
+    let status = acia.read(acia::STATUS);
+    assert_eq!(status & (acia::STATUS_IRQ | acia::STATUS_RDRF), 0x88);
+    assert!(!acia.irq());
+    assert_eq!(acia.read(acia::DATA), b'h');
+
+    tick(&mut acia, 1);
+    assert_eq!(acia.read(acia::DATA), b'i');
+}
+
+#[test]
+fn receiver_is_off_without_dtr() {
+    let host = Rc::new(RefCell::new(BufferHost::new()));
+    let mut acia = Acia::new(host.clone());
+    host.borrow_mut().send(b"x");
+
+    tick(&mut acia, 10);
+    assert_eq!(acia.read(acia::STATUS) & acia::STATUS_RDRF, 0);
+
+    acia.write(acia::COMMAND, 0x0B);
+    tick(&mut acia, 1);
+    assert_ne!(acia.read(acia::STATUS) & acia::STATUS_RDRF, 0);
+
+    // Programmed reset drops DTR again
+    acia.write(acia::STATUS, 0x00);
+    assert_eq!(acia.read(acia::COMMAND), 0x00);
+}
+
+#[test]
+fn stream_host_reads_on_a_thread() {
+    let mut host = StreamHost::new(Cursor::new(b"ok".to_vec()), Vec::new());
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        if let Some(byte) = host.receive() {
+            received.push(byte);
+        }
+    }
+    assert_eq!(received, b"ok");
+}
+
+#[test]
+fn cpu_echoes_through_the_acia() {
+    let mut cpu = Cpu::new();
+    let host = Rc::new(RefCell::new(BufferHost::new()));
+    cpu.memory.map(
+        Mapping {
+            mirror_mask: 0x0003,
+            ..Mapping::new(0x5000, 0x5003)
+        },
+        Acia::new(host.clone()),
+    );
+
+    #[rustfmt::skip]
+    let program = [
+        0xA9, 0x0B,       // LDA #$0B   DTR, no interrupts
+        0x8D, 0x02, 0x50, // STA $5002
+        0xAD, 0x01, 0x50, // LDA $5001  wait for a byte
+        0x29, 0x08,       // AND #$08
+        0xF0, 0xF9,       // BEQ -7
+        0xAD, 0x00, 0x50, // LDA $5000
+        0x49, 0x20,       // EOR #$20   swap case
+        0x8D, 0x00, 0x50, // STA $5000
+        0x4C, 0x05, 0x02, // JMP $0205
+    ];
+    cpu.memory.load(0x0200, &program);
+    cpu.pc = 0x0200;
+    host.borrow_mut().send(b"Hello");
+
+    for _ in 0..2000 {
+        cpu.clock();
+    }
+
+    assert_eq!(host.borrow().output, b"hELLO");
+}
@@ -0,0 +1,49 @@
+use cpu6502::{devices::serial::BufferHost, systems::apple1::Apple1};
+use std::{cell::RefCell, rc::Rc};
+
+fn boot() -> (Apple1, Rc<RefCell<BufferHost>>) {
+    let terminal = Rc::new(RefCell::new(BufferHost::new()));
+    let mut apple = Apple1::new(terminal.clone());
+    apple.reset();
+    apple.run(10_000);
+    (apple, terminal)
+}
+
+fn type_line(apple: &mut Apple1, terminal: &Rc<RefCell<BufferHost>>, line: &str) -> String {
+    terminal.borrow_mut().output.clear();
+    terminal.borrow_mut().send(line.as_bytes());
+    terminal.borrow_mut().send(b"\n");
+    apple.run(500_000);
+    String::from_utf8(terminal.borrow().output.clone()).unwrap()
+}
+
+#[test]
+fn wozmon_prompt_after_reset() {
+    let (_apple, terminal) = boot();
+    assert_eq!(terminal.borrow().output, b"\\\n");
+}
+
+#[test]
+fn wozmon_examines_memory() {
+    let (mut apple, terminal) = boot();
+    let output = type_line(&mut apple, &terminal, "FF00.FF0F");
+    assert_eq!(
+        output,
+        "FF00.FF0F\n\n\
+         FF00: D8 58 A0 7F 8C 12 D0 A9\n\
+         FF08: A7 8D 11 D0 8D 13 D0 C9\n"
+    );
+}
+
+#[test]
+fn wozmon_deposits_and_runs_a_program() {
+    let (mut apple, terminal) = boot();
+
+    // LDA #'A'; JSR ECHO; JMP GETLINE
+    type_line(&mut apple, &terminal, "300: A9 C1 20 EF FF 4C 1F FF");
+    assert_eq!(apple.cpu.memory[0x0300], 0xA9);
+    assert_eq!(apple.cpu.memory[0x0307], 0xFF);
+
+    let output = type_line(&mut apple, &terminal, "300r");
+    assert_eq!(output, "300R\n\n0300: A9A\n");
+}
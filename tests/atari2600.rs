@@ -0,0 +1,107 @@
+use cpu6502::{
+    bus::Device,
+    systems::atari2600::{
+        cartridge::{BankSwitching, Cartridge, CartridgeError},
+        tia::{CLOCKS_PER_CPU_CYCLE, CLOCKS_PER_SCANLINE},
+        Atari2600, SWITCH_RESET,
+    },
+};
+
+/// A 4K cartridge running `program` from $F000
+fn atari(program: &[u8]) -> Atari2600 {
+    let mut rom = vec![0xEA; 0x1000];
+    rom[..program.len()].copy_from_slice(program);
+    rom[0xFFC..].copy_from_slice(&[0x00, 0xF0, 0x00, 0xF0]);
+
+    let mut atari = Atari2600::from_rom(&rom).unwrap();
+    atari.reset();
+    atari
+}
+
+#[test]
+fn address_space_is_13_bits() {
+    let mut atari = atari(&[0xA9, 0x42]);
+    let memory = &mut atari.cpu.memory;
+
+    // RIOT RAM answers for zero page and the stack page alike
+    memory.write(0x0080, 0x42);
+    assert_eq!(memory.read(0x0180), 0x42);
+    assert_eq!(memory.read(0x2080), 0x42);
+
+    assert_eq!(memory.read(0xF000), 0xA9);
+    assert_eq!(memory.read(0x1001), 0x42);
+    assert_eq!(memory.read(0x7FFD), 0xF0);
+
+    atari.set_switches(!SWITCH_RESET);
+    assert_eq!(atari.cpu.memory.read(0x0282), !SWITCH_RESET);
+}
+
+#[test]
+fn wsync_halts_until_the_end_of_the_line() {
+    // STA WSYNC
+    let mut atari = atari(&[0x85, 0x02]);
+    atari.step();
+    let cycles = atari.step();
+
+    let tia = atari.tia.borrow();
+    assert_eq!(tia.scanline, 1);
+    assert!(tia.clock < CLOCKS_PER_CPU_CYCLE);
+    assert_eq!(
+        atari.cycles,
+        (CLOCKS_PER_SCANLINE / CLOCKS_PER_CPU_CYCLE) as u64
+    );
+    assert!(cycles > 3, "{cycles}");
+}
+
+#[test]
+fn kernel_scanlines_are_counted_per_frame() {
+    let program = [
+        0xA9, 0x02, // start: LDA #$02
+        0x85, 0x00, //        STA VSYNC
+        0xA9, 0x00, //        LDA #$00
+        0x85, 0x00, //        STA VSYNC
+        0xA2, 0x64, //        LDX #100
+        0x85, 0x02, // line:  STA WSYNC
+        0xCA, //              DEX
+        0xD0, 0xFB, //        BNE line
+        0x4C, 0x00, 0xF0, //  JMP start
+    ];
+    let mut atari = atari(&program);
+    atari.run_frame();
+    atari.run_frame();
+
+    let cycles = atari.run_frame();
+    assert_eq!(atari.tia.borrow().frame_scanlines, 100);
+    assert_eq!(cycles, 100 * 76);
+}
+
+#[test]
+fn bank_switching_follows_hotspot_accesses() {
+    // Every byte of an F8 bank is its number, vectors in both banks
+    let mut rom: Vec<u8> = (0..2).flat_map(|bank| vec![bank; 0x1000]).collect();
+    for bank in 0..2 {
+        rom[bank * 0x1000 + 0xFFC..bank * 0x1000 + 0xFFE].copy_from_slice(&[0x00, 0xF0]);
+    }
+    let mut atari = Atari2600::from_rom(&rom).unwrap();
+    atari.reset();
+    assert_eq!(atari.cartridge.borrow().bank_switching, BankSwitching::F8);
+
+    let memory = &mut atari.cpu.memory;
+    assert_eq!(memory.read(0xF000), 1);
+    memory.read(0x1FF8);
+    assert_eq!(memory.read(0xF000), 0);
+    memory.write(0xFFF9, 0);
+    assert_eq!(memory.read(0xF000), 1);
+
+    let rom: Vec<u8> = (0..8).flat_map(|bank| vec![bank; 0x1000]).collect();
+    let mut cartridge = Cartridge::new(&rom).unwrap();
+    assert_eq!(cartridge.bank_switching, BankSwitching::F4);
+    assert_eq!(cartridge.bank, 7);
+    cartridge.read(0xFF4 + 5);
+    assert_eq!(cartridge.read(0x000), 5);
+
+    assert_eq!(
+        Cartridge::new(&[0; 0x3000]).err(),
+        Some(CartridgeError::UnsupportedSize(0x3000))
+    );
+}
@@ -0,0 +1,117 @@
+use cpu6502::systems::nes::Nes;
+use std::{env, fs, path::PathBuf};
+
+// Blargg's NES test ROMs report through PRG RAM: $6000 holds the status,
+// $6001-$6003 a signature once that's valid, and text starts at $6004.
+// See https://github.com/christopherpow/nes-test-roms
+
+const STATUS: u16 = 0x6000;
+const SIGNATURE: [u8; 3] = [0xDE, 0xB0, 0x61];
+const TEXT: u16 = 0x6004;
+
+const RUNNING: u8 = 0x80;
+const RESET_REQUESTED: u8 = 0x81;
+
+/// Gives up after a minute of emulated time
+const MAX_FRAMES: u32 = 3600;
+/// Frames to wait before pressing RESET when asked to (at least 100ms)
+const RESET_DELAY_FRAMES: u32 = 10;
+
+/// The ROMs come from $BLARGG_ROMS or roms/nes/blargg
+fn rom_dir() -> PathBuf {
+    env::var_os("BLARGG_ROMS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("roms/nes/blargg"))
+}
+
+fn has_signature(nes: &mut Nes) -> bool {
+    (0..3).all(|i| nes.cpu.memory.read(STATUS + 1 + i) == SIGNATURE[i as usize])
+}
+
+fn text(nes: &mut Nes) -> String {
+    let mut text = String::new();
+    for addr in TEXT..0x8000 {
+        let byte = nes.cpu.memory.read(addr);
+        if byte == 0 {
+            break;
+        }
+        text.push(byte as char);
+    }
+    text.trim().to_string()
+}
+
+/// Runs a ROM until it reports a result. Returns `None` when it passed,
+/// otherwise what went wrong, a missing ROM included.
+fn run(rom: &str) -> Option<String> {
+    let path = rom_dir().join(rom);
+    let image = match fs::read(&path) {
+        Ok(image) => image,
+        Err(err) => return Some(format!("{}: {err}", path.display())),
+    };
+    let mut nes = match Nes::from_ines(&image) {
+        Ok(nes) => nes,
+        Err(err) => return Some(format!("{rom}: {err}")),
+    };
+    nes.reset();
+
+    let mut reset_in = None;
+    for _ in 0..MAX_FRAMES {
+        nes.run_frame();
+
+        if let Some(frames) = reset_in {
+            if frames == 0 {
+                nes.reset();
+                reset_in = None;
+            } else {
+                reset_in = Some(frames - 1);
+            }
+            continue;
+        }
+
+        if !has_signature(&mut nes) {
+            continue;
+        }
+        match nes.cpu.memory.read(STATUS) {
+            RUNNING => {}
+            RESET_REQUESTED => reset_in = Some(RESET_DELAY_FRAMES),
+            0 => return None,
+            code => return Some(format!("{rom}: failed with {code}\n{}", text(&mut nes))),
+        }
+    }
+    Some(format!(
+        "{rom}: no result after {MAX_FRAMES} frames\n{}",
+        text(&mut nes)
+    ))
+}
+
+fn run_all(roms: &[&str]) {
+    let failures: Vec<String> = roms.iter().filter_map(|rom| run(rom)).collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n\n"));
+}
+
+#[test]
+#[ignore = "needs blargg's ROMs in $BLARGG_ROMS or roms/nes/blargg"]
+fn instr_test() {
+    run_all(&["instr_test-v5/official_only.nes"]);
+}
+
+#[test]
+#[ignore = "needs blargg's ROMs in $BLARGG_ROMS or roms/nes/blargg"]
+fn instr_timing() {
+    run_all(&[
+        "instr_timing/rom_singles/1-instr_timing.nes",
+        "instr_timing/rom_singles/2-branch_timing.nes",
+    ]);
+}
+
+#[test]
+#[ignore = "needs blargg's ROMs in $BLARGG_ROMS or roms/nes/blargg"]
+fn cpu_interrupts() {
+    run_all(&[
+        "cpu_interrupts_v2/rom_singles/1-cli_latency.nes",
+        "cpu_interrupts_v2/rom_singles/2-nmi_and_brk.nes",
+        "cpu_interrupts_v2/rom_singles/3-nmi_and_irq.nes",
+        "cpu_interrupts_v2/rom_singles/4-irq_and_dma.nes",
+        "cpu_interrupts_v2/rom_singles/5-branch_delays_irq.nes",
+    ]);
+}
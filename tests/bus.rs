@@ -0,0 +1,207 @@
+use cpu6502::{
+    banking::Banks,
+    bus::{Bus, Mapping},
+    cpu::Cpu,
+    opcodes::Variant,
+};
+use std::{cell::RefCell, rc::Rc};
+
+#[test]
+fn unmapped_addresses_are_ram() {
+    let mut bus = Bus::new();
+    bus.write(0x1234, 0xAB);
+    assert_eq!(bus.read(0x1234), 0xAB);
+    assert_eq!(bus[0x1234], 0xAB);
+}
+
+#[test]
+fn handler_sees_mirrored_offsets() {
+    let mut bus = Bus::new();
+    let writes = Rc::new(RefCell::new(Vec::new()));
+    let log = writes.clone();
+
+    bus.map_handler(
+        Mapping {
+            mirror_mask: 0x0007,
+            ..Mapping::new(0x2000, 0x3FFF)
+        },
+        |addr| addr as u8,
+        move |addr, value| log.borrow_mut().push((addr, value)),
+    );
+
+    assert_eq!(bus.read(0x2002), 0x02);
+    assert_eq!(bus.read(0x3FFA), 0x02);
+    bus.write(0x200F, 0x55);
+    assert_eq!(*writes.borrow(), vec![(0x0007, 0x55)]);
+
+    // RAM underneath is untouched
+    assert_eq!(bus[0x200F], 0x00);
+}
+
+#[test]
+fn higher_priority_region_wins() {
+    let mut bus = Bus::new();
+    bus.map_handler(Mapping::new(0xD000, 0xDFFF), |_| 0x11, |_, _| {});
+    bus.map_handler(
+        Mapping {
+            priority: 1,
+            ..Mapping::new(0xD400, 0xD4FF)
+        },
+        |_| 0x22,
+        |_, _| {},
+    );
+    // Registered last but with a lower priority
+    bus.map_handler(
+        Mapping {
+            priority: -1,
+            ..Mapping::new(0xD000, 0xDFFF)
+        },
+        |_| 0x33,
+        |_, _| {},
+    );
+
+    assert_eq!(bus.read(0xD000), 0x11);
+    assert_eq!(bus.read(0xD410), 0x22);
+
+    // Same priority: the newest region is on top
+    bus.map_handler(Mapping::new(0xD800, 0xD8FF), |_| 0x44, |_, _| {});
+    assert_eq!(bus.read(0xD800), 0x44);
+}
+
+#[test]
+fn rom_ignores_writes_and_unmap_restores_ram() {
+    let mut bus = Bus::new();
+    bus[0xFFFC] = 0x99;
+    let rom = bus.map_rom(0xFFFC, &[0x00, 0x80, 0x00, 0x80]);
+
+    bus.write(0xFFFC, 0x12);
+    assert_eq!(bus.read(0xFFFC), 0x00);
+    assert_eq!(bus.read(0xFFFD), 0x80);
+
+    assert!(bus.unmap(rom));
+    assert!(!bus.unmap(rom));
+    assert_eq!(bus.read(0xFFFC), 0x99);
+}
+
+#[test]
+fn peek_reads_rom_and_leaves_devices_alone() {
+    let mut bus = Bus::new();
+    bus[0x2000] = 0x99;
+    let reads = Rc::new(RefCell::new(0));
+    let count = reads.clone();
+    bus.map_handler(
+        Mapping::new(0x2000, 0x20FF),
+        move |_| {
+            *count.borrow_mut() += 1;
+            0x11
+        },
+        |_, _| {},
+    );
+    bus.map_rom(0xF000, &[0xA9, 0x42]);
+
+    assert_eq!(bus.peek(0xF001), 0x42);
+    // A handler can't peek, so the RAM underneath shows
+    assert_eq!(bus.peek(0x2000), 0x99);
+    assert_eq!(*reads.borrow(), 0);
+    assert_eq!(bus.disassemble(0xF000, 1, Variant::Nmos6502)[0].to_string(), "LDA #$42");
+}
+
+#[test]
+fn cpu_accesses_go_through_the_bus() {
+    let mut cpu = Cpu::new();
+    let port = Rc::new(RefCell::new(0u8));
+    let reg = port.clone();
+    cpu.memory.map_handler(
+        Mapping::new(0xD010, 0xD013),
+        |_| 0x7F,
+        move |_, value| *reg.borrow_mut() = value,
+    );
+
+    // LDA $D010; STA $D012
+    cpu.memory.load(0x0200, &[0xAD, 0x10, 0xD0, 0x8D, 0x12, 0xD0]);
+    cpu.pc = 0x0200;
+    for _ in 0..8 {
+        cpu.clock();
+    }
+
+    assert_eq!(cpu.a, 0x7F);
+    assert_eq!(*port.borrow(), 0x7F);
+}
+
+#[test]
+fn bank_switch_register_remaps_pages() {
+    let mut bus = Bus::new();
+    let bank0 = bus.banks.add_rom(&[0xA0; 0x4000]);
+    let bank1 = bus.banks.add_rom(&[0xA1; 0x4000]);
+    assert_eq!(bus.banks.bank_pages(bank1), 0x40);
+
+    bus.banks.map(0x80, 0x40, bank0, 0);
+    bus.map_bank_switch(
+        Mapping::new(0x8000, 0xFFFF),
+        move |banks: &mut Banks, _addr: u16, value: u8| {
+            let bank = if value & 1 == 0 { bank0 } else { bank1 };
+            banks.map(0x80, 0x40, bank, 0);
+        },
+    );
+
+    assert_eq!(bus.read(0x8000), 0xA0);
+    assert_eq!(bus.read(0xBFFF), 0xA0);
+
+    // Writes hit the control register, reads fall through to the bank
+    bus.write(0x8000, 0x01);
+    assert_eq!(bus.read(0x8000), 0xA1);
+    assert_eq!(bus.peek(0x9234), 0xA1);
+
+    // Base RAM is still behind it
+    assert_eq!(bus[0x8000], 0x00);
+}
+
+#[test]
+fn rom_bank_reads_with_writes_to_ram_underneath() {
+    let mut bus = Bus::new();
+    let kernal = bus.banks.add_rom(&[0xEA; 0x2000]);
+    bus.banks.map_read(0xE0, 0x20, kernal, 0);
+
+    bus.write(0xE000, 0x42);
+    assert_eq!(bus.read(0xE000), 0xEA);
+    assert_eq!(bus[0xE000], 0x42);
+
+    bus.banks.unmap(0xE0, 0x20);
+    assert_eq!(bus.read(0xE000), 0x42);
+}
+
+#[test]
+fn writes_to_rom_mapped_for_writing_are_ignored() {
+    let mut bus = Bus::new();
+    let rom = bus.banks.add_rom(&[0x11; 0x100]);
+    let ram = bus.banks.add_ram(0x200);
+    bus.banks.map(0x40, 1, rom, 0);
+    bus.banks.map(0x41, 2, ram, 0);
+
+    bus.write(0x4000, 0x99);
+    bus.write(0x4180, 0x77);
+    assert_eq!(bus.read(0x4000), 0x11);
+    assert_eq!(bus.read(0x4180), 0x77);
+    assert_eq!(bus.banks.bank(ram)[0x80], 0x77);
+    assert_eq!(bus[0x4000], 0x00);
+}
+
+#[test]
+fn pokes_only_change_their_own_page_version() {
+    let mut bus = Bus::new();
+    let ram = bus.banks.add_ram(0x200);
+    bus.banks.map(0x40, 2, ram, 0);
+    let versions = |bus: &Bus| [0x0400, 0x1000, 0x4000, 0x4100].map(|a| bus.page_version(a));
+
+    let before = versions(&bus);
+    bus[0x0410] = 0x12;
+    bus.load(0x0FFF, &[0x34, 0x56]);
+    *bus.banks.byte_mut(ram, 0x180) = 0x78;
+    let after = versions(&bus);
+
+    assert_ne!(before[0], after[0]);
+    assert_ne!(before[1], after[1]);
+    assert_eq!(before[2], after[2]);
+    assert_ne!(before[3], after[3]);
+    assert_eq!(bus.read(0x4180), 0x78);
+}
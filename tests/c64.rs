@@ -0,0 +1,155 @@
+use cpu6502::{assembler::assemble, systems::c64::C64};
+use std::{env, path::PathBuf};
+
+/// Stand-in ROMs filled with a marker byte, the KERNAL spins at $E000
+fn c64() -> C64 {
+    let mut kernal = vec![0xEE; 0x2000];
+    kernal[..3].copy_from_slice(&[0x4C, 0x00, 0xE0]);
+    kernal[0x1FFC] = 0x00;
+    kernal[0x1FFD] = 0xE0;
+
+    let mut c64 = C64::new(&[0xBA; 0x2000], &kernal, &[0xCC; 0x1000]);
+    c64.reset();
+    c64
+}
+
+fn set_port(c64: &mut C64, value: u8) {
+    c64.cpu.memory.write(0x0000, 0x2F);
+    c64.cpu.memory.write(0x0001, value);
+}
+
+#[test]
+fn processor_port_banks_roms_and_io() {
+    let mut c64 = c64();
+    let memory = &mut c64.cpu.memory;
+    memory.write(0xA000, 0x11);
+    memory.write(0xE003, 0x22);
+    memory.write(0xD800, 0x05);
+
+    // Power-on configuration, writes went to the RAM underneath
+    assert_eq!(memory.read(0xA000), 0xBA);
+    assert_eq!(memory.read(0xE003), 0xEE);
+    assert_eq!(memory.read(0xD800), 0x05);
+
+    set_port(&mut c64, 0x36);
+    assert_eq!(c64.cpu.memory.read(0xA000), 0x11);
+    assert_eq!(c64.cpu.memory.read(0xE003), 0xEE);
+
+    set_port(&mut c64, 0x33);
+    assert_eq!(c64.cpu.memory.read(0xA000), 0xBA);
+    assert_eq!(c64.cpu.memory.read(0xD800), 0xCC);
+
+    set_port(&mut c64, 0x30);
+    assert_eq!(c64.cpu.memory.read(0xE003), 0x22);
+    assert_eq!(c64.cpu.memory.read(0xD800), 0x00);
+
+    // Colour RAM kept its contents while banked out
+    set_port(&mut c64, 0x37);
+    assert_eq!(c64.cpu.memory.read(0xD800), 0x05);
+    assert_eq!(c64.cpu.memory.read(0x0001), 0x37);
+}
+
+#[test]
+fn typed_text_goes_through_the_keyboard_buffer() {
+    let mut c64 = c64();
+    c64.type_text("run\n");
+    c64.step();
+
+    assert_eq!(c64.cpu.memory[0x00C6], 4);
+    assert_eq!(&c64.cpu.memory.banks.bank(0)[0x0277..0x027B], b"RUN\r");
+}
+
+#[test]
+fn screen_ram_reads_back_as_text() {
+    let mut c64 = c64();
+    c64.cpu.memory.load(0x0400, &[0x20; 1000]);
+    // "READY." with the cursor (a reverse space) underneath
+    c64.cpu
+        .memory
+        .load(0x0400, &[0x12, 0x05, 0x01, 0x04, 0x19, 0x2E]);
+    c64.cpu.memory.load(0x0428, &[0xA0]);
+
+    let text = c64.screen_text();
+    let lines: Vec<&str> = text.split('\n').collect();
+    assert_eq!(lines.len(), 25);
+    assert_eq!(lines[0], "READY.");
+    assert_eq!(lines[1], "");
+}
+
+/// A stand-in KERNAL that banks BASIC out and back in through the port
+/// and echoes the keyboard buffer to the screen, like BASIC's input loop
+const ECHO: &str = "
+        ldx #$37
+        stx $01
+        ldx #$2F
+        stx $00         ; port lines to outputs
+        ldx #$36
+        stx $01         ; BASIC out
+        lda $A000
+        sta $0450
+        ldx #$37
+        stx $01         ; and back in
+        lda $A000
+        sta $0451
+        ldy #0
+wait:   lda $C6
+        beq wait
+        ldx #0
+copy:   lda $0277,x
+        and #$3F        ; ASCII to screen code
+        sta $0400,y
+        iny
+        inx
+        cpx $C6
+        bne copy
+        lda #0
+        sta $C6
+        jmp wait
+";
+
+#[test]
+fn kernal_code_banks_and_reads_typed_keys() {
+    let mut kernal = vec![0xEE; 0x2000];
+    let code = assemble(ECHO, 0xE000).unwrap();
+    kernal[..code.len()].copy_from_slice(&code);
+    kernal[0x1FFC] = 0x00;
+    kernal[0x1FFD] = 0xE0;
+    let mut c64 = C64::new(&[0xBA; 0x2000], &kernal, &[0xCC; 0x1000]);
+    c64.cpu.memory.load(0x0400, &[0x20; 1000]);
+    c64.cpu.memory[0xA000] = 0x11;
+    c64.reset();
+
+    // Longer than the 10 key buffer, so it goes in two helpings
+    c64.type_text("hello, 6510 world!");
+    c64.run(20_000);
+
+    assert_eq!(c64.cpu.memory[0x0450], 0x11);
+    assert_eq!(c64.cpu.memory[0x0451], 0xBA);
+    assert_eq!(c64.cpu.memory[0x00C6], 0);
+    assert_eq!(c64.screen_text().lines().next(), Some("HELLO, 6510 WORLD!"));
+}
+
+#[test]
+#[ignore = "needs the C64 ROMs (basic, kernal, chargen) in $C64_ROMS or roms/c64"]
+fn boots_to_basic_ready() {
+    let dir = env::var_os("C64_ROMS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("roms/c64"));
+    let mut c64 = C64::from_dir(&dir)
+        .unwrap_or_else(|err| panic!("no C64 ROMs in {}: {err}", dir.display()));
+    c64.reset();
+
+    for _ in 0..50 {
+        c64.run(100_000);
+        if c64.screen_text().contains("READY.") {
+            break;
+        }
+    }
+    let screen = c64.screen_text();
+    assert!(screen.contains("BASIC BYTES FREE"), "{screen}");
+    assert!(screen.contains("READY."), "{screen}");
+
+    c64.type_text("print 6*7\n");
+    c64.run(200_000);
+    assert!(c64.screen_text().contains(" 42"), "{}", c64.screen_text());
+}
@@ -0,0 +1,193 @@
+use cpu6502::{
+    bus::{Device, Line, Mapping},
+    cpu::Cpu,
+    devices::cia::{self, Cia, Tod},
+};
+use std::{cell::RefCell, rc::Rc};
+
+fn tick(cia: &mut Cia, cycles: usize) {
+    for _ in 0..cycles {
+        cia.tick();
+    }
+}
+
+#[test]
+fn timer_a_one_shot_underflow() {
+    let mut cia = Cia::new();
+    cia.write(cia::ICR, 0x81);
+    cia.write(cia::TA_LO, 9);
+    cia.write(cia::TA_HI, 0);
+    cia.write(cia::CRA, 0x09);
+
+    // Period is latch + 1
+    tick(&mut cia, 9);
+    assert!(!cia.interrupt_asserted());
+    tick(&mut cia, 1);
+    assert!(cia.interrupt_asserted());
+
+    // Stopped and reloaded
+    assert_eq!(cia.read(cia::CRA) & 0x01, 0);
+    assert_eq!(cia.read(cia::TA_LO), 9);
+
+    // Reading ICR acknowledges
+    assert_eq!(cia.read(cia::ICR), 0x81);
+    assert!(!cia.interrupt_asserted());
+    assert_eq!(cia.read(cia::ICR), 0x00);
+}
+
+#[test]
+fn timer_b_counts_timer_a_underflows() {
+    let mut cia = Cia::new();
+    cia.write(cia::TA_LO, 1);
+    cia.write(cia::TA_HI, 0);
+    cia.write(cia::TB_LO, 2);
+    cia.write(cia::TB_HI, 0);
+    cia.write(cia::CRB, 0x41);
+    cia.write(cia::CRA, 0x01);
+
+    // Timer A underflows every 2 cycles, timer B after 3 of those
+    tick(&mut cia, 5);
+    assert_eq!(cia.icr_flags & 0x02, 0);
+    tick(&mut cia, 1);
+    assert_eq!(cia.icr_flags & 0x02, 0x02);
+}
+
+#[test]
+fn tod_counts_latches_and_alarms() {
+    let mut cia = Cia::new();
+    cia.tod_pin_period = 0;
+    // 50Hz
+    cia.write(cia::CRA, 0x80);
+
+    // Set the alarm to 1:00:01.0
+    cia.write(cia::CRB, 0x80);
+    cia.write(cia::TOD_HR, 0x01);
+    cia.write(cia::TOD_MIN, 0x00);
+    cia.write(cia::TOD_SEC, 0x01);
+    cia.write(cia::TOD_10THS, 0x00);
+    cia.write(cia::CRB, 0x00);
+    cia.write(cia::ICR, 0x84);
+
+    // Writing the hours stops the clock until the tenths are written
+    cia.write(cia::TOD_HR, 0x01);
+    for _ in 0..50 {
+        cia.pulse_tod();
+    }
+    assert_eq!(cia.tod.tenths, 0);
+    cia.write(cia::TOD_10THS, 0x00);
+
+    for _ in 0..45 {
+        cia.pulse_tod();
+    }
+    assert_eq!(cia.read(cia::TOD_HR), 0x01);
+    for _ in 0..5 {
+        cia.pulse_tod();
+    }
+    // Still latched from the hours read
+    assert_eq!(cia.read(cia::TOD_SEC), 0x00);
+    assert_eq!(cia.read(cia::TOD_10THS), 0x09);
+    assert_eq!(cia.read(cia::TOD_SEC), 0x01);
+    assert!(cia.interrupt_asserted());
+}
+
+#[test]
+fn tod_rolls_over_to_pm() {
+    let mut cia = Cia::new();
+    cia.tod = Tod {
+        tenths: 9,
+        seconds: 0x59,
+        minutes: 0x59,
+        hours: 0x11,
+    };
+    for _ in 0..6 {
+        cia.pulse_tod();
+    }
+    assert_eq!(
+        cia.tod,
+        Tod {
+            tenths: 0,
+            seconds: 0,
+            minutes: 0,
+            hours: 0x92,
+        }
+    );
+}
+
+#[test]
+fn keyboard_matrix_scan() {
+    let mut cia = Cia::new();
+    cia.write(cia::DDRA, 0xFF);
+    cia.write(cia::DDRB, 0x00);
+    // Column 1, row 5
+    cia.set_key(1, 5, true);
+
+    cia.write(cia::PRA, 0xFE);
+    assert_eq!(cia.read(cia::PRB), 0xFF);
+    cia.write(cia::PRA, 0xFD);
+    assert_eq!(cia.read(cia::PRB), 0xDF);
+    cia.write(cia::PRA, 0x00);
+    assert_eq!(cia.read(cia::PRB), 0xDF);
+
+    cia.set_key(1, 5, false);
+    assert_eq!(cia.read(cia::PRB), 0xFF);
+}
+
+#[test]
+fn serial_port_shifts_in_on_cnt() {
+    let mut cia = Cia::new();
+    cia.write(cia::ICR, 0x88);
+    for bit in [1, 0, 1, 1, 0, 0, 1, 0] {
+        cia.set_sp(bit == 1);
+        cia.set_cnt(false);
+        cia.set_cnt(true);
+    }
+    assert_eq!(cia.read(cia::SDR), 0b1011_0010);
+    assert!(cia.interrupt_asserted());
+}
+
+#[test]
+fn cia_wired_to_nmi() {
+    let mut cpu = Cpu::new();
+    let cia = Rc::new(RefCell::new(Cia::new()));
+    let id = cpu.memory.map(
+        Mapping {
+            mirror_mask: 0x000F,
+            ..Mapping::new(0xDD00, 0xDDFF)
+        },
+        cia.clone(),
+    );
+    cpu.memory.connect(id, Line::Nmi);
+
+    #[rustfmt::skip]
+    let program = [
+        0x78,             // SEI        NMI ignores the I flag
+        0xA9, 0x81,       // LDA #$81
+        0x8D, 0x0D, 0xDD, // STA $DD0D
+        0xA9, 0x10,       // LDA #$10
+        0x8D, 0x04, 0xDD, // STA $DD04
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x05, 0xDD, // STA $DD05
+        0xA9, 0x11,       // LDA #$11   force load and start, continuous
+        0x8D, 0x0E, 0xDD, // STA $DD0E
+        0x4C, 0x15, 0x02, // JMP *
+    ];
+    #[rustfmt::skip]
+    let handler = [
+        0xAD, 0x0D, 0xDD, // LDA $DD0D  acknowledge
+        0xE6, 0x00,       // INC $00
+        0x40,             // RTI
+    ];
+    cpu.memory.load(0x0200, &program);
+    cpu.memory.load(0x0300, &handler);
+    cpu.memory[0xFFFA] = 0x00;
+    cpu.memory[0xFFFB] = 0x03;
+    cpu.pc = 0x0200;
+
+    for _ in 0..300 {
+        cpu.clock();
+    }
+
+    // The timer period is 17 cycles, each underflow is a separate edge
+    let count = cpu.memory[0x0000];
+    assert!(count >= 10, "only {} NMIs", count);
+}
@@ -0,0 +1,169 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::{Cpu, Flag},
+    opcodes::Variant,
+};
+
+/// Runs `opcode #m` with A, the carry and D set up, returns A and P
+fn run(variant: Variant, opcode: u8, a: u8, m: u8, carry: bool) -> (u8, u8) {
+    let mut cpu = Cpu::new();
+    cpu.variant = variant;
+    cpu.memory.load(0x0200, &[opcode, m]);
+    cpu.pc = 0x0200;
+    cpu.a = a;
+    cpu.set_flag(Flag::Decimal, true);
+    cpu.set_flag(Flag::Carry, carry);
+    cpu.step();
+    (cpu.a, cpu.status)
+}
+
+/// Packs 0 to 99 as two BCD digits
+fn bcd(n: u8) -> u8 {
+    ((n / 10) << 4) | (n % 10)
+}
+
+const C: u8 = 1 << Flag::Carry as u8;
+const Z: u8 = 1 << Flag::Zero as u8;
+const V: u8 = 1 << Flag::Overflow as u8;
+const N: u8 = 1 << Flag::Negative as u8;
+
+#[test]
+fn adc_and_sbc_add_and_subtract_bcd() {
+    for x in 0..100 {
+        for y in 0..100 {
+            for carry in [false, true] {
+                let (a, m) = (bcd(x), bcd(y));
+
+                let sum = x as u16 + y as u16 + carry as u16;
+                let (res, p) = run(Variant::Nmos6502, 0x69, a, m, carry);
+                assert_eq!(res, bcd((sum % 100) as u8), "{a:02X} + {m:02X} + {carry}");
+                assert_eq!(p & C != 0, sum > 99, "{a:02X} + {m:02X} + {carry}");
+
+                let diff = x as i16 - y as i16 - !carry as i16;
+                let (res, p) = run(Variant::Nmos6502, 0xE9, a, m, carry);
+                assert_eq!(
+                    res,
+                    bcd(diff.rem_euclid(100) as u8),
+                    "{a:02X} - {m:02X} - {}",
+                    !carry
+                );
+                assert_eq!(p & C != 0, diff >= 0, "{a:02X} - {m:02X} - {}", !carry);
+            }
+        }
+    }
+}
+
+#[test]
+fn nmos_flags_come_from_the_unadjusted_sums() {
+    // A, operand, carry in: A and the N, V, Z, C flags out
+    let adc = [
+        // Z from the binary sum ($9A), N from $A0 before the fix up
+        (0x99, 0x01, false, 0x00, N | C),
+        // V from $80 before the fix up
+        (0x79, 0x00, true, 0x80, N | V),
+        (0x00, 0x00, false, 0x00, Z),
+        // Not BCD: each digit is still fixed up on its own
+        (0x0F, 0x0F, false, 0x14, 0),
+        (0xFF, 0xFF, true, 0x55, N | C),
+    ];
+    for (a, m, carry, res, flags) in adc {
+        let (out, p) = run(Variant::Nmos6502, 0x69, a, m, carry);
+        assert_eq!(
+            (out, p & (N | V | Z | C)),
+            (res, flags),
+            "{a:02X} + {m:02X}"
+        );
+    }
+
+    // SBC keeps every binary flag
+    let sbc = [
+        (0x00, 0x01, true, 0x99, N),
+        (0x10, 0x10, true, 0x00, Z | C),
+        (0x80, 0x01, true, 0x79, V | C),
+    ];
+    for (a, m, carry, res, flags) in sbc {
+        let (out, p) = run(Variant::Nmos6502, 0xE9, a, m, carry);
+        assert_eq!(
+            (out, p & (N | V | Z | C)),
+            (res, flags),
+            "{a:02X} - {m:02X}"
+        );
+    }
+}
+
+#[test]
+fn adc_and_sbc_use_bcd_unless_the_variant_lacks_it() {
+    let program = "
+        sed
+        clc
+        lda #$09
+        adc #$01
+        sta $10
+        sec
+        lda #$10
+        sbc #$01
+        sta $11
+        clc
+        lda #$99
+        adc #$01
+    ";
+    let run = |variant| {
+        let mut cpu = Cpu::new();
+        cpu.variant = variant;
+        let code = assemble(program, 0x0200).unwrap();
+        cpu.memory.load(0x0200, &code);
+        cpu.pc = 0x0200;
+        while cpu.pc < 0x0200 + code.len() as u16 {
+            cpu.step();
+        }
+        cpu
+    };
+
+    let cpu = run(Variant::Nmos6502);
+    assert_eq!(
+        (cpu.memory[0x10], cpu.memory[0x11], cpu.a),
+        (0x10, 0x09, 0x00)
+    );
+    assert!(cpu.get_flag(Flag::Carry));
+    // Z follows the binary sum ($9A) on NMOS parts
+    assert!(!cpu.get_flag(Flag::Zero));
+
+    let cpu = run(Variant::Ricoh2A03);
+    assert_eq!(
+        (cpu.memory[0x10], cpu.memory[0x11], cpu.a),
+        (0x0A, 0x0F, 0x9A)
+    );
+    assert!(!cpu.get_flag(Flag::Carry));
+}
+
+#[test]
+fn combined_adc_and_sbc_opcodes_honour_decimal_mode() {
+    // SED; CLC; LDA #$09; RRA $10; SEC; LDA #$10; ISC $11; CLC; LDA #$FF; ARR #$99
+    let program = [
+        0xF8, 0x18, 0xA9, 0x09, 0x67, 0x10, 0x38, 0xA9, 0x10, 0xE7, 0x11, 0x18, 0xA9, 0xFF, 0x6B,
+        0x99,
+    ];
+    let mut cpu = Cpu::new();
+    cpu.memory.load(0x0200, &program);
+    cpu.pc = 0x0200;
+    cpu.memory[0x10] = 0x02;
+    cpu.memory[0x11] = 0x00;
+
+    for _ in 0..4 {
+        cpu.step();
+    }
+    assert_eq!((cpu.memory[0x10], cpu.a), (0x01, 0x10));
+
+    for _ in 0..3 {
+        cpu.step();
+    }
+    assert_eq!((cpu.memory[0x11], cpu.a), (0x01, 0x09));
+
+    for _ in 0..3 {
+        cpu.step();
+    }
+    assert_eq!(cpu.a, 0xA2);
+    assert!(cpu.get_flag(Flag::Carry));
+    assert!(cpu.get_flag(Flag::Overflow));
+    assert!(!cpu.get_flag(Flag::Negative));
+}
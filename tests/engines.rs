@@ -0,0 +1,280 @@
+use cpu6502::{
+    assembler::assemble,
+    banking::{BankSwitch, Banks},
+    bus::Mapping,
+    cpu::{Cpu, Engine},
+    heatmap::{Heat, Heatmap},
+    profiler::Profiler,
+    uninit::{UninitChecker, UninitMode},
+};
+
+/// Small LCG so every run sees the same "random" state
+struct Rng(u32);
+
+impl Rng {
+    fn next(&mut self) -> u8 {
+        self.0 = self.0.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+        (self.0 >> 16) as u8
+    }
+}
+
+fn cpu(engine: Engine, seed: u32, opcode: u8) -> Cpu {
+    let mut rng = Rng(seed);
+    let mut cpu = Cpu::new();
+    cpu.engine = engine;
+    for addr in 0..0x10000 {
+        cpu.memory[addr] = rng.next();
+    }
+    cpu.a = rng.next();
+    cpu.x = rng.next();
+    cpu.y = rng.next();
+    cpu.sp = rng.next();
+    cpu.status = rng.next() | 0x20;
+    cpu.pc = 0x0400;
+    cpu.memory[0x0400] = opcode;
+    cpu
+}
+
+#[test]
+fn cached_engine_agrees_with_the_table() {
+    for opcode in 0..=0xFF {
+        for seed in 0..8 {
+            let mut table = cpu(Engine::Table, seed, opcode);
+            let mut cached = cpu(Engine::Cached, seed, opcode);
+
+            let cycles = (table.step(), cached.step());
+            let registers = |cpu: &Cpu| (cpu.pc, cpu.sp, cpu.a, cpu.x, cpu.y, cpu.status);
+            assert_eq!(cycles.0, cycles.1, "${opcode:02X} cycles, seed {seed}");
+            assert_eq!(
+                registers(&table),
+                registers(&cached),
+                "${opcode:02X} registers, seed {seed}"
+            );
+            let ram = |cpu: &Cpu| cpu.memory.banks.bank(0).to_vec();
+            assert!(
+                ram(&table) == ram(&cached),
+                "${opcode:02X} memory, seed {seed}"
+            );
+        }
+    }
+}
+
+const PROGRAM: &str = "
+start:  cli
+        ldx #0
+fill:   txa
+        sta $0300,x
+        inx
+        cpx #$20        ; poked to $40 halfway through
+        bne fill
+        ldy #0
+sum:    lda $0300,y
+        clc
+        adc $20
+        sta $20
+        iny
+        bne sum
+        jsr patch
+        jsr $0600       ; a different bank later on
+        jmp start
+
+patch:  inc addr        ; the LDA below, in the same block
+        .byte $AD
+addr:   .word $0300
+        sta $23
+        rts
+
+irq:    inc $24
+        rti
+";
+
+/// Runs the table and cached engines a clock at a time through self
+/// modifying code, interrupts, a bank switch and a poke from outside,
+/// comparing them after every clock
+#[test]
+fn cached_engine_runs_in_lock_step() {
+    let code = assemble(PROGRAM, 0x0400).unwrap();
+    let irq = 0x0400 + code.len() as u16 - 3;
+    let machine = |engine| {
+        let mut cpu = Cpu::new();
+        cpu.engine = engine;
+        cpu.memory.load(0x0400, &code);
+        cpu.memory.load(0xFFFE, &irq.to_le_bytes());
+        let increment = cpu.memory.banks.add_rom(&[0xE6, 0x25, 0x60]);
+        let decrement = cpu.memory.banks.add_rom(&[0xC6, 0x25, 0x60]);
+        cpu.memory.banks.map_read(0x06, 1, increment, 0);
+        cpu.pc = 0x0400;
+        (cpu, decrement)
+    };
+    let (mut table, decrement) = machine(Engine::Table);
+    let (mut cached, _) = machine(Engine::Cached);
+
+    let registers = |cpu: &Cpu| {
+        (
+            cpu.pc, cpu.sp, cpu.a, cpu.x, cpu.y, cpu.status, cpu.cycles, cpu.opcode,
+        )
+    };
+    for clock in 0..200_000 {
+        for cpu in [&mut table, &mut cached] {
+            match clock % 5000 {
+                1000 => cpu.set_irq(true),
+                1010 => cpu.set_irq(false),
+                _ => {}
+            }
+            match clock {
+                60_000 => cpu.memory.banks.map_read(0x06, 1, decrement, 0),
+                120_000 => cpu.memory[0x0409] = 0x40,
+                _ => {}
+            }
+            cpu.clock();
+        }
+        assert_eq!(registers(&table), registers(&cached), "clock {clock}");
+    }
+
+    let ram = |cpu: &Cpu| cpu.memory.banks.bank(0).to_vec();
+    assert!(ram(&table) == ram(&cached));
+    // Everything ran: the interrupt, both banks, the patched operand
+    assert!(table.memory[0x24] > 0 && table.memory[0x23] > 0);
+    assert!(cached.cached_blocks() > 0);
+}
+
+/// Answers reads of its registers, like the C64 processor port
+struct Port(u8);
+
+impl BankSwitch for Port {
+    fn write(&mut self, _banks: &mut Banks, _addr: u16, value: u8) {
+        self.0 = value;
+    }
+
+    fn read(&mut self, _addr: u16) -> Option<u8> {
+        Some(self.0)
+    }
+
+    fn answers_reads(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn code_next_to_bank_switch_registers_is_cached() {
+    let mut cpu = Cpu::new();
+    cpu.engine = Engine::Cached;
+    let mut rom = assemble("inc $0221\njmp $0002", 0x8000).unwrap();
+    rom.resize(0x8000, 0xEA);
+    let rom = cpu.memory.banks.add_rom(&rom);
+    cpu.memory.banks.map_read(0x80, 0x80, rom, 0);
+    cpu.memory.map_bank_switch(
+        Mapping::new(0x8000, 0xFFFF),
+        |_: &mut Banks, _: u16, _: u8| {},
+    );
+    cpu.memory
+        .map_bank_switch(Mapping::new(0x0000, 0x0001), Port(0));
+    cpu.memory
+        .load(0x0002, &assemble("inc $0220\njmp $8000", 0x0002).unwrap());
+    cpu.pc = 0x0002;
+
+    for _ in 0..100 {
+        cpu.step();
+    }
+    assert_eq!((cpu.memory[0x0220], cpu.memory[0x0221]), (25, 25));
+    assert_eq!(cpu.cached_blocks(), 2);
+
+    // A device over the operand takes the zero page block out of the cache
+    cpu.memory
+        .map_handler(Mapping::new(0x0003, 0x0003), |_| 0x21, |_, _| {});
+    for _ in 0..4 {
+        cpu.step();
+    }
+    assert_eq!((cpu.memory[0x0220], cpu.memory[0x0221]), (25, 27));
+}
+
+#[test]
+fn code_in_mapped_rom_is_cached() {
+    let mut cpu = Cpu::new();
+    cpu.engine = Engine::Cached;
+    let code = assemble("loop: inc $0220\njmp loop", 0xF000).unwrap();
+    let rom = cpu.memory.map_rom(0xF000, &code);
+    cpu.pc = 0xF000;
+
+    for _ in 0..100 {
+        cpu.step();
+    }
+    assert_eq!(cpu.memory[0x0220], 50);
+    assert_eq!(cpu.cached_blocks(), 1);
+
+    // Mapped over by RAM, the block is decoded again from there
+    cpu.memory.unmap(rom);
+    cpu.memory.load(
+        0xF000,
+        &assemble("loop: dec $0220\njmp loop", 0xF000).unwrap(),
+    );
+    for _ in 0..100 {
+        cpu.step();
+    }
+    assert_eq!(cpu.memory[0x0220], 0);
+}
+
+#[test]
+fn engines_account_for_code_reads_alike() {
+    let run = |engine| {
+        let mut cpu = Cpu::new();
+        cpu.engine = engine;
+        let code = assemble(
+            "ldx #0\nloop: lda $0300,x\ninx\ncpx #4\nbne loop\njmp $0400",
+            0x0400,
+        );
+        cpu.memory.load(0x0400, &code.unwrap());
+        // Set up after loading, so the code itself was never initialized
+        cpu.memory.uninit = Some(Box::new(UninitChecker::new(UninitMode::Warn)));
+        cpu.memory.heatmap = Some(Box::new(Heatmap::new()));
+        cpu.profiler = Some(Box::new(Profiler::new()));
+        cpu.pc = 0x0400;
+        for _ in 0..50 {
+            cpu.step();
+        }
+
+        let heatmap = cpu.memory.heatmap.as_deref().unwrap();
+        let heat = [Heat::Reads, Heat::Executes].map(|heat| heatmap.counts(heat).to_vec());
+        let mut profile = Vec::new();
+        cpu.profiler
+            .as_deref()
+            .unwrap()
+            .write_csv(&mut profile)
+            .unwrap();
+        let reads = cpu.memory.uninit.as_deref().unwrap().reads().to_vec();
+        (heat, profile, reads)
+    };
+
+    let table = run(Engine::Table);
+    assert!(table.2.iter().any(|read| read.addr == 0x0401));
+    assert!(table.2.iter().any(|read| read.addr == 0x0300));
+    assert!(table == run(Engine::Cached));
+}
+
+#[test]
+fn block_cache_is_bounded() {
+    let mut cpu = Cpu::new();
+    cpu.engine = Engine::Cached;
+    // Calls an RTS at every address from $1000 up, each one a block
+    let program = "
+        lda #$00
+        sta $10
+        lda #$10
+        sta $11
+loop:   jsr call
+        inc $10
+        bne loop
+        inc $11
+        jmp loop
+call:   jmp ($0010)
+    ";
+    cpu.memory.load(0x0400, &assemble(program, 0x0400).unwrap());
+    cpu.memory.load(0x1000, &[0x60; 0x2000]);
+    cpu.pc = 0x0400;
+
+    for _ in 0..30_000 {
+        cpu.step();
+    }
+    assert!(cpu.memory[0x11] >= 0x21);
+    assert!(cpu.cached_blocks() > 0 && cpu.cached_blocks() <= 4096);
+}
@@ -0,0 +1,118 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::{Cpu, Engine},
+    heatmap::{Heat, Heatmap},
+    uninit::{UninitChecker, UninitMode},
+};
+
+const PROGRAM: &str = "
+start:  lda $0500       ; ROM, never counts as unwritten
+        ldx #0
+loop:   lda $0300,x
+        sta $0380,x
+        inc $10
+        pha
+        pla
+        inx
+        cpx #4
+        bne loop
+done:   jmp done
+";
+
+fn run(engine: Engine) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.engine = engine;
+    cpu.memory.uninit = Some(Box::new(UninitChecker::new(UninitMode::Warn)));
+    cpu.memory.load(0x0400, &assemble(PROGRAM, 0x0400).unwrap());
+    cpu.memory.load(0x0300, &[0x01, 0x02]);
+    let rom = cpu.memory.banks.add_rom(&[0x42; 0x100]);
+    cpu.memory.banks.map(0x05, 1, rom, 0);
+    cpu.pc = 0x0400;
+    cpu.memory.heatmap = Some(Box::new(Heatmap::new()));
+
+    // LDA, LDX, four rounds of eight, three laps of JMP done
+    for _ in 0..2 + 4 * 8 + 3 {
+        cpu.step();
+    }
+    cpu
+}
+
+#[test]
+fn counts_reads_writes_and_executes() {
+    let cpu = run(Engine::Table);
+    let heatmap = cpu.memory.heatmap.as_ref().unwrap();
+    let count = |heat, addr: u16| heatmap.counts(heat)[addr as usize];
+
+    assert_eq!(count(Heat::Reads, 0x0302), 1);
+    assert_eq!(count(Heat::Writes, 0x0382), 1);
+    // INC reads once and writes twice, PHA/PLA go through $01FD
+    assert_eq!(
+        (count(Heat::Reads, 0x10), count(Heat::Writes, 0x10)),
+        (4, 8)
+    );
+    assert_eq!(
+        (count(Heat::Reads, 0x01FD), count(Heat::Writes, 0x01FD)),
+        (4, 4)
+    );
+
+    // Instruction bytes are executed, not read
+    assert_eq!(count(Heat::Executes, 0x0405), 4);
+    assert_eq!(count(Heat::Executes, 0x0407), 4);
+    assert_eq!(count(Heat::Reads, 0x0405), 0);
+    assert_eq!(count(Heat::Executes, 0x0414), 3);
+
+    // Loaded bytes count as initialized
+    assert_eq!(
+        heatmap.unwritten_reads(),
+        [(0x0010, 1), (0x0302, 1), (0x0303, 1)]
+    );
+    let uninit = cpu.memory.uninit.as_ref().unwrap();
+    assert_eq!(uninit.reads().len(), 3);
+}
+
+#[test]
+fn unwritten_reads_need_the_uninit_checker() {
+    let mut cpu = Cpu::new();
+    cpu.memory.heatmap = Some(Box::new(Heatmap::new()));
+    cpu.memory.load(0x0400, &assemble("lda $0300", 0x0400).unwrap());
+    cpu.pc = 0x0400;
+    cpu.step();
+    let heatmap = cpu.memory.heatmap.as_ref().unwrap();
+    assert_eq!(heatmap.counts(Heat::Reads)[0x0300], 1);
+    assert!(heatmap.unwritten_reads().is_empty());
+}
+
+#[test]
+fn engines_see_the_same_accesses() {
+    let table = run(Engine::Table);
+    let cached = run(Engine::Cached);
+    for heat in [Heat::Reads, Heat::Writes, Heat::Executes] {
+        assert!(
+            table.memory.heatmap.as_ref().unwrap().counts(heat)
+                == cached.memory.heatmap.as_ref().unwrap().counts(heat),
+            "{heat:?}"
+        );
+    }
+}
+
+#[test]
+fn exports_pgm_and_csv() {
+    let cpu = run(Engine::Table);
+    let heatmap = cpu.memory.heatmap.as_ref().unwrap();
+
+    let mut pgm = Vec::new();
+    heatmap.write_pgm(Heat::Executes, &mut pgm).unwrap();
+    let header = b"P5\n256 256\n255\n";
+    assert!(pgm.starts_with(header));
+    assert_eq!(pgm.len(), header.len() + 0x10000);
+    let pixels = &pgm[header.len()..];
+    assert_eq!(pixels[0x0300], 0);
+    assert_eq!(pixels[0x0405], 255);
+    assert!(pixels[0x0400] > 0 && pixels[0x0400] < pixels[0x0414]);
+
+    let mut csv = Vec::new();
+    heatmap.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with("address,reads,writes,executes,unwritten_reads\n0010,4,8,0,1\n"));
+    assert!(csv.contains("\n0500,1,0,0,0\n"));
+}
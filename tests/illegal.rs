@@ -0,0 +1,158 @@
+use cpu6502::{
+    bus::{Handler, Mapping},
+    cpu::{Cpu, CpuError, Flag},
+    heatmap::{Heat, Heatmap},
+    illegal::{IllegalPolicy, OpcodeClass},
+    profiler::Profiler,
+};
+use std::{cell::Cell, rc::Rc};
+
+fn cpu_with_program(program: &[u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.pc = 0x0200;
+    for (i, &byte) in program.iter().enumerate() {
+        cpu.memory[0x0200 + i] = byte;
+    }
+    cpu
+}
+
+#[test]
+fn every_undocumented_opcode_has_a_class() {
+    let count = |class| {
+        (0..=0xFF)
+            .filter(|&opcode| OpcodeClass::of(opcode) == Some(class))
+            .count()
+    };
+    assert_eq!(count(OpcodeClass::Nop), 27);
+    assert_eq!(count(OpcodeClass::Stable), 58);
+    assert_eq!(count(OpcodeClass::Unstable), 8);
+    assert_eq!(count(OpcodeClass::Jam), 12);
+    assert_eq!(OpcodeClass::of(0xA9), None);
+
+    // None of them is an instant no-op any more
+    let cpu = Cpu::new();
+    assert!((0..=0xFF).all(|opcode| cpu.instruction(opcode).cycles > 0));
+}
+
+#[test]
+fn combined_opcodes_are_emulated_by_default() {
+    // LAX $10; SLO $11; DCP $12; SBX #$01
+    let mut cpu = cpu_with_program(&[0xA7, 0x10, 0x07, 0x11, 0xC7, 0x12, 0xCB, 0x01]);
+    cpu.memory[0x10] = 0x81;
+    cpu.memory[0x11] = 0xC0;
+    cpu.memory[0x12] = 0x82;
+
+    assert_eq!(cpu.step(), 3);
+    assert_eq!((cpu.a, cpu.x), (0x81, 0x81));
+
+    assert_eq!(cpu.step(), 5);
+    assert_eq!(cpu.memory[0x11], 0x80);
+    assert_eq!(cpu.a, 0x81);
+    assert!(cpu.get_flag(Flag::Carry));
+
+    assert_eq!(cpu.step(), 5);
+    assert_eq!(cpu.memory[0x12], 0x81);
+    assert!(cpu.get_flag(Flag::Zero));
+
+    cpu.step();
+    assert_eq!(cpu.x, 0x80);
+}
+
+#[test]
+fn nop_policy_skips_the_operand() {
+    // SLO $0300
+    let mut cpu = cpu_with_program(&[0x0F, 0x00, 0x03]);
+    cpu.memory[0x0300] = 0x40;
+    cpu.set_illegal_policy(OpcodeClass::Stable, IllegalPolicy::Nop);
+
+    assert_eq!(cpu.step(), 6);
+    assert_eq!(cpu.pc, 0x0203);
+    assert_eq!(cpu.memory[0x0300], 0x40);
+    assert_eq!(cpu.a, 0x00);
+}
+
+#[test]
+fn nop_policy_leaves_devices_alone() {
+    // LAX ($80),Y with the pointer in a device
+    let mut cpu = cpu_with_program(&[0xB3, 0x80]);
+    let reads = Rc::new(Cell::new(0));
+    let counter = reads.clone();
+    cpu.memory.map(
+        Mapping::new(0x0080, 0x0081),
+        Handler {
+            read: move |_| {
+                counter.set(counter.get() + 1);
+                0x00
+            },
+            write: |_, _| {},
+        },
+    );
+    cpu.set_illegal_policy(OpcodeClass::Stable, IllegalPolicy::Nop);
+
+    assert_eq!(cpu.step(), 5);
+    assert_eq!(cpu.pc, 0x0202);
+    assert_eq!(reads.get(), 0);
+}
+
+#[test]
+fn trap_policy_stops_the_run() {
+    // LDA #$01; ANE #$FF
+    let mut cpu = cpu_with_program(&[0xA9, 0x01, 0x8B, 0xFF]);
+    cpu.set_illegal_policy(OpcodeClass::Unstable, IllegalPolicy::Trap);
+
+    assert_eq!(
+        cpu.run(100),
+        Err(CpuError::IllegalOpcode {
+            pc: 0x0202,
+            opcode: 0x8B
+        })
+    );
+    assert_eq!(cpu.pc, 0x0202);
+    assert_eq!(cpu.a, 0x01);
+
+    // Back to emulating, the CPU stays stopped until reset
+    cpu.set_illegal_policy(OpcodeClass::Unstable, IllegalPolicy::Emulate);
+    assert!(cpu.try_step().is_err());
+    cpu.reset();
+    cpu.step();
+    cpu.pc = 0x0202;
+    assert_eq!(cpu.try_step(), Ok(2));
+    assert_eq!(cpu.a, 0x00);
+}
+
+#[test]
+fn trapped_opcodes_are_not_counted_as_executed() {
+    // LDA #$01; ANE #$FF
+    let mut cpu = cpu_with_program(&[0xA9, 0x01, 0x8B, 0xFF]);
+    cpu.set_illegal_policy(OpcodeClass::Unstable, IllegalPolicy::Trap);
+    cpu.profiler = Some(Box::new(Profiler::new()));
+    cpu.memory.heatmap = Some(Box::new(Heatmap::new()));
+    assert!(cpu.run(100).is_err());
+
+    let profiler = cpu.profiler.as_ref().unwrap();
+    assert_eq!(profiler.executions(0x0200), 1);
+    assert_eq!(profiler.executions(0x0202), 0);
+    assert_eq!(profiler.cycles(), 2);
+    assert!(!profiler.covered(0x0202));
+    let executes = cpu.memory.heatmap.as_ref().unwrap().counts(Heat::Executes);
+    assert_eq!(executes[0x0201..0x0204], [1, 0, 0]);
+}
+
+#[test]
+fn jam_locks_up_the_cpu() {
+    let mut cpu = cpu_with_program(&[0xEA, 0x02]);
+    assert_eq!(
+        cpu.run(100),
+        Err(CpuError::Jammed {
+            pc: 0x0201,
+            opcode: 0x02
+        })
+    );
+
+    cpu.reset();
+    cpu.step();
+    cpu.pc = 0x0200;
+    cpu.set_illegal_policy(OpcodeClass::Jam, IllegalPolicy::Nop);
+    assert_eq!(cpu.run(4), Ok(4));
+    assert_eq!(cpu.pc, 0x0202);
+}
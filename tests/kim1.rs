@@ -0,0 +1,125 @@
+use cpu6502::{
+    devices::serial::BufferHost,
+    systems::kim1::{Kim1, KEY_GO, TTY_BIT_CYCLES},
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// A stand-in 6530-002 ROM running `program` from $1C00
+fn rom_002(program: &[u8]) -> Vec<u8> {
+    let mut rom = vec![0; 0x400];
+    rom[..program.len()].copy_from_slice(program);
+    rom[0x3FC] = 0x00;
+    rom[0x3FD] = 0x1C;
+    rom
+}
+
+fn boot(program: &[u8]) -> (Kim1, Rc<RefCell<BufferHost>>) {
+    let terminal = Rc::new(RefCell::new(BufferHost::new()));
+    let mut kim = Kim1::new(&rom_002(program), &[0; 0x400], terminal.clone());
+    kim.reset();
+    (kim, terminal)
+}
+
+/// Receives a character on PA7 and answers with the next one on PB0, with
+/// delay loops timed to `TTY_BIT_CYCLES` like the monitor's
+#[rustfmt::skip]
+const TTY_ECHO: [u8; 0x52] = [
+    0xA9, 0x01,       //       LDA #$01
+    0x8D, 0x43, 0x17, //       STA PBDD
+    0x8D, 0x42, 0x17, //       STA SBD    idle at mark
+    0x2C, 0x40, 0x17, // WAIT  BIT SAD
+    0x30, 0xFB,       //       BMI WAIT   start bit
+    0x20, 0x4C, 0x1C, //       JSR HALF
+    0xA0, 0x08,       //       LDY #8
+    0x20, 0x49, 0x1C, // RBIT  JSR FULL
+    0xAD, 0x40, 0x17, //       LDA SAD
+    0x0A,             //       ASL A
+    0x66, 0x00,       //       ROR $00
+    0x88,             //       DEY
+    0xD0, 0xF4,       //       BNE RBIT
+    0x20, 0x49, 0x1C, //       JSR FULL   stop bit
+    0xE6, 0x00,       //       INC $00
+    0xA9, 0x00,       //       LDA #$00
+    0x8D, 0x42, 0x17, //       STA SBD    start bit
+    0x20, 0x49, 0x1C, //       JSR FULL
+    0xA0, 0x08,       //       LDY #8
+    0x46, 0x00,       // TBIT  LSR $00
+    0xA9, 0x00,       //       LDA #$00
+    0x2A,             //       ROL A
+    0x8D, 0x42, 0x17, //       STA SBD
+    0x20, 0x49, 0x1C, //       JSR FULL
+    0x88,             //       DEY
+    0xD0, 0xF2,       //       BNE TBIT
+    0xA9, 0x01,       //       LDA #$01
+    0x8D, 0x42, 0x17, //       STA SBD    stop bits
+    0x20, 0x49, 0x1C, //       JSR FULL
+    0x20, 0x49, 0x1C, //       JSR FULL
+    0x4C, 0x08, 0x1C, //       JMP WAIT
+    0x20, 0x4C, 0x1C, // FULL  JSR HALF
+    0xA2, 0x50,       // HALF  LDX #80    413 cycles with the JSR
+    0xCA,             // DL    DEX
+    0xD0, 0xFD,       //       BNE DL
+    0x60,             //       RTS
+];
+
+/// Cycles to send a frame and get the answer back
+const ROUND_TRIP: u64 = 24 * TTY_BIT_CYCLES as u64;
+
+#[test]
+fn tty_is_bit_banged_through_pa7_and_pb0() {
+    let (mut kim, terminal) = boot(&TTY_ECHO);
+
+    terminal.borrow_mut().send(b"a");
+    kim.run(ROUND_TRIP);
+    // Typed in upper case
+    assert_eq!(terminal.borrow().output, b"B");
+
+    terminal.borrow_mut().send(b"1");
+    kim.run(ROUND_TRIP);
+    assert_eq!(terminal.borrow().output, b"B2");
+}
+
+#[test]
+fn rubout_goes_ahead_of_the_terminal() {
+    let (mut kim, terminal) = boot(&TTY_ECHO);
+
+    kim.rubout();
+    terminal.borrow_mut().send(b"a");
+    kim.run(ROUND_TRIP);
+
+    // $7F + 1, with the top bit dropped
+    assert_eq!(terminal.borrow().output, b"\0");
+}
+
+#[test]
+fn keypad_rows_are_scanned_through_port_b() {
+    // LDA #$1E; STA PBDD; LDA #$04; STA SBD; LDA SAD; STA $00; JMP $1C0A
+    let (mut kim, _) = boot(&[
+        0xA9, 0x1E, 0x8D, 0x43, 0x17, 0xA9, 0x04, 0x8D, 0x42, 0x17, 0xAD, 0x40, 0x17, 0x85,
+        0x00, 0x4C, 0x0A, 0x1C,
+    ]);
+
+    kim.key = Some(KEY_GO);
+    kim.run(100);
+    assert_eq!(kim.cpu.memory[0x0000], 0xDF);
+
+    // Keys on other rows don't show up
+    kim.key = Some(0x03);
+    kim.run(100);
+    assert_eq!(kim.cpu.memory[0x0000], 0xFF);
+}
+
+#[test]
+fn display_digits_are_latched() {
+    // LDA #$7F; STA PADD; LDA #$1E; STA PBDD; LDA #$0A; STA SBD;
+    // LDA #$06; STA SAD; LDA #$00; STA SAD; JMP $1C0F
+    let (mut kim, _) = boot(&[
+        0xA9, 0x7F, 0x8D, 0x41, 0x17, 0xA9, 0x1E, 0x8D, 0x43, 0x17, 0xA9, 0x0A, 0x8D, 0x42,
+        0x17, 0xA9, 0x06, 0x8D, 0x40, 0x17, 0xA9, 0x00, 0x8D, 0x40, 0x17, 0x4C, 0x0F, 0x1C,
+    ]);
+    kim.tty = false;
+    kim.run(100);
+
+    // Digit select 5 is the second digit, "1" lights segments b and c
+    assert_eq!(kim.display, [0, 0x06, 0, 0, 0, 0]);
+}
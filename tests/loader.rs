@@ -1,4 +1,4 @@
-use cpu6502::cpu::{Cpu, Flag};
+use cpu6502::cpu::Cpu;
 use serde::{Deserialize, Serialize};
 use std::{fs, path::Path};
 
@@ -51,17 +51,6 @@ fn run_all_opcode_functional_tests() {
         for (idx, tc) in test_cases.into_iter().enumerate() {
             let test_num = idx + 1;
 
-            // If this is an ADC test in decimal mode, skip it
-            let adc_sbc_opcodes = [
-                "61", "65", "69", "6d", "71", "75", "79", "7d", "e1", "e5", "e9", "ed", "f1", "f5",
-                "f9", "fd",
-            ];
-            if adc_sbc_opcodes.contains(&&tc.name[..2])
-                && (tc.initial.p & (1 << Flag::Decimal as u8)) != 0
-            {
-                println!("  -> skipping decimal‐mode ADC/SBC test {}", tc.name);
-                continue;
-            }
             let result = std::panic::catch_unwind(|| {
                 println!(
                     "\n--------------- [{}] {}/{} - {} ---------------",
@@ -70,7 +59,7 @@ fn run_all_opcode_functional_tests() {
                 run_test_case(&tc);
             });
 
-            if let Err(_) = result {
+            if result.is_err() {
                 eprintln!("  -> Test failed: {} [{}]", tc.name, file_stem);
                 match serde_json::to_string_pretty(&tc) {
                     Ok(json) => eprintln!("Failing Test JSON:\n{}", json),
@@ -0,0 +1,93 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::{Cpu, Engine},
+    lockstep::{record, replay, Field, LockStep, Step},
+};
+
+const PROGRAM: &str = "
+start:  ldx #0
+copy:   lda $0300,x
+        asl
+        sta $0380,x
+        pha
+        pla
+        inx
+        cpx #$10
+        bne copy
+        jsr sub
+        jmp start
+sub:    inc $10
+        rts
+";
+
+fn machine(engine: Engine) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.engine = engine;
+    cpu.memory.load(0x0400, &assemble(PROGRAM, 0x0400).unwrap());
+    for i in 0..0x10 {
+        cpu.memory[0x0300 + i] = i as u8 * 7;
+    }
+    cpu.pc = 0x0400;
+    cpu
+}
+
+#[test]
+fn engines_run_in_lock_step() {
+    let mut lockstep = LockStep::new(machine(Engine::Table), machine(Engine::Cached));
+    assert_eq!(lockstep.run(5000).unwrap(), 5000);
+}
+
+#[test]
+fn reports_the_first_divergence() {
+    let mut candidate = machine(Engine::Cached);
+    candidate.memory[0x0305] = 0xFF;
+    let mut lockstep = LockStep::new(machine(Engine::Table), candidate);
+
+    let divergence = lockstep.run(5000).unwrap_err();
+    // LDX, then five rounds of eight before LDA $0305
+    assert_eq!(divergence.instruction, 1 + 5 * 8);
+    assert_eq!(divergence.fields, [Field::A, Field::Status]);
+    assert_eq!(divergence.actual.addr, 0x0402);
+
+    let report = divergence.to_string();
+    assert!(report.contains("> 0402  BD 00 03  LDA $0300,X"), "{report}");
+    assert!(report.contains("  040E  D0 F2     BNE $0402"), "{report}");
+    assert!(report.contains("expected 0402 PC:0405 A:23"), "{report}");
+}
+
+#[test]
+fn context_shows_code_in_mapped_rom() {
+    let machine = |engine| {
+        let mut cpu = machine(engine);
+        cpu.memory
+            .map_rom(0xF000, &assemble(PROGRAM, 0xF000).unwrap());
+        cpu.pc = 0xF000;
+        cpu
+    };
+    let mut candidate = machine(Engine::Cached);
+    candidate.memory[0x0305] = 0xFF;
+    let mut lockstep = LockStep::new(machine(Engine::Table), candidate);
+
+    let report = lockstep.run(5000).unwrap_err().to_string();
+    assert!(report.contains("> F002  BD 00 03  LDA $0300,X"), "{report}");
+}
+
+#[test]
+fn replays_a_recorded_trace() {
+    let trace = record(&mut machine(Engine::Table), 200);
+    assert_eq!(trace[3].writes, [(0x0380, 0x00)]);
+
+    let text: Vec<String> = trace.iter().map(|step| step.to_string()).collect();
+    let parsed: Vec<Step> = text.iter().map(|line| line.parse().unwrap()).collect();
+    assert_eq!(parsed, trace);
+
+    assert!(replay(&mut machine(Engine::Cached), &parsed).is_ok());
+
+    // Storing to the wrong place shows up as a write difference
+    let mut cpu = machine(Engine::Table);
+    cpu.memory[0x0407] = 0x81;
+    let divergence = replay(&mut cpu, &parsed).unwrap_err();
+    assert_eq!(divergence.instruction, 3);
+    assert_eq!(divergence.fields, [Field::Writes]);
+    assert!("0400 PC:0402".parse::<Step>().is_err());
+}
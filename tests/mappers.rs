@@ -0,0 +1,175 @@
+use cpu6502::systems::nes::{cartridge::Mirroring, mapper::SUPPORTED, Nes};
+
+/// An iNES image whose 8K PRG banks and 1K CHR banks start with their own
+/// index. The last PRG bank spins at $E010 after reset.
+fn nes(mapper: u8, prg_banks: u8, chr_banks: u8) -> Nes {
+    let mut image = b"NES\x1A".to_vec();
+    image.extend([prg_banks, chr_banks, mapper << 4, mapper & 0xF0]);
+    image.extend([0; 8]);
+
+    let prg_8k = prg_banks as usize * 2;
+    for bank in 0..prg_8k {
+        let mut data = vec![0xEA; 0x2000];
+        data[0] = bank as u8;
+        if bank == prg_8k - 1 {
+            data[0x10..0x13].copy_from_slice(&[0x4C, 0x10, 0xE0]);
+            data[0x1FFA..].copy_from_slice(&[0x10, 0xE0, 0x10, 0xE0, 0x10, 0xE0]);
+        }
+        image.extend(data);
+    }
+    for bank in 0..chr_banks as usize * 8 {
+        image.extend(vec![bank as u8; 0x400]);
+    }
+
+    let mut nes = Nes::from_ines(&image).unwrap();
+    nes.reset();
+    nes
+}
+
+fn mmc1_write(nes: &mut Nes, addr: u16, value: u8) {
+    for bit in 0..5 {
+        nes.cpu.memory.write(addr, value >> bit);
+    }
+}
+
+#[test]
+fn mmc1_loads_registers_serially() {
+    let mut nes = nes(1, 8, 2);
+    let memory = &mut nes.cpu.memory;
+    assert_eq!(memory.read(0x8000), 0);
+    assert_eq!(memory.read(0xC000), 14);
+
+    mmc1_write(&mut nes, 0xE000, 3);
+    assert_eq!(nes.cpu.memory.read(0x8000), 6);
+    assert_eq!(nes.cpu.memory.read(0xC000), 14);
+
+    // Fix the first bank at $8000 and switch $C000, vertical mirroring
+    mmc1_write(&mut nes, 0x8000, 0x0A);
+    assert_eq!(nes.cpu.memory.read(0x8000), 0);
+    assert_eq!(nes.cpu.memory.read(0xC000), 6);
+    assert_eq!(nes.mapper.borrow().mirroring(), Mirroring::Vertical);
+
+    // 4K CHR mode
+    mmc1_write(&mut nes, 0x8000, 0x12);
+    mmc1_write(&mut nes, 0xC000, 3);
+    assert_eq!(nes.ppu.borrow().ppu_read(0x1000), 12);
+
+    // A write with bit 7 set resets the shift register and the PRG mode
+    nes.cpu.memory.write(0x8000, 0x01);
+    nes.cpu.memory.write(0x8000, 0x80);
+    assert_eq!(nes.cpu.memory.read(0xC000), 14);
+}
+
+#[test]
+fn uxrom_switches_the_low_bank() {
+    let mut nes = nes(2, 8, 0);
+    nes.cpu.memory.write(0x8000, 2);
+    assert_eq!(nes.cpu.memory.read(0x8000), 4);
+    assert_eq!(nes.cpu.memory.read(0xC000), 14);
+
+    // CHR RAM
+    nes.ppu.borrow_mut().ppu_write(0x0123, 0x99);
+    assert_eq!(nes.ppu.borrow().ppu_read(0x0123), 0x99);
+}
+
+#[test]
+fn cnrom_switches_chr() {
+    let mut nes = nes(3, 2, 4);
+    nes.cpu.memory.write(0xFFF0, 2);
+    assert_eq!(nes.ppu.borrow().ppu_read(0x0000), 16);
+    assert_eq!(nes.ppu.borrow().ppu_read(0x1C00), 23);
+}
+
+#[test]
+fn axrom_switches_32k_and_nametable() {
+    let mut nes = nes(7, 8, 0);
+    assert_eq!(
+        nes.mapper.borrow().mirroring(),
+        Mirroring::SingleScreenLower
+    );
+
+    nes.cpu.memory.write(0x8000, 0x12);
+    assert_eq!(nes.cpu.memory.read(0x8000), 8);
+    assert_eq!(nes.cpu.memory.read(0xE000), 11);
+    assert_eq!(
+        nes.mapper.borrow().mirroring(),
+        Mirroring::SingleScreenUpper
+    );
+}
+
+#[test]
+fn mmc3_banks_prg_and_chr() {
+    let mut nes = nes(4, 8, 2);
+    let memory = &mut nes.cpu.memory;
+
+    memory.write(0x8000, 0x06);
+    memory.write(0x8001, 3);
+    assert_eq!(memory.read(0x8000), 3);
+    assert_eq!(memory.read(0xC000), 14);
+    assert_eq!(memory.read(0xE000), 15);
+
+    // Swap $8000 and $C000
+    memory.write(0x8000, 0x46);
+    assert_eq!(memory.read(0x8000), 14);
+    assert_eq!(memory.read(0xC000), 3);
+
+    memory.write(0x8000, 0x02);
+    memory.write(0x8001, 9);
+    assert_eq!(nes.ppu.borrow().ppu_read(0x1000), 9);
+
+    // R0 is a 2K bank
+    nes.cpu.memory.write(0x8000, 0x00);
+    nes.cpu.memory.write(0x8001, 4);
+    assert_eq!(nes.ppu.borrow().ppu_read(0x0400), 5);
+}
+
+#[test]
+fn mmc3_scanline_counter_raises_irq() {
+    let mut nes = nes(4, 8, 2);
+    let memory = &mut nes.cpu.memory;
+    memory.write(0xC000, 3);
+    memory.write(0xC001, 0);
+    memory.write(0xE001, 0);
+
+    // Reload to 3, then count down to 0
+    for _ in 0..3 {
+        nes.mapper.borrow_mut().scanline();
+    }
+    assert!(!nes.mapper.borrow().irq());
+    nes.mapper.borrow_mut().scanline();
+    assert!(nes.mapper.borrow().irq());
+
+    nes.cpu.memory.write(0xE000, 0);
+    assert!(!nes.mapper.borrow().irq());
+
+    // With rendering on the PPU clocks it and the CPU sees the IRQ
+    nes.cpu.memory.write(0xE001, 0);
+    nes.cpu.memory.write(0x2001, 0x18);
+    nes.run_frame();
+    assert!(nes.cpu.irq_asserted());
+}
+
+#[test]
+fn every_mapper_runs_a_16k_prg() {
+    for mapper in SUPPORTED {
+        let mut nes = nes(mapper, 1, 1);
+        // Windows wider than the ROM and bank numbers past its end
+        match mapper {
+            // 32K PRG mode
+            1 => mmc1_write(&mut nes, 0x8000, 0x00),
+            // PRG mode 1, R6 = 9
+            4 => {
+                nes.cpu.memory.write(0x8000, 0x46);
+                nes.cpu.memory.write(0x8001, 9);
+            }
+            _ => nes.cpu.memory.write(0x8000, 0x07),
+        }
+
+        assert_eq!(nes.cpu.memory.read(0xA000), 1, "mapper {mapper}");
+        assert_eq!(nes.cpu.memory.read(0xE000), 1, "mapper {mapper}");
+        for _ in 0..10 {
+            nes.cpu.step();
+        }
+        assert_eq!(nes.cpu.pc, 0xE010, "mapper {mapper}");
+    }
+}
@@ -0,0 +1,183 @@
+use cpu6502::{
+    cpu::Engine,
+    opcodes::Variant,
+    systems::nes::{
+        cartridge::{Cartridge, CartridgeError, Mirroring},
+        Nes, BUTTON_A, BUTTON_START,
+    },
+};
+
+/// An NROM-128 image running `program` from $C000, NMI handler at $C100
+fn ines(program: &[u8], nmi: &[u8]) -> Vec<u8> {
+    let mut image = b"NES\x1A".to_vec();
+    image.extend([1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let mut prg = vec![0xEA; 0x4000];
+    prg[..program.len()].copy_from_slice(program);
+    prg[0x100..0x100 + nmi.len()].copy_from_slice(nmi);
+    prg[0x3FFA..].copy_from_slice(&[0x00, 0xC1, 0x00, 0xC0, 0x00, 0xC0]);
+    image.extend(prg);
+    image.extend(vec![0; 0x2000]);
+    image
+}
+
+fn nes(program: &[u8], nmi: &[u8]) -> Nes {
+    let mut nes = Nes::from_ines(&ines(program, nmi)).unwrap();
+    nes.reset();
+    nes
+}
+
+#[test]
+fn ines_header_is_parsed() {
+    let mut image = ines(&[], &[]);
+    image[6] = 0x01;
+    let cartridge = Cartridge::from_ines(&image).unwrap();
+    assert_eq!(cartridge.prg_rom.len(), 0x4000);
+    assert_eq!(cartridge.chr.len(), 0x2000);
+    assert_eq!(cartridge.mirroring, Mirroring::Vertical);
+
+    assert_eq!(
+        Cartridge::from_ines(b"NOPE").err(),
+        Some(CartridgeError::BadHeader)
+    );
+    assert_eq!(
+        Cartridge::from_ines(&image[..0x1000]).err(),
+        Some(CartridgeError::Truncated)
+    );
+    image[6] = 0x50;
+    assert_eq!(
+        Cartridge::from_ines(&image).err(),
+        Some(CartridgeError::UnsupportedMapper(5))
+    );
+}
+
+#[test]
+fn cartridges_built_by_hand_are_checked_for_a_mapper() {
+    let mut cartridge = Cartridge::from_ines(&ines(&[], &[])).unwrap();
+    cartridge.mapper = 5;
+    assert_eq!(
+        Nes::new(cartridge).err(),
+        Some(CartridgeError::UnsupportedMapper(5))
+    );
+}
+
+#[test]
+fn ram_is_mirrored_and_rom_is_read_only() {
+    let mut nes = nes(&[], &[]);
+    assert_eq!(nes.cpu.variant, Variant::Ricoh2A03);
+    nes.cpu.memory.write(0x0001, 0x42);
+    assert_eq!(nes.cpu.memory.read(0x1801), 0x42);
+
+    // NROM-128 shows up twice
+    nes.cpu.memory.write(0x8000, 0x00);
+    assert_eq!(nes.cpu.memory.read(0x8000), 0xEA);
+    assert_eq!(nes.cpu.memory.read(0xBFFC), 0x00);
+    assert_eq!(nes.cpu.memory.read(0xBFFD), 0xC0);
+}
+
+#[test]
+fn vblank_raises_nmi_once_per_frame() {
+    // LDA #$80; STA PPUCTRL; JMP *
+    let program = [0xA9, 0x80, 0x8D, 0x00, 0x20, 0x4C, 0x05, 0xC0];
+    // INC $00; RTI
+    let mut nes = nes(&program, &[0xE6, 0x00, 0x40]);
+
+    nes.run_frame();
+    let cycles = nes.run_frame();
+    // 262 * 341 dots at 3 dots per cycle, give or take an instruction
+    assert!((29_775..=29_790).contains(&cycles), "{cycles}");
+
+    nes.run_frame();
+    for _ in 0..10 {
+        nes.step();
+    }
+    assert_eq!(nes.cpu.memory[0x0000], 3);
+}
+
+#[test]
+fn cached_engine_keeps_code_under_the_mapper_registers() {
+    // LDA #$80; STA PPUCTRL; JMP *
+    let program = [0xA9, 0x80, 0x8D, 0x00, 0x20, 0x4C, 0x05, 0xC0];
+    // INC $00; RTI
+    let mut nes = nes(&program, &[0xE6, 0x00, 0x40]);
+    nes.cpu.engine = Engine::Cached;
+
+    for _ in 0..3 {
+        nes.run_frame();
+    }
+    assert!(nes.cpu.memory[0x0000] >= 2);
+    assert!(nes.cpu.cached_blocks() >= 2);
+}
+
+#[test]
+fn ppudata_goes_through_the_read_buffer() {
+    let mut nes = nes(&[], &[]);
+    let memory = &mut nes.cpu.memory;
+
+    memory.write(0x2006, 0x20);
+    memory.write(0x2006, 0x08);
+    memory.write(0x2007, 0x55);
+    memory.write(0x2007, 0x66);
+
+    // Horizontal mirroring: $2400 is $2000
+    memory.write(0x2006, 0x24);
+    memory.write(0x2006, 0x08);
+    memory.read(0x2007);
+    assert_eq!(memory.read(0x2007), 0x55);
+    assert_eq!(memory.read(0x2007), 0x66);
+}
+
+#[test]
+fn controller_shifts_out_buttons() {
+    let mut nes = nes(&[], &[]);
+    nes.set_buttons(0, BUTTON_A | BUTTON_START);
+
+    let memory = &mut nes.cpu.memory;
+    memory.write(0x4016, 1);
+    memory.write(0x4016, 0);
+    let bits: Vec<u8> = (0..9).map(|_| memory.read(0x4016) & 0x01).collect();
+    assert_eq!(bits, [1, 0, 0, 1, 0, 0, 0, 0, 1]);
+}
+
+#[test]
+fn oam_dma_copies_a_page_and_stalls() {
+    // LDA #$02; STA OAMDMA; JMP *
+    let program = [0xA9, 0x02, 0x8D, 0x14, 0x40, 0x4C, 0x05, 0xC0];
+    let mut nes = nes(&program, &[]);
+    for i in 0..=0xFF {
+        nes.cpu.memory[0x0200 + i] = i as u8;
+    }
+
+    nes.step();
+    nes.step();
+    let cycles = nes.step();
+
+    assert!(cycles == 4 + 513 || cycles == 4 + 514, "{cycles}");
+    assert_eq!(nes.ppu.borrow().oam[0x80], 0x80);
+}
+
+#[test]
+fn apu_frame_counter_raises_irq_and_clocks_lengths() {
+    let mut nes = nes(&[], &[]);
+    let memory = &mut nes.cpu.memory;
+    // Pulse 1 enabled with a length of 254, pulse 2 left disabled
+    memory.write(0x4015, 0x01);
+    memory.write(0x4003, 0x08);
+    memory.write(0x4007, 0x08);
+    assert_eq!(memory.read(0x4015), 0x01);
+
+    while nes.cycles < 30_000 {
+        nes.step();
+    }
+    assert!(nes.io.borrow().apu.irq());
+    assert_eq!(nes.cpu.memory.read(0x4015), 0x41);
+    assert!(!nes.io.borrow().apu.irq());
+
+    // Inhibiting the IRQ keeps it quiet, disabling a channel clears its length
+    nes.cpu.memory.write(0x4017, 0x40);
+    nes.cpu.memory.write(0x4015, 0x00);
+    while nes.cycles < 70_000 {
+        nes.step();
+    }
+    assert_eq!(nes.cpu.memory.read(0x4015), 0x00);
+}
@@ -0,0 +1,192 @@
+use cpu6502::{
+    assembler::{assemble, assemble_for, AsmError},
+    cpu::{Cpu, Engine},
+    disassembler::{disassemble, disassemble_for},
+    illegal::OpcodeClass,
+    opcodes::{Access, AddrMode, PagePenalty, Variant, OPCODES},
+    table::INSTRUCTIONS,
+};
+
+#[test]
+fn spec_agrees_with_the_decode_table() {
+    for (opcode, spec) in OPCODES.iter().enumerate() {
+        assert_eq!(spec.opcode as usize, opcode);
+        assert_eq!(spec.mnemonic, INSTRUCTIONS[opcode].name);
+        assert_eq!(spec.cycles, INSTRUCTIONS[opcode].cycles);
+        assert_eq!(spec.class, OpcodeClass::of(opcode as u8));
+    }
+    assert_eq!(OPCODES.iter().filter(|spec| spec.documented()).count(), 151);
+
+    // LDA abs,X pays for page crossings, STA abs,X and INC abs,X don't
+    assert_eq!(OPCODES[0xBD].penalty, PagePenalty::PageCross);
+    assert_eq!(OPCODES[0x9D].penalty, PagePenalty::None);
+    assert_eq!(OPCODES[0xFE].access, Access::ReadModifyWrite);
+    assert_eq!(OPCODES[0xD0].penalty, PagePenalty::Branch);
+
+    assert!(OPCODES[0xA7].available(Variant::Ricoh2A03));
+    assert!(!OPCODES[0xA7].available(Variant::Cmos65C02));
+    assert!(OPCODES[0xA9].available(Variant::Cmos65C02));
+}
+
+#[test]
+fn executor_consumes_the_spec_length() {
+    for spec in &OPCODES {
+        if spec.mode == AddrMode::Relative
+            || matches!(spec.mnemonic, "brk" | "jmp" | "jsr" | "rts" | "rti" | "jam")
+        {
+            continue;
+        }
+        let mut cpu = Cpu::new();
+        cpu.pc = 0x0200;
+        cpu.memory[0x0200] = spec.opcode;
+        cpu.step();
+        assert_eq!(cpu.pc, 0x0200 + spec.bytes as u16, "${:02X}", spec.opcode);
+    }
+}
+
+#[test]
+fn disassembles_every_addressing_mode() {
+    let code = [
+        0x18, 0x0A, 0xA9, 0x10, 0xA5, 0x10, 0xB5, 0x10, 0xB6, 0x10, 0xAD, 0x34, 0x12, 0xBD, 0x34,
+        0x12, 0xB9, 0x34, 0x12, 0x6C, 0x34, 0x12, 0xA1, 0x10, 0xB1, 0x10, 0xD0, 0xFE, 0xA7, 0x10,
+    ];
+    let text: Vec<String> = disassemble(&code, 0x0200)
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    assert_eq!(
+        text,
+        [
+            "CLC",
+            "ASL A",
+            "LDA #$10",
+            "LDA $10",
+            "LDA $10,X",
+            "LDX $10,Y",
+            "LDA $1234",
+            "LDA $1234,X",
+            "LDA $1234,Y",
+            "JMP ($1234)",
+            "LDA ($10,X)",
+            "LDA ($10),Y",
+            "BNE $021A",
+            "LAX $10",
+        ]
+    );
+}
+
+#[test]
+fn brk_is_implied_and_skips_its_padding() {
+    assert_eq!(OPCODES[0x00].mode, AddrMode::Implied);
+    assert_eq!(OPCODES[0x00].bytes, 2);
+    let lines = disassemble(&[0x00, 0x42, 0xEA], 0x0200);
+    assert_eq!(lines[0].to_string(), "BRK");
+    assert_eq!(lines[0].bytes(), [0x00, 0x42]);
+    assert_eq!(lines[1].to_string(), "NOP");
+    assert_eq!(
+        assemble("brk\nbrk #$42", 0x0200).unwrap(),
+        [0x00, 0x00, 0x00, 0x42]
+    );
+
+    for engine in [Engine::Table, Engine::Cached] {
+        let mut cpu = Cpu::new();
+        cpu.engine = engine;
+        cpu.memory.load(0x0200, &assemble("brk #$42\nnop", 0x0200).unwrap());
+        cpu.memory.load(0x0300, &assemble("rti", 0x0300).unwrap());
+        cpu.memory[0xFFFE] = 0x00;
+        cpu.memory[0xFFFF] = 0x03;
+        cpu.pc = 0x0200;
+        cpu.step();
+        assert_eq!(cpu.pc, 0x0300, "{engine:?}");
+        cpu.step();
+        assert_eq!(cpu.pc, 0x0202, "{engine:?}");
+    }
+}
+
+#[test]
+fn cmos_variant_runs_undocumented_opcodes_as_nops() {
+    // LAX $10; LAX $10; JMP $0200
+    let code = [0xA7, 0x10, 0xA7, 0x10, 0x4C, 0x00, 0x02];
+    for engine in [Engine::Table, Engine::Cached] {
+        let mut cpu = Cpu::new();
+        cpu.engine = engine;
+        cpu.memory.load(0x0200, &code);
+        cpu.memory[0x10] = 0x42;
+        cpu.pc = 0x0200;
+        assert_eq!(cpu.step(), 3);
+        assert_eq!((cpu.a, cpu.x), (0x42, 0x42));
+
+        // Blocks decoded for the NMOS part aren't run on the 65C02
+        cpu.variant = Variant::Cmos65C02;
+        cpu.a = 0;
+        cpu.x = 0;
+        for _ in 0..2 {
+            cpu.step();
+        }
+        assert_eq!(cpu.step(), 3, "{engine:?}");
+        assert_eq!((cpu.pc, cpu.a, cpu.x), (0x0202, 0, 0), "{engine:?}");
+    }
+
+    let line = disassemble_for(&code, 0x0200, Variant::Cmos65C02)[0];
+    assert_eq!((line.to_string(), line.next()), ("NOP $10".to_string(), 0x0202));
+    assert_eq!(disassemble(&code, 0x0200)[0].to_string(), "LAX $10");
+
+    assert!(matches!(
+        assemble_for("lax $10", 0x0200, Variant::Cmos65C02),
+        Err(AsmError::UnknownInstruction { line: 1, .. })
+    ));
+    assert_eq!(
+        assemble_for("lda $10", 0x0200, Variant::Cmos65C02),
+        Ok(vec![0xA5, 0x10])
+    );
+}
+
+#[test]
+fn assembles_what_it_disassembles() {
+    for spec in OPCODES.iter().filter(|spec| spec.documented()) {
+        // BRK's padding byte isn't part of its text
+        let code = [spec.opcode, if spec.opcode == 0x00 { 0x00 } else { 0x80 }, 0x12];
+        let line = disassemble(&code[..spec.bytes as usize], 0x0200)[0];
+        let bytes = assemble(&line.to_string(), 0x0200).unwrap();
+        assert_eq!(bytes, line.bytes(), "{}", line);
+    }
+}
+
+#[test]
+fn assembles_labels_and_data() {
+    let source = "
+        sta #$10
+    ";
+    assert!(matches!(
+        assemble(source, 0),
+        Err(AsmError::UnknownInstruction { line: 2, .. })
+    ));
+
+    let source = "
+        zp: .byte $00, 2
+        start:
+            ldx #%101     ; count down
+        loop: dex
+            stx zp,y
+            bne loop
+            jmp end
+            brk
+        end: rts
+            .word start
+    ";
+    assert_eq!(
+        assemble(source, 0x0000).unwrap(),
+        [
+            0x00, 0x02, 0xA2, 0x05, 0xCA, 0x96, 0x00, 0xD0, 0xFB, 0x4C, 0x0E, 0x00, 0x00, 0x00,
+            0x60, 0x02, 0x00,
+        ]
+    );
+
+    assert_eq!(
+        assemble("bne far\n.byte 0", 0x0200),
+        Err(AsmError::UnknownLabel {
+            line: 1,
+            label: "far".to_string()
+        })
+    );
+}
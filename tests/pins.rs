@@ -0,0 +1,87 @@
+use cpu6502::cpu::{Cpu, Flag};
+
+fn cpu_with_program(program: &[u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.pc = 0x0200;
+    for (i, &byte) in program.iter().enumerate() {
+        cpu.memory[0x0200 + i] = byte;
+    }
+    cpu
+}
+
+#[test]
+fn rdy_low_stalls_opcode_fetch() {
+    // LDA #$42
+    let mut cpu = cpu_with_program(&[0xA9, 0x42]);
+    cpu.set_rdy(false);
+
+    for _ in 0..10 {
+        cpu.clock();
+    }
+    assert_eq!(cpu.pc, 0x0200);
+    assert_eq!(cpu.a, 0x00);
+
+    cpu.set_rdy(true);
+    cpu.clock();
+    assert_eq!(cpu.pc, 0x0202);
+    assert_eq!(cpu.a, 0x42);
+}
+
+#[test]
+fn rdy_low_does_not_stall_write_cycles() {
+    // STA $10 (3 cycles, last one is the write)
+    let mut cpu = cpu_with_program(&[0x85, 0x10, 0xEA]);
+    cpu.clock();
+    cpu.clock();
+    assert_eq!(cpu.cycles, 1);
+
+    cpu.set_rdy(false);
+    assert!(cpu.is_write_cycle());
+    cpu.clock();
+    assert_eq!(cpu.cycles, 0);
+
+    // The following opcode fetch is a read and stalls
+    cpu.clock();
+    assert_eq!(cpu.pc, 0x0202);
+}
+
+#[test]
+fn step_returns_while_rdy_stalls_an_instruction() {
+    // LDA $10 (3 cycles)
+    let mut cpu = cpu_with_program(&[0xA5, 0x10, 0xEA]);
+    cpu.memory[0x10] = 0x42;
+    cpu.clock();
+    cpu.set_rdy(false);
+
+    assert_eq!(cpu.step(), 1);
+    assert_eq!(cpu.cycles, 2);
+    assert_eq!(cpu.run(10), Ok(10));
+    assert_eq!(cpu.cycles, 2);
+
+    cpu.set_rdy(true);
+    assert_eq!(cpu.step(), 2);
+    assert_eq!((cpu.pc, cpu.a), (0x0202, 0x42));
+}
+
+#[test]
+fn so_falling_edge_sets_overflow() {
+    // CLV; NOP
+    let mut cpu = cpu_with_program(&[0xB8, 0xEA]);
+    cpu.clock();
+    cpu.clock();
+    assert!(!cpu.get_flag(Flag::Overflow));
+
+    cpu.set_so(false);
+    cpu.clock();
+    assert!(cpu.get_flag(Flag::Overflow));
+
+    // Holding SO low does not set V again
+    cpu.set_flag(Flag::Overflow, false);
+    cpu.clock();
+    assert!(!cpu.get_flag(Flag::Overflow));
+
+    // Rising edge has no effect
+    cpu.set_so(true);
+    cpu.clock();
+    assert!(!cpu.get_flag(Flag::Overflow));
+}
@@ -0,0 +1,124 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::Cpu,
+    profiler::{CallStats, Profiler},
+};
+
+const PROGRAM: &str = "
+start:  ldx #3
+loop:   jsr outer
+        dex
+        bne loop
+done:   jmp done
+outer:  jsr inner
+        nop
+        rts
+inner:  nop
+        rts
+nmi:    rti
+";
+
+fn profiled() -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.memory.load(0x0400, &assemble(PROGRAM, 0x0400).unwrap());
+    cpu.memory.load(0xFFFA, &[0x12, 0x04]);
+    cpu.pc = 0x0400;
+    cpu.profiler = Some(Box::new(Profiler::new()));
+
+    // LDX, three rounds of eight, two laps of JMP done
+    for _ in 0..1 + 3 * 8 + 2 {
+        cpu.step();
+    }
+    cpu.set_nmi(true);
+    cpu.step();
+    cpu.step();
+    cpu
+}
+
+#[test]
+fn counts_executions_and_coverage() {
+    let cpu = profiled();
+    let profiler = cpu.profiler.as_ref().unwrap();
+
+    // 34 in the loop, 66 in the calls, 6 in JMP, 13 for the NMI
+    assert_eq!(profiler.cycles(), 119);
+    assert_eq!(profiler.interrupts(), 1);
+    assert_eq!(profiler.executions(0x0402), 3);
+    // Two taken branches and the one that falls through
+    assert_eq!(profiler.cycles_at(0x0406), 8);
+    assert_eq!(profiler.opcode(0x20), (6, 36));
+    assert_eq!(profiler.opcode(0xEA), (6, 12));
+
+    assert!(profiler.covered(0x0403) && profiler.covered(0x0404));
+    assert!(profiler.covered(0x0412));
+    assert!(!profiler.covered(0x0413));
+
+    let mut csv = Vec::new();
+    profiler.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with("address,opcode,mnemonic,executions,cycles\n0400,A2,ldx,1,2\n"));
+    assert!(csv.contains("\n0402,20,jsr,3,18\n"));
+
+    let mut csv = Vec::new();
+    profiler.write_opcode_csv(&mut csv).unwrap();
+    assert!(String::from_utf8(csv)
+        .unwrap()
+        .contains("\n60,rts,Implied,6,36\n"));
+}
+
+#[test]
+fn builds_the_call_graph() {
+    let cpu = profiled();
+    let profiler = cpu.profiler.as_ref().unwrap();
+
+    let stats = |calls, inclusive, exclusive| CallStats {
+        calls,
+        inclusive,
+        exclusive,
+    };
+    assert_eq!(profiler.calls()[&0x040B], stats(3, 66, 42));
+    assert_eq!(profiler.calls()[&0x0410], stats(3, 24, 24));
+    assert_eq!(profiler.calls()[&0x0412], stats(1, 13, 13));
+
+    let mut folded = Vec::new();
+    profiler.write_folded(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "root 40\nroot;$040B 42\nroot;$040B;$0410 24\nroot;$0412 13\n"
+    );
+
+    let mut csv = Vec::new();
+    profiler.write_calls_csv(&mut csv).unwrap();
+    assert_eq!(
+        String::from_utf8(csv).unwrap(),
+        "address,calls,inclusive,exclusive\n040B,3,66,42\n0410,3,24,24\n0412,1,13,13\n"
+    );
+}
+
+#[test]
+fn calls_that_drop_their_return_address_end() {
+    let program = "
+start:  jsr sub
+        jmp start
+sub:    pla
+        pla
+        jmp start
+    ";
+    let mut cpu = Cpu::new();
+    cpu.memory.load(0x0400, &assemble(program, 0x0400).unwrap());
+    cpu.pc = 0x0400;
+    cpu.profiler = Some(Box::new(Profiler::new()));
+    for _ in 0..4000 {
+        cpu.step();
+    }
+
+    let profiler = cpu.profiler.as_ref().unwrap();
+    // Each call ends with its second PLA
+    assert_eq!(profiler.calls()[&0x0406].calls, 1000);
+    let mut folded = Vec::new();
+    profiler.write_folded(&mut folded).unwrap();
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "root 9000\nroot;$0406 8000\n"
+    );
+}
@@ -0,0 +1,194 @@
+use cpu6502::{
+    assembler::assemble,
+    banking::{Banks, BASE_RAM},
+    bus::Mapping,
+    cpu::{Cpu, Engine},
+    rewind::Rewind,
+};
+
+const PROGRAM: &str = "
+start:  ldx #0
+copy:   lda $0300,x
+        asl
+        sta $0380,x
+        pha
+        pla
+        inx
+        cpx #$10
+        bne copy
+        jsr sub
+        jmp start
+sub:    inc $0403       ; copy from one byte further next time
+        rts
+";
+
+fn machine(engine: Engine, limit: usize) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.engine = engine;
+    cpu.memory.load(0x0400, &assemble(PROGRAM, 0x0400).unwrap());
+    for i in 0..0x20 {
+        cpu.memory[0x0300 + i] = i as u8 * 7;
+    }
+    cpu.pc = 0x0400;
+    cpu.rewind = Some(Box::new(Rewind::new(limit)));
+    cpu
+}
+
+fn state(cpu: &Cpu) -> (u16, u8, u8, u8, u8, u8, Vec<u8>) {
+    let memory = cpu.memory.banks.bank(0).to_vec();
+    (cpu.pc, cpu.a, cpu.x, cpu.y, cpu.sp, cpu.status, memory)
+}
+
+#[test]
+fn steps_back_through_every_instruction() {
+    for engine in [Engine::Table, Engine::Cached] {
+        let mut cpu = machine(engine, 1 << 20);
+        let mut states = vec![state(&cpu)];
+        for _ in 0..400 {
+            cpu.step();
+            states.push(state(&cpu));
+        }
+        assert_eq!(cpu.rewind.as_ref().unwrap().len(), 400);
+
+        for back in (0..400).rev() {
+            assert_eq!(cpu.step_back(1), 1);
+            assert!(state(&cpu) == states[back], "{engine:?} back to {back}");
+        }
+        assert_eq!(cpu.step_back(1), 0);
+
+        // Running forward again does the same thing, patched code included
+        for _ in 0..400 {
+            cpu.step();
+        }
+        assert!(state(&cpu) == states[400], "{engine:?}");
+    }
+}
+
+#[test]
+fn runs_back_to_the_last_write() {
+    let mut cpu = machine(Engine::Table, 1 << 20);
+    for _ in 0..300 {
+        cpu.step();
+    }
+    let written = cpu.memory[0x0381];
+
+    let undone = cpu.rewind_to_write(0x0381).unwrap();
+    assert!(undone > 1);
+    assert_eq!(cpu.pc, 0x0406);
+    assert_eq!(cpu.x, 0x01);
+    cpu.step();
+    assert_eq!(cpu.memory[0x0381], written);
+
+    assert_eq!(cpu.rewind_to_write(0x0200), None);
+    assert_eq!(cpu.pc, 0x0409);
+}
+
+#[test]
+fn finds_writes_through_mirrors() {
+    // Past the 13 address lines of a 6507
+    let mut cpu = machine(Engine::Table, 1 << 20);
+    cpu.memory.set_address_lines(13);
+    for _ in 0..300 {
+        cpu.step();
+    }
+    assert!(cpu.rewind_to_write(0x2381).is_some());
+    assert_eq!((cpu.pc, cpu.x), (0x0406, 0x01));
+
+    // RAM mapped again further up, as on the NES
+    let mut cpu = machine(Engine::Table, 1 << 20);
+    cpu.memory.banks.map(0x0B, 1, BASE_RAM, 0x03);
+    for _ in 0..300 {
+        cpu.step();
+    }
+    assert!(cpu.rewind_to_write(0x0B81).is_some());
+    assert_eq!((cpu.pc, cpu.x), (0x0406, 0x01));
+}
+
+#[test]
+fn cached_code_follows_rewound_patches() {
+    let run = |engine| {
+        let mut cpu = machine(engine, 1 << 20);
+        for _ in 0..410 {
+            cpu.step();
+        }
+        // Back to before the last patch, then skip it
+        cpu.rewind_to_write(0x0403).unwrap();
+        cpu.pc = 0x0400;
+        for _ in 0..20 {
+            cpu.step();
+        }
+        state(&cpu)
+    };
+    assert!(run(Engine::Cached) == run(Engine::Table));
+}
+
+#[test]
+fn keeps_within_the_limit() {
+    let mut cpu = machine(Engine::Table, 4096);
+    for _ in 0..1000 {
+        cpu.step();
+    }
+    let rewind = cpu.rewind.as_ref().unwrap();
+    let kept = rewind.len();
+    assert!(kept > 10 && kept < 1000);
+    assert!(rewind.used() <= 4096);
+    assert_eq!(cpu.step_back(5000), kept);
+    assert!(cpu.rewind.as_ref().unwrap().is_empty());
+}
+
+#[test]
+fn restores_banked_ram_after_a_switch() {
+    let mut cpu = Cpu::new();
+    let ram = cpu.memory.banks.add_ram(0x100);
+    cpu.memory.banks.map(0x80, 1, ram, 0);
+    cpu.memory
+        .load(0x0400, &assemble("lda #$AA\nsta $8010", 0x0400).unwrap());
+    cpu.pc = 0x0400;
+    cpu.rewind = Some(Box::new(Rewind::new(1 << 16)));
+
+    cpu.step();
+    cpu.step();
+    cpu.memory.banks.unmap(0x80, 1);
+    assert_eq!(cpu.memory.banks.bank(ram)[0x10], 0xAA);
+
+    cpu.step_back(1);
+    assert_eq!(cpu.memory.banks.bank(ram)[0x10], 0x00);
+    assert_eq!(cpu.memory[0x8010], 0x00);
+}
+
+#[test]
+fn restores_the_bank_layout() {
+    for engine in [Engine::Table, Engine::Cached] {
+        let mut cpu = Cpu::new();
+        cpu.engine = engine;
+        let ram = cpu.memory.banks.add_ram(0x100);
+        *cpu.memory.banks.byte_mut(ram, 0x10) = 0x55;
+        cpu.memory.map_bank_switch(
+            Mapping::new(0xC000, 0xC000),
+            move |banks: &mut Banks, _: u16, value: u8| match value {
+                0 => banks.unmap(0x80, 1),
+                _ => banks.map(0x80, 1, ram, 0),
+            },
+        );
+        let program = "lda #1\nsta $C000\nlda $8010\nldx #0\nstx $C000";
+        cpu.memory.load(0x0400, &assemble(program, 0x0400).unwrap());
+        cpu.pc = 0x0400;
+        cpu.rewind = Some(Box::new(Rewind::new(1 << 16)));
+
+        for _ in 0..5 {
+            cpu.step();
+        }
+        assert_eq!((cpu.a, cpu.memory.peek(0x8010)), (0x55, 0x00), "{engine:?}");
+
+        // Back to before the switch back to base RAM...
+        cpu.step_back(2);
+        assert_eq!(cpu.memory.peek(0x8010), 0x55, "{engine:?}");
+        // ...and before the switch to the bank
+        assert_eq!(cpu.step_back(3), 3);
+        assert_eq!(cpu.memory.peek(0x8010), 0x00, "{engine:?}");
+        for _ in 0..3 {
+            cpu.step();
+        }
+        assert_eq!(cpu.a, 0x55, "{engine:?}");
+    }
+}
@@ -0,0 +1,99 @@
+use cpu6502::{
+    bus::{Device, Mapping},
+    cpu::Cpu,
+    devices::riot::{self, Riot, RiotRam},
+};
+use std::{cell::RefCell, rc::Rc};
+
+fn tick(riot: &mut Riot, cycles: usize) {
+    for _ in 0..cycles {
+        riot.tick();
+    }
+}
+
+#[test]
+fn timer_counts_with_prescaler() {
+    for (reg, divider) in [(0x14, 1), (0x15, 8), (0x16, 64), (0x17, 1024)] {
+        let mut riot = Riot::new();
+        riot.write(reg, 3);
+
+        // Decremented on the next cycle, then every `divider` cycles
+        tick(&mut riot, 1);
+        assert_eq!(riot.read(0x04), 2);
+        tick(&mut riot, 2 * divider);
+        assert_eq!(riot.read(0x04), 0);
+        assert_eq!(riot.read(0x05) & riot::FLAG_TIMER, 0);
+
+        tick(&mut riot, divider);
+        assert_eq!(riot.read(0x05) & riot::FLAG_TIMER, riot::FLAG_TIMER);
+        assert_eq!(riot.timer.value, 0xFF);
+
+        // Past zero it counts every cycle
+        tick(&mut riot, 5);
+        assert_eq!(riot.timer.value, 0xFA);
+    }
+}
+
+#[test]
+fn reading_timer_clears_flag_and_restores_divider() {
+    let mut riot = Riot::new();
+    riot.write(0x1D, 0); // divide by 8, interrupt enabled
+    tick(&mut riot, 1);
+    assert!(riot.irq());
+
+    assert_eq!(riot.read(0x0C), 0xFF);
+    assert!(!riot.irq());
+    tick(&mut riot, 7);
+    assert_eq!(riot.timer.value, 0xFF);
+    tick(&mut riot, 1);
+    assert_eq!(riot.timer.value, 0xFE);
+}
+
+#[test]
+fn pa7_edge_detect() {
+    let mut riot = Riot::new();
+    // Positive edge, interrupt enabled
+    riot.write(0x07, 0);
+    riot.set_port_a(0x00);
+    assert!(!riot.irq());
+    riot.set_port_a(0x80);
+    assert!(riot.irq());
+
+    assert_eq!(riot.read(0x05), riot::FLAG_PA7);
+    assert!(!riot.irq());
+}
+
+#[test]
+fn ports_and_ram_through_the_bus() {
+    let mut cpu = Cpu::new();
+    let riot = Rc::new(RefCell::new(Riot::new()));
+    // Atari 2600 layout
+    cpu.memory.map(Mapping::new(0x0080, 0x00FF), RiotRam(riot.clone()));
+    cpu.memory.map(
+        Mapping {
+            mirror_mask: 0x001F,
+            ..Mapping::new(0x0280, 0x029F)
+        },
+        riot.clone(),
+    );
+    riot.borrow_mut().set_port_b(0x0B);
+
+    #[rustfmt::skip]
+    let program = [
+        0xAD, 0x82, 0x02, // LDA $0282  SWCHB
+        0x85, 0x80,       // STA $80
+        0xA9, 0x0A,       // LDA #10
+        0x8D, 0x95, 0x02, // STA $0295  TIM8T
+    ];
+    cpu.memory.load(0xF000, &program);
+    cpu.pc = 0xF000;
+    for _ in 0..13 {
+        cpu.clock();
+    }
+
+    assert_eq!(riot.borrow().ram[0], 0x0B);
+    // The RAM lives in the RIOT, not in base RAM
+    assert_eq!(cpu.memory[0x0080], 0x00);
+    assert_eq!(riot.borrow().timer.divider, 8);
+    assert_eq!(riot.borrow().timer.value, 9);
+}
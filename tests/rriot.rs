@@ -0,0 +1,46 @@
+use cpu6502::{
+    bus::Device,
+    devices::rriot::{self, Rriot},
+};
+
+fn tick(rriot: &mut Rriot, cycles: usize) {
+    for _ in 0..cycles {
+        rriot.tick();
+    }
+}
+
+#[test]
+fn timer_is_written_without_a4() {
+    let mut rriot = Rriot::new();
+    rriot.write(0x05, 2); // divide by 8
+
+    tick(&mut rriot, 1 + 8);
+    assert_eq!(rriot.read(0x06), 0);
+    assert_eq!(rriot.read(0x07) & rriot::FLAG_TIMER, 0);
+
+    tick(&mut rriot, 8);
+    assert_eq!(rriot.read(0x07) & rriot::FLAG_TIMER, rriot::FLAG_TIMER);
+    assert!(!rriot.interrupt());
+}
+
+#[test]
+fn timer_irq_follows_a3() {
+    let mut rriot = Rriot::new();
+    rriot.write(0x0C, 1); // divide by 1, interrupt enabled
+
+    tick(&mut rriot, 2);
+    assert!(rriot.interrupt());
+
+    // Reading the timer acknowledges it
+    rriot.read(0x0E);
+    assert!(!rriot.interrupt());
+}
+
+#[test]
+fn ports_mix_outputs_and_pins() {
+    let mut rriot = Rriot::new();
+    rriot.write(0x01, 0x0F);
+    rriot.write(0x00, 0x05);
+    rriot.set_port_a(0xA0);
+    assert_eq!(rriot.read(0x00), 0xA5);
+}
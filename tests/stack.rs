@@ -0,0 +1,145 @@
+use cpu6502::{
+    assembler::assemble,
+    cpu::Cpu,
+    stack::{StackChecker, StackIssue},
+};
+
+const PROGRAM: &str = "
+start:  ldx #$FF
+        txs
+        jsr good
+        jsr skip
+        .byte $EA       ; skip returns past this
+        lda #$04        ; RTS as a jump to $0411
+        pha
+        lda #$10
+        pha
+        rts
+jump:   jsr viarti
+good:   pha
+        pla
+        rts
+skip:   tsx
+        inc $0101,x
+        rts
+viarti: php
+        rti
+nmi:    rti
+";
+
+fn checked(program: &str) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.memory.load(0x0400, &assemble(program, 0x0400).unwrap());
+    cpu.memory.load(0xFFFA, &[0x1E, 0x04]);
+    cpu.pc = 0x0400;
+    cpu.stack_checker = Some(Box::new(StackChecker::new()));
+    cpu
+}
+
+#[test]
+fn pairs_calls_with_returns() {
+    let mut cpu = checked(PROGRAM);
+    for _ in 0..18 {
+        cpu.step();
+    }
+    assert_eq!(cpu.pc, 0x0413);
+    cpu.set_nmi(true);
+    cpu.step();
+    cpu.step();
+
+    let checker = cpu.stack_checker.as_ref().unwrap();
+    assert_eq!(
+        checker.issues(),
+        [
+            StackIssue::WrongReturn {
+                pc: 0x041B,
+                expected: 0x0409,
+                target: 0x040A
+            },
+            StackIssue::Unmatched {
+                pc: 0x0410,
+                target: 0x0411
+            },
+            StackIssue::Mismatched {
+                pc: 0x041D,
+                call: 0x0411
+            },
+        ]
+    );
+    assert_eq!(checker.max_depth(), 3);
+    assert_eq!(checker.depth(), 0);
+    assert_eq!(
+        checker.issues()[0].to_string(),
+        "$041B: return to $040A instead of $0409"
+    );
+}
+
+#[test]
+fn warns_when_sp_wraps() {
+    let mut cpu = checked(
+        "
+        ldx #$00
+        txs
+        pha
+        ldx #$FF
+        txs
+        pla
+        ",
+    );
+    for _ in 0..6 {
+        cpu.step();
+    }
+
+    let checker = cpu.stack_checker.as_ref().unwrap();
+    assert_eq!(
+        checker.issues(),
+        [
+            StackIssue::Overflow { pc: 0x0403 },
+            StackIssue::Underflow { pc: 0x0407 }
+        ]
+    );
+    // TXS down to $00 from $FD, the push that wrapped doesn't count
+    assert_eq!(checker.max_depth(), 0xFD);
+}
+
+#[test]
+fn depth_ignores_the_stack_while_sp_is_wrapped() {
+    let mut cpu = checked("pha\npha\npha\npla\npla\npla\npha");
+    cpu.sp = 0x01;
+    for _ in 0..7 {
+        cpu.step();
+    }
+
+    let checker = cpu.stack_checker.as_ref().unwrap();
+    assert_eq!(
+        checker.issues(),
+        [
+            StackIssue::Overflow { pc: 0x0401 },
+            StackIssue::Underflow { pc: 0x0404 }
+        ]
+    );
+    // Still counted from $01 once SP is back on the page
+    assert_eq!(cpu.sp, 0x00);
+    assert_eq!(checker.max_depth(), 1);
+}
+
+#[test]
+fn measures_depth_from_where_the_stack_started() {
+    let mut cpu = checked("jsr sub\nsub: pha\npla\nrts");
+    cpu.memory.load(0xFFFC, &[0x00, 0x04]);
+    cpu.sp = 0x00;
+    cpu.reset();
+    assert_eq!(cpu.sp, 0xFD);
+    for _ in 0..5 {
+        cpu.step();
+    }
+    // From $FD, not $FF: the return address and A
+    assert_eq!(cpu.stack_checker.as_ref().unwrap().max_depth(), 3);
+
+    let mut cpu = checked("pha\npha\npla");
+    cpu.sp = 0xE0;
+    for _ in 0..3 {
+        cpu.step();
+    }
+    assert_eq!(cpu.stack_checker.as_ref().unwrap().max_depth(), 2);
+}
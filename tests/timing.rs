@@ -0,0 +1,115 @@
+use cpu6502::cpu::{Cpu, Flag};
+
+fn cpu_with_program(program: &[u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.pc = 0x0200;
+    for (i, &byte) in program.iter().enumerate() {
+        cpu.memory[0x0200 + i] = byte;
+    }
+    cpu
+}
+
+#[test]
+fn taken_branches_cost_extra_cycles() {
+    // BNE +0 (taken), BEQ +0 (not taken), BNE to the next page
+    let mut cpu = cpu_with_program(&[0xD0, 0x00, 0xF0, 0x00]);
+    assert_eq!(cpu.step(), 3);
+    assert_eq!(cpu.step(), 2);
+
+    cpu.pc = 0x02F0;
+    cpu.memory[0x02F0] = 0xD0;
+    cpu.memory[0x02F1] = 0x20;
+    assert_eq!(cpu.step(), 4);
+    assert_eq!(cpu.pc, 0x0312);
+}
+
+#[test]
+fn page_crossing_only_costs_reads() {
+    // LDA $02FF,X; STA $02FF,X; INC $02FF,X with X = 1
+    let mut cpu = cpu_with_program(&[0xBD, 0xFF, 0x02, 0x9D, 0xFF, 0x02, 0xFE, 0xFF, 0x02]);
+    cpu.x = 1;
+    assert_eq!(cpu.step(), 5);
+    assert_eq!(cpu.step(), 5);
+    assert_eq!(cpu.step(), 7);
+}
+
+#[test]
+fn cli_delays_a_pending_irq_by_one_instruction() {
+    // CLI; NOP; NOP
+    let mut cpu = cpu_with_program(&[0x58, 0xEA, 0xEA]);
+    cpu.set_flag(Flag::InterruptDisable, true);
+    cpu.memory[0xFFFE] = 0x00;
+    cpu.memory[0xFFFF] = 0x03;
+    cpu.set_irq(true);
+
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.pc, 0x0202);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x0300);
+}
+
+#[test]
+fn sei_still_lets_a_pending_irq_through() {
+    // CLI; SEI; NOP
+    let mut cpu = cpu_with_program(&[0x58, 0x78, 0xEA]);
+    cpu.memory[0xFFFE] = 0x00;
+    cpu.memory[0xFFFF] = 0x03;
+
+    cpu.step();
+    cpu.set_irq(true);
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.pc, 0x0300);
+}
+
+#[test]
+fn corrected_base_cycle_counts() {
+    // ASL $0300,X; AND $10
+    let mut cpu = cpu_with_program(&[0x1E, 0x00, 0x03, 0x25, 0x10]);
+    assert_eq!(cpu.step(), 7);
+    assert_eq!(cpu.step(), 3);
+}
+
+#[test]
+fn indexed_writes_cost_the_same_on_every_page() {
+    // STA (zp),Y; STA abs,Y; STA abs,X and the abs,X read-modify-writes
+    let writes = [0x91, 0x99, 0x9D, 0x1E, 0x3E, 0x5E, 0x7E, 0xDE, 0xFE];
+    // LDA (zp),Y; LDA abs,Y; LDA abs,X
+    let reads = [0xB1, 0xB9, 0xBD];
+    for opcode in writes.into_iter().chain(reads) {
+        let cycles = |index| {
+            // Pointer at $10 to $02FF, absolute operand $02FF
+            let mut cpu = cpu_with_program(&[opcode, 0xFF, 0x02]);
+            cpu.memory[0x10] = 0xFF;
+            cpu.memory[0x11] = 0x02;
+            if opcode == 0x91 || opcode == 0xB1 {
+                cpu.memory[0x0201] = 0x10;
+            }
+            cpu.x = index;
+            cpu.y = index;
+            cpu.step()
+        };
+        let penalty = if reads.contains(&opcode) { 1 } else { 0 };
+        assert_eq!(cycles(1), cycles(0) + penalty, "${opcode:02X}");
+    }
+}
+
+#[test]
+fn plp_delays_a_pending_irq_by_one_instruction() {
+    // PLP (pulling I clear); NOP; NOP
+    let mut cpu = cpu_with_program(&[0x28, 0xEA, 0xEA]);
+    cpu.set_flag(Flag::InterruptDisable, true);
+    cpu.sp = 0xFC;
+    cpu.memory[0x01FD] = 0x20;
+    cpu.memory[0xFFFE] = 0x00;
+    cpu.memory[0xFFFF] = 0x03;
+    cpu.set_irq(true);
+
+    cpu.step();
+    assert!(!cpu.get_flag(Flag::InterruptDisable));
+    cpu.step();
+    assert_eq!(cpu.pc, 0x0202);
+    cpu.step();
+    assert_eq!(cpu.pc, 0x0300);
+}
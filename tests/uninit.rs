@@ -0,0 +1,158 @@
+use cpu6502::{
+    assembler::assemble,
+    bus::Mapping,
+    cpu::{Cpu, CpuError},
+    uninit::{PowerOn, UninitChecker, UninitMode, UninitRead},
+};
+
+const PROGRAM: &str = "
+start:  lda $0300       ; set up from outside
+        sta $11
+        lda $11
+        lda $10         ; never written
+        inc $12         ; reads before it writes
+        lda $12
+        pha
+        pla
+        lda $0500       ; ROM
+done:   jmp done
+";
+
+fn checked(mode: UninitMode) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.memory.uninit = Some(Box::new(UninitChecker::new(mode)));
+    cpu.memory.load(0x0400, &assemble(PROGRAM, 0x0400).unwrap());
+    cpu.memory[0x0300] = 0x42;
+    let rom = cpu.memory.banks.add_rom(&[0x55; 0x100]);
+    cpu.memory.banks.map(0x05, 1, rom, 0);
+    cpu.pc = 0x0400;
+    cpu
+}
+
+#[test]
+fn warns_about_reads_of_uninitialized_ram() {
+    let mut cpu = checked(UninitMode::Warn);
+    for _ in 0..12 {
+        cpu.try_step().unwrap();
+    }
+
+    let uninit = cpu.memory.uninit.as_ref().unwrap();
+    assert_eq!(
+        uninit.reads(),
+        [
+            UninitRead {
+                pc: 0x0407,
+                addr: 0x0010
+            },
+            UninitRead {
+                pc: 0x0409,
+                addr: 0x0012
+            },
+        ]
+    );
+    assert!(uninit.initialized(0x0012) && uninit.initialized(0x01FD));
+    assert!(!uninit.initialized(0x0010));
+}
+
+#[test]
+fn stops_after_the_offending_instruction() {
+    let mut cpu = checked(UninitMode::Stop);
+    let err = CpuError::UninitializedRead {
+        pc: 0x0407,
+        addr: 0x0010,
+    };
+    assert_eq!(cpu.run(1000), Err(err));
+    assert_eq!(cpu.pc, 0x0409);
+    assert_eq!(cpu.try_step(), Err(err));
+    assert_eq!(err.to_string(), "uninitialized read of $0010 at $0407");
+
+    // Running code nothing put there counts too
+    let mut cpu = checked(UninitMode::Stop);
+    cpu.memory.load(0x0400, &[0x4C, 0x00, 0x20]);
+    assert_eq!(
+        cpu.run(1000),
+        Err(CpuError::UninitializedRead {
+            pc: 0x2000,
+            addr: 0x2000
+        })
+    );
+}
+
+#[test]
+fn keeps_the_first_read_of_each_address() {
+    let mut cpu = Cpu::new();
+    cpu.memory.uninit = Some(Box::new(UninitChecker::new(UninitMode::Warn)));
+    let program = "loop: lda $10\nlda $11\njmp loop";
+    cpu.memory.load(0x0400, &assemble(program, 0x0400).unwrap());
+    cpu.pc = 0x0400;
+    for _ in 0..300 {
+        cpu.step();
+    }
+    assert_eq!(
+        cpu.memory.uninit.as_ref().unwrap().reads(),
+        [
+            UninitRead {
+                pc: 0x0400,
+                addr: 0x0010
+            },
+            UninitRead {
+                pc: 0x0402,
+                addr: 0x0011
+            },
+        ]
+    );
+}
+
+#[test]
+fn devices_only_hide_their_own_addresses() {
+    let mut cpu = Cpu::new();
+    cpu.memory.uninit = Some(Box::new(UninitChecker::new(UninitMode::Warn)));
+    cpu.memory
+        .map_handler(Mapping::new(0x0000, 0x0001), |_| 0x37, |_, _| {});
+    let program = "lda $00\nsta $01\nlda $02";
+    cpu.memory.load(0x0400, &assemble(program, 0x0400).unwrap());
+    cpu.pc = 0x0400;
+    for _ in 0..3 {
+        cpu.step();
+    }
+
+    let uninit = cpu.memory.uninit.as_ref().unwrap();
+    assert_eq!(
+        uninit.reads(),
+        [UninitRead {
+            pc: 0x0404,
+            addr: 0x0002
+        }]
+    );
+    // The write went to the device, not the RAM underneath
+    assert!(!uninit.initialized(0x0001));
+}
+
+#[test]
+fn fills_ram_at_power_on() {
+    let mut cpu = checked(UninitMode::Warn);
+    let ram = cpu.memory.banks.add_ram(0x100);
+
+    cpu.memory.power_on(PowerOn::Ones);
+    assert_eq!(cpu.memory[0x0400], 0xFF);
+    assert_eq!(cpu.memory.banks.bank(ram)[0x80], 0xFF);
+    assert_eq!(cpu.memory.read(0x0500), 0x55);
+    assert!(!cpu.memory.uninit.as_ref().unwrap().initialized(0x0400));
+
+    cpu.memory.power_on(PowerOn::Alternating);
+    assert_eq!((cpu.memory[0x1000], cpu.memory[0x1001]), (0x00, 0xFF));
+
+    let random = |seed| {
+        let mut cpu = Cpu::new();
+        cpu.memory.power_on(PowerOn::Random { seed });
+        cpu.memory.banks.bank(0)[..0x100].to_vec()
+    };
+    assert_eq!(random(1), random(1));
+    assert_ne!(random(1), random(2));
+    assert_ne!(random(2), random(3));
+    assert!(random(0).iter().any(|&byte| byte != 0));
+    assert!(random(1).iter().any(|&byte| byte != random(1)[0]));
+
+    cpu.memory.power_on(PowerOn::Zeros);
+    assert!(cpu.memory.banks.bank(0).iter().all(|&byte| byte == 0));
+}
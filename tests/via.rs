@@ -0,0 +1,159 @@
+use cpu6502::{
+    bus::{Device, Mapping},
+    cpu::Cpu,
+    devices::via::{self, Via},
+};
+use std::{cell::RefCell, rc::Rc};
+
+fn tick(via: &mut Via, cycles: usize) {
+    for _ in 0..cycles {
+        via.tick();
+    }
+}
+
+#[test]
+fn timer1_one_shot_fires_once() {
+    let mut via = Via::new();
+    via.write(via::IER, 0x80 | 0x40);
+    via.write(via::T1C_L, 10);
+    via.write(via::T1C_H, 0);
+
+    tick(&mut via, 10);
+    assert!(!via.irq());
+    tick(&mut via, 1);
+    assert!(via.irq());
+    assert_eq!(via.read(via::IFR), 0xC0);
+
+    // Reading T1C-L acknowledges
+    via.read(via::T1C_L);
+    assert!(!via.irq());
+
+    tick(&mut via, 0x20000);
+    assert!(!via.irq());
+}
+
+#[test]
+fn timer1_free_run_reloads_and_toggles_pb7() {
+    let mut via = Via::new();
+    via.write(via::ACR, 0xC0);
+    via.write(via::T1C_L, 4);
+    via.write(via::T1C_H, 0);
+    let pb7 = via.port_b() & 0x80;
+
+    // Period is latch + 2 cycles
+    for _ in 0..3 {
+        tick(&mut via, 5);
+        assert_eq!(via.ifr & 0x40, 0x40);
+        via.write(via::IFR, 0x40);
+        tick(&mut via, 1);
+        assert_eq!(via.ifr & 0x40, 0);
+    }
+    assert_ne!(via.port_b() & 0x80, pb7);
+}
+
+#[test]
+fn timer2_counts_pb6_pulses() {
+    let mut via = Via::new();
+    via.write(via::ACR, 0x20);
+    via.write(via::IER, 0x80 | 0x20);
+    via.write(via::T2C_L, 2);
+    via.write(via::T2C_H, 0);
+
+    tick(&mut via, 100);
+    assert!(!via.irq());
+
+    for _ in 0..3 {
+        via.set_port_b(0xBF);
+        via.set_port_b(0xFF);
+    }
+    assert!(via.irq());
+}
+
+#[test]
+fn ports_respect_data_direction() {
+    let mut via = Via::new();
+    via.write(via::DDRA, 0x0F);
+    via.write(via::ORA, 0xA5);
+    via.set_port_a(0x30);
+
+    assert_eq!(via.port_a(), 0x35);
+    assert_eq!(via.read(via::ORA_NO_HANDSHAKE), 0x35);
+}
+
+#[test]
+fn ca1_edge_latches_and_interrupts() {
+    let mut via = Via::new();
+    // Positive CA1 edge, CA2 handshake output, latching on port A
+    via.write(via::PCR, 0x09);
+    via.write(via::ACR, 0x01);
+    via.write(via::IER, 0x80 | 0x02);
+    via.set_port_a(0x42);
+
+    via.set_ca1(false);
+    via.set_ca1(true);
+    assert!(via.irq());
+    assert!(via.ca2());
+
+    via.set_port_a(0x00);
+    assert_eq!(via.read(via::ORA), 0x42);
+    assert!(!via.irq());
+    assert!(!via.ca2());
+}
+
+#[test]
+fn shift_register_shifts_out_under_phase2() {
+    let mut via = Via::new();
+    via.write(via::ACR, 0x18);
+    via.write(via::SR, 0b1010_0000);
+
+    let mut bits = Vec::new();
+    for _ in 0..8 {
+        via.tick();
+        bits.push(via.cb2() as u8);
+    }
+    assert_eq!(bits, [1, 0, 1, 0, 0, 0, 0, 0]);
+    assert_eq!(via.ifr & 0x04, 0x04);
+}
+
+#[test]
+fn timer_interrupt_reaches_the_cpu() {
+    let mut cpu = Cpu::new();
+    let via = Rc::new(RefCell::new(Via::new()));
+    cpu.memory.map(
+        Mapping {
+            mirror_mask: 0x000F,
+            ..Mapping::new(0x6000, 0x600F)
+        },
+        via.clone(),
+    );
+
+    #[rustfmt::skip]
+    let program = [
+        0xA9, 0xC0,       // LDA #$C0   enable T1 interrupt
+        0x8D, 0x0E, 0x60, // STA $600E
+        0xA9, 0x20,       // LDA #$20
+        0x8D, 0x04, 0x60, // STA $6004
+        0xA9, 0x00,       // LDA #$00
+        0x8D, 0x05, 0x60, // STA $6005  start T1
+        0x58,             // CLI
+        0x4C, 0x10, 0x02, // JMP *
+    ];
+    #[rustfmt::skip]
+    let handler = [
+        0xAD, 0x04, 0x60, // LDA $6004  acknowledge
+        0xE6, 0x00,       // INC $00
+        0x40,             // RTI
+    ];
+    cpu.memory.load(0x0200, &program);
+    cpu.memory.load(0x0300, &handler);
+    cpu.memory[0xFFFE] = 0x00;
+    cpu.memory[0xFFFF] = 0x03;
+    cpu.pc = 0x0200;
+
+    for _ in 0..200 {
+        cpu.clock();
+    }
+
+    assert_eq!(cpu.memory[0x0000], 1);
+    assert!(!via.borrow().irq());
+}